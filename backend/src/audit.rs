@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+
+use actix_web::{web::{self, Data}, HttpResponse};
+use anyhow::bail;
+use common::{audit::{AuditAction, AuditRecord, FieldChange, GetPartHistoryProps}, error::ApiResult, types::PartId};
+use surrealdb::sql::{Value, Thing, json};
+
+use crate::{DB, auth::{Authenticated, can}};
+
+/// Creates audit record from variables named with the suffix, e.g. `$part0` for suffix `0`
+fn audit_sql(suffix: &str) -> String {
+    format!("CREATE audit SET part = $part{suffix}, user = $user{suffix}, action = $action{suffix}, changes = $changes{suffix}, time = time::now()")
+}
+
+fn bind_entry(vars: &mut BTreeMap<String, Value>, entry: AuditEntry, suffix: &str) -> anyhow::Result<()> {
+    vars.insert(format!("part{suffix}"), Thing::from(("part", entry.part.as_str())).into());
+    vars.insert(format!("user{suffix}"), entry.user.into());
+    vars.insert(format!("action{suffix}"), json(&serde_json::to_string(&entry.action)?)?);
+    vars.insert(format!("changes{suffix}"), json(&serde_json::to_string(&entry.changes)?)?);
+
+    Ok(())
+}
+
+/// Mutation of a part and who made it
+pub struct AuditEntry {
+    pub part: String,
+    pub user: String,
+    pub action: AuditAction,
+    pub changes: Vec<FieldChange>,
+}
+
+/// Runs a mutation together with its audit record in one transaction,
+/// so a part is never changed without a record of it.
+/// Mutation can use `$part` as the record of the part. Returns result of the mutation.
+pub async fn execute_audited(sql: &str, mut vars: BTreeMap<String, Value>, entry: AuditEntry, db: &Data<DB>) -> anyhow::Result<Value> {
+    bind_entry(&mut vars, entry, "")?;
+
+    let sql = format!("BEGIN TRANSACTION; {}; {}; COMMIT TRANSACTION;", sql, audit_sql(""));
+
+    let responses = db.datastore.execute(&sql, &db.session, Some(vars), false).await?;
+
+    let mut results = Vec::new();
+    for response in responses {
+        results.push(response.result?);
+    }
+
+    match results.into_iter().next() {
+        Some(result) => Ok(result),
+        None => bail!("No response from DB"),
+    }
+}
+
+/// Runs many mutations with their audit records in one transaction, either all of them are applied or none.
+/// Mutation at index `i` can use `$part{i}` as the record of its part, and its variables have to be in `vars`.
+pub async fn execute_audited_batch(mutations: Vec<(String, AuditEntry)>, mut vars: BTreeMap<String, Value>, db: &Data<DB>) -> anyhow::Result<()> {
+    let mut statements = Vec::new();
+    for (index, (sql, entry)) in mutations.into_iter().enumerate() {
+        let suffix = index.to_string();
+        bind_entry(&mut vars, entry, &suffix)?;
+        statements.push(sql);
+        statements.push(audit_sql(&suffix));
+    }
+
+    let sql = format!("BEGIN TRANSACTION; {}; COMMIT TRANSACTION;", statements.join("; "));
+
+    let responses = db.datastore.execute(&sql, &db.session, Some(vars), false).await?;
+
+    for response in responses {
+        response.result?;
+    }
+
+    Ok(())
+}
+
+async fn get_history(id: &PartId, db: &Data<DB>) -> anyhow::Result<Vec<AuditRecord>> {
+    let sql = "SELECT * FROM audit WHERE part = $part ORDER BY time ASC";
+    let vars: BTreeMap<String, Value> = [
+        ("part".into(), id.into()),
+    ].into();
+
+    let response = db.datastore.execute(sql, &db.session, Some(vars), false).await?;
+
+    let result = match response.into_iter().next() {
+        Some(response) => response.result?,
+        None => bail!("No response from DB"),
+    };
+
+    let json = serde_json::to_value(result)?;
+    let mut records: Vec<AuditRecord> = serde_json::from_value(json)?;
+    for record in records.iter_mut() {
+        record.id = record.id.replace("audit:", "");
+        record.part = record.part.replace("part:", "");
+    }
+
+    Ok(records)
+}
+
+/// Changes of a part, oldest first. History of deleted parts is kept.
+pub async fn part_history(_auth: Authenticated<can::UpdateParts>, props: web::Json<GetPartHistoryProps>, db: Data<DB>) -> ApiResult<HttpResponse> {
+    Ok(HttpResponse::Ok().json(get_history(&props.id, &db).await?))
+}
@@ -0,0 +1,242 @@
+use std::{collections::BTreeMap, env, future::Future, marker::PhantomData, pin::Pin, sync::OnceLock, time::{SystemTime, UNIX_EPOCH}};
+
+use actix_web::{dev::Payload, http::header, web::{self, Data}, FromRequest, HttpRequest, HttpResponse};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use common::{error::{ApiError, ApiResult}, user::{LoginProps, LoginResponse, User}};
+use hmac::{Hmac, Mac};
+use serde::{Serialize, Deserialize};
+use sha2::Sha256;
+use surrealdb::sql::{Value, Thing, Id};
+
+use crate::{DB, users::{check_credentials, execute_user_query}};
+
+/// How long a session lasts, in seconds
+const SESSION_LENGTH: u64 = 12 * 60 * 60;
+
+/// Key used to sign tokens. Without `PCPC_SESSION_SECRET` a random key is used,
+/// so sessions end when the server restarts.
+fn session_secret() -> &'static [u8] {
+    static SECRET: OnceLock<Vec<u8>> = OnceLock::new();
+    SECRET.get_or_init(|| match env::var("PCPC_SESSION_SECRET") {
+        Ok(secret) => secret.into_bytes(),
+        Err(_) => {
+            println!("PCPC_SESSION_SECRET is not set, sessions will end on restart");
+            rand::random::<[u8; 32]>().to_vec()
+        },
+    })
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|x| x.as_secs()).unwrap_or(0)
+}
+
+/// Content of a token. Signature proves the backend issued it,
+/// the session record allows ending it before it expires.
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Claims {
+    session: String,
+    user: String,
+    /// Unix timestamp in seconds
+    expires: u64,
+}
+
+fn mac() -> Hmac<Sha256> {
+    Hmac::<Sha256>::new_from_slice(session_secret()).expect("HMAC accepts keys of any size")
+}
+
+fn issue_token(claims: &Claims) -> anyhow::Result<String> {
+    let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(claims)?);
+    let mut mac = mac();
+    mac.update(payload.as_bytes());
+    let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+
+    Ok(format!("{}.{}", payload, signature))
+}
+
+/// Claims of a token with valid signature, that hasn't expired yet
+fn verify_token(token: &str) -> Option<Claims> {
+    let (payload, signature) = token.split_once('.')?;
+    let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+    let mut mac = mac();
+    mac.update(payload.as_bytes());
+    mac.verify_slice(&signature).ok()?;
+
+    let claims: Claims = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+    if claims.expires <= now() {
+        return None;
+    }
+
+    Some(claims)
+}
+
+fn session_vars(claims: &Claims) -> BTreeMap<String, Value> {
+    [
+        ("session".into(), Thing::from(("session", claims.session.as_str())).into()),
+        ("user".into(), Thing::from(("user", claims.user.as_str())).into()),
+        ("expires".into(), claims.expires.into()),
+        ("now".into(), now().into()),
+    ].into()
+}
+
+/// Current state of the user of a session, None if session has ended
+async fn session_user(claims: &Claims, db: &Data<DB>) -> anyhow::Result<Option<User>> {
+    let sql = "SELECT user.id AS id, user.username AS username, user.role AS role, user.disabled AS disabled FROM $session WHERE user = $user";
+    let users = execute_user_query(sql, session_vars(claims), db).await?;
+
+    Ok(users.into_iter().next())
+}
+
+/// Requirement checked by [`Authenticated`]
+pub trait Guard {
+    fn allows(user: &User) -> bool;
+}
+
+/// Any logged in user
+pub struct AnyUser;
+
+impl Guard for AnyUser {
+    fn allows(_user: &User) -> bool {
+        true
+    }
+}
+
+/// Guards requiring a [`Permission`], e.g. `Authenticated<can::DeleteParts>`
+pub mod can {
+    use common::user::{Permission, User};
+
+    use super::Guard;
+
+    macro_rules! permission_guards {
+        ($($permission:ident),*) => {
+            $(
+                pub struct $permission;
+
+                impl Guard for $permission {
+                    fn allows(user: &User) -> bool {
+                        user.role.can(Permission::$permission)
+                    }
+                }
+            )*
+        };
+    }
+
+    permission_guards!(CreateParts, UpdateParts, DeleteParts, ManageUsers, SaveBuilds, SaveFavorites, WriteReviews);
+}
+
+/// Extractor of the user logged in with `Authorization: Bearer <token>`.
+/// Rejects the request with 401 without valid session and with 403 if guard `G` doesn't allow the user.
+pub struct Authenticated<G: Guard = AnyUser> {
+    pub user: User,
+    /// Id of the session, used to log out
+    pub session: String,
+    _guard: PhantomData<G>,
+}
+
+impl<G: Guard> FromRequest for Authenticated<G> {
+    type Error = ApiError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let claims = req.headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|x| x.to_str().ok())
+            .and_then(|x| x.strip_prefix("Bearer "))
+            .and_then(verify_token);
+        let db = req.app_data::<Data<DB>>().cloned();
+
+        Box::pin(async move {
+            let claims = claims.ok_or_else(|| ApiError::unauthorized("Invalid or expired session"))?;
+            let db = db.ok_or_else(ApiError::internal)?;
+            let user = session_user(&claims, &db).await?
+                .ok_or_else(|| ApiError::unauthorized("Session has ended"))?;
+
+            if user.disabled {
+                return Err(ApiError::unauthorized("User is disabled"));
+            }
+
+            if !G::allows(&user) {
+                return Err(ApiError::forbidden("Your role doesn't allow this"));
+            }
+
+            Ok(Self {
+                user,
+                session: claims.session,
+                _guard: PhantomData,
+            })
+        })
+    }
+}
+
+pub async fn create_session(user: User, db: &Data<DB>) -> anyhow::Result<LoginResponse> {
+    let claims = Claims {
+        session: Id::rand().to_raw(),
+        user: user.id.clone(),
+        expires: now() + SESSION_LENGTH,
+    };
+
+    // Expired sessions are cleaned up here, as they can't be used anyway
+    let sql = "DELETE session WHERE expires <= $now; CREATE $session SET user = $user, expires = $expires";
+    let responses = db.datastore.execute(sql, &db.session, Some(session_vars(&claims)), false).await?;
+
+    for response in responses {
+        response.result?;
+    }
+
+    Ok(LoginResponse {
+        token: issue_token(&claims)?,
+        user,
+        expires: claims.expires,
+    })
+}
+
+pub async fn login(props: web::Json<LoginProps>, db: Data<DB>) -> ApiResult<HttpResponse> {
+    let user = match check_credentials(&props.username, &props.password, &db).await? {
+        Some(user) => user,
+        None => return Err(ApiError::unauthorized("Wrong username or password")),
+    };
+
+    Ok(HttpResponse::Ok().json(create_session(user, &db).await?))
+}
+
+pub async fn logout(auth: Authenticated, db: Data<DB>) -> ApiResult<HttpResponse> {
+    let vars: BTreeMap<String, Value> = [
+        ("session".into(), Thing::from(("session", auth.session.as_str())).into()),
+    ].into();
+
+    let response = db.datastore.execute("DELETE $session", &db.session, Some(vars), false).await;
+    response.map_err(anyhow::Error::from)?;
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn me(auth: Authenticated) -> HttpResponse {
+    HttpResponse::Ok().json(auth.user)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{issue_token, verify_token, now, Claims};
+
+    #[test]
+    fn tokens() {
+        let claims = Claims {
+            session: "session".into(),
+            user: "user".into(),
+            expires: now() + 60,
+        };
+        let token = issue_token(&claims).unwrap();
+        assert_eq!(verify_token(&token), Some(claims));
+
+        let (payload, signature) = token.split_once('.').unwrap();
+        let forged = format!("{}A.{}", payload, signature);
+        assert_eq!(verify_token(&forged), None);
+        assert_eq!(verify_token(payload), None);
+
+        let expired = Claims {
+            session: "session".into(),
+            user: "user".into(),
+            expires: now() - 1,
+        };
+        assert_eq!(verify_token(&issue_token(&expired).unwrap()), None);
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+
+use actix_web::{web::{self, Data}, HttpResponse};
+use anyhow::bail;
+use common::{error::{ApiError, ApiResult}, user::{Permission, User}, build::{DBBuild, DBBuildProps, GetBuildProps, UpdateBuildProps, DeleteBuildProps}, export::{ExportBuildProps, build_table}, price::Currency, optimizer::{Candidate, SuggestBuildProps, suggest_builds}};
+use surrealdb::sql::{json, Value, Thing};
+
+use crate::{DB, auth::{Authenticated, can}, export_response, prices::{get_all_lowest_prices, get_lowest_prices}, repository::PartRepository};
+
+async fn execute_build_query(sql: &str, vars: BTreeMap<String, Value>, db: &Data<DB>) -> anyhow::Result<Vec<DBBuild>> {
+    let response = db.datastore.execute(sql, &db.session, Some(vars), false).await?;
+
+    let result = match response.into_iter().next() {
+        Some(response) => response.result?,
+        None => bail!("No response from DB"),
+    };
+
+    let json = serde_json::to_value(result)?;
+    let mut builds: Vec<DBBuild> = serde_json::from_value(json)?;
+    for build in builds.iter_mut() {
+        build.id = build.id.replace("build:", "");
+    }
+
+    Ok(builds)
+}
+
+/// Build the user may change, expects `$build` to be set in vars
+async fn owned_build(user: &User, vars: &BTreeMap<String, Value>, db: &Data<DB>) -> ApiResult<DBBuild> {
+    let build = match execute_build_query("SELECT * FROM $build", vars.clone(), db).await?.into_iter().next() {
+        Some(build) => build,
+        None => return Err(build_not_found()),
+    };
+
+    // Admins can clean up builds of anyone
+    if build.owner != user.id && !user.role.can(Permission::ManageUsers) {
+        return Err(ApiError::forbidden("Build belongs to another user"));
+    }
+
+    Ok(build)
+}
+
+/// Content of a build record, owner isn't part of props so users can't change it
+fn build_content(props: DBBuildProps, owner: &str) -> anyhow::Result<Value> {
+    let mut content = serde_json::to_value(props)?;
+    content["owner"] = owner.into();
+
+    Ok(json(&content.to_string())?)
+}
+
+fn build_thing(id: &str) -> Value {
+    Thing::from(("build", id)).into()
+}
+
+pub async fn build(props: web::Json<GetBuildProps>, db: Data<DB>) -> ApiResult<HttpResponse> {
+    let props = props.into_inner();
+
+    let builds = match &props.id {
+        Some(id) => {
+            let vars: BTreeMap<String, Value> = [
+                ("build".into(), build_thing(id)),
+            ].into();
+            execute_build_query("SELECT * FROM $build", vars, &db).await?
+        },
+        None => {
+            // LIMIT doesn't accept variables
+            let sql = format!("SELECT * FROM build LIMIT {}", props.limit);
+            execute_build_query(&sql, BTreeMap::new(), &db).await?
+        },
+    };
+
+    if props.id.is_some() {
+        return match builds.into_iter().next() {
+            Some(build) => Ok(HttpResponse::Ok().json(build)),
+            None => Err(build_not_found()),
+        };
+    }
+
+    Ok(HttpResponse::Ok().json(builds))
+}
+
+fn build_not_found() -> ApiError {
+    ApiError::not_found("Build doesn't exist")
+}
+
+pub async fn create_build(auth: Authenticated<can::SaveBuilds>, props: web::Json<DBBuildProps>, db: Data<DB>) -> ApiResult<HttpResponse> {
+    let vars: BTreeMap<String, Value> = [
+        ("props".into(), build_content(props.into_inner(), &auth.user.id)?),
+    ].into();
+
+    match execute_build_query("CREATE build CONTENT $props", vars, &db).await?.into_iter().next() {
+        Some(build) => Ok(HttpResponse::Ok().json(build)),
+        None => Err(ApiError::internal()),
+    }
+}
+
+pub async fn update_build(auth: Authenticated<can::SaveBuilds>, props: web::Json<UpdateBuildProps>, db: Data<DB>) -> ApiResult<HttpResponse> {
+    let props = props.into_inner();
+    let mut vars: BTreeMap<String, Value> = [
+        ("build".into(), build_thing(&props.id)),
+    ].into();
+
+    // UPDATE on a missing record would create it
+    let build = owned_build(&auth.user, &vars, &db).await?;
+    vars.insert("props".into(), build_content(props.props, &build.owner)?);
+
+    match execute_build_query("UPDATE $build CONTENT $props", vars, &db).await?.into_iter().next() {
+        Some(build) => Ok(HttpResponse::Ok().json(build)),
+        None => Err(ApiError::internal()),
+    }
+}
+
+pub async fn delete_build(auth: Authenticated<can::SaveBuilds>, props: web::Json<DeleteBuildProps>, db: Data<DB>) -> ApiResult<HttpResponse> {
+    let vars: BTreeMap<String, Value> = [
+        ("build".into(), build_thing(&props.id)),
+    ].into();
+
+    owned_build(&auth.user, &vars, &db).await?;
+
+    execute_build_query("DELETE $build", vars, &db).await?;
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+async fn get_candidates<R: PartRepository>(props: &SuggestBuildProps, parts: &R, db: &Data<DB>) -> anyhow::Result<Vec<Candidate>> {
+    let prices = get_all_lowest_prices(props.currency, db).await?;
+    let ids: Vec<String> = prices.keys().cloned().collect();
+    let parts = parts.get_many(&ids).await?;
+
+    Ok(parts.into_iter()
+        .filter_map(|part| prices.get(&part.id).map(|price| Candidate { price: price.amount, part }))
+        .collect())
+}
+
+/// Best compatible builds under the budget, from parts that have a price in the requested currency
+pub async fn suggest_build<R: PartRepository>(props: web::Json<SuggestBuildProps>, parts: Data<R>, db: Data<DB>) -> ApiResult<HttpResponse> {
+    let props = props.into_inner();
+    let candidates = get_candidates(&props, parts.as_ref(), &db).await?;
+
+    // Search can take a while, so it doesn't block other requests
+    let suggestions = web::block(move || suggest_builds(&candidates, &props)).await
+        .map_err(anyhow::Error::from)?;
+
+    Ok(HttpResponse::Ok().json(suggestions))
+}
+
+/// Parts of a build with their prices as a file
+pub async fn export_build<R: PartRepository>(props: web::Json<ExportBuildProps>, parts: Data<R>, db: Data<DB>) -> ApiResult<HttpResponse> {
+    let vars: BTreeMap<String, Value> = [
+        ("build".into(), build_thing(&props.id)),
+    ].into();
+
+    let build = match execute_build_query("SELECT * FROM $build", vars, &db).await?.into_iter().next() {
+        Some(build) => build,
+        None => return Err(build_not_found()),
+    };
+
+    let ids = build.slots.ids();
+    let parts = parts.get_many(&ids).await?;
+    let prices = get_lowest_prices(&ids, Currency::default(), &db).await?.into_iter().collect();
+
+    Ok(export_response(&build_table(&build.slots, &parts, &prices), props.format, &build.name))
+}
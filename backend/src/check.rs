@@ -0,0 +1,84 @@
+use std::collections::BTreeSet;
+
+use actix_web::web::Data;
+use anyhow::bail;
+use common::{DBPart, build::DBBuild};
+use serde_json::Value;
+
+use crate::{DB, migrations::{SCHEMA_VERSION, schema_version}};
+
+/// Rows of a query without variables, as JSON
+pub async fn select(sql: &str, db: &Data<DB>) -> anyhow::Result<Vec<Value>> {
+    let response = db.datastore.execute(sql, &db.session, None, false).await?;
+
+    let result = match response.into_iter().next() {
+        Some(response) => response.result?,
+        None => bail!("No response from DB"),
+    };
+
+    Ok(serde_json::from_value(serde_json::to_value(result)?)?)
+}
+
+fn field(row: &Value, key: &str) -> String {
+    match &row[key] {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Stored data the handlers can't read or that points to deleted records, described for people.
+/// History of deleted parts is kept on purpose, so audit records are not checked.
+pub async fn check_integrity(db: &Data<DB>) -> anyhow::Result<Vec<String>> {
+    let mut problems = Vec::new();
+
+    match schema_version(db).await? {
+        Some(SCHEMA_VERSION) => {},
+        Some(version) => problems.push(format!("Schema is version {}, expected {}", version, SCHEMA_VERSION)),
+        None => problems.push(format!("Schema has no version, expected {}", SCHEMA_VERSION)),
+    }
+
+    let mut part_ids = BTreeSet::new();
+    for row in select("SELECT * FROM part", db).await? {
+        let id = field(&row, "id");
+        if let Err(error) = serde_json::from_value::<DBPart>(row) {
+            problems.push(format!("{} can't be read: {}", id, error));
+        }
+        part_ids.insert(id.replace("part:", ""));
+    }
+
+    for row in select("SELECT * FROM build", db).await? {
+        let id = field(&row, "id");
+        let build: DBBuild = match serde_json::from_value(row) {
+            Ok(build) => build,
+            Err(error) => {
+                problems.push(format!("{} can't be read: {}", id, error));
+                continue;
+            },
+        };
+
+        for (slot, part) in build.slots.slots() {
+            if !part_ids.contains(&part) {
+                problems.push(format!("{} has missing part {} as {}", id, part, slot));
+            }
+        }
+    }
+
+    for row in select("SELECT id, part, user FROM favorite WHERE part.id = NONE OR user.id = NONE", db).await? {
+        problems.push(format!("{} of {} has missing part or user {}", field(&row, "id"), field(&row, "part"), field(&row, "user")));
+    }
+
+    for row in select("SELECT id, part, user FROM review WHERE part.id = NONE OR user.id = NONE", db).await? {
+        problems.push(format!("{} of {} has missing part or user {}", field(&row, "id"), field(&row, "part"), field(&row, "user")));
+    }
+
+    for row in select("SELECT id, part FROM price WHERE part.id = NONE", db).await? {
+        problems.push(format!("{} is of missing part {}", field(&row, "id"), field(&row, "part")));
+    }
+
+    let sql = "SELECT id, username FROM user WHERE role = NONE OR disabled = NONE OR string::startsWith(password, '$argon2') = false";
+    for row in select(sql, db).await? {
+        problems.push(format!("User {} is not migrated", field(&row, "username")));
+    }
+
+    Ok(problems)
+}
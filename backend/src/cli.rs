@@ -0,0 +1,265 @@
+use std::{io::IsTerminal, path::{Path, PathBuf}, str::FromStr, sync::Arc};
+
+use actix_web::web::Data;
+use anyhow::bail;
+use clap::{Parser, Subcommand};
+use common::{GetPartProps, PartsCategory, error::ApiError, export::{ExportFormat, property_fields}, user::{CreateUserProps, Role}, validation::validate_password};
+use serde::Deserialize;
+use surrealdb::sql::Value;
+
+use crate::{DB, check::check_integrity, import::{import_file, import_rows, json_rows}, migrations, parts::get_parts_table, repository::SurrealParts, serve, users};
+
+/// Author of audit records of parts changed from the command line
+const CLI_USER: &str = "cli";
+
+#[derive(Parser)]
+#[command(about = "Server of the PC part catalog, with tools to administer its database")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Runs the HTTP server, the default when no command is given
+    Serve,
+    /// Adds users and parts from a JSON fixture, e.g. `fixtures/dev.json`
+    Seed {
+        file: PathBuf,
+    },
+    /// Manages users
+    User {
+        #[command(subcommand)]
+        command: UserCommand,
+    },
+    /// Imports parts from a CSV or JSON file, all of them or none
+    Import {
+        file: PathBuf,
+        /// Category of parts in a CSV file, e.g. `CPU`
+        #[arg(long)]
+        category: Option<String>,
+        /// Only validates the file
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Exports parts with all properties of their category to a CSV, JSON or Markdown file
+    Export {
+        file: PathBuf,
+        /// Category of exported parts, e.g. `CPU`
+        #[arg(long)]
+        category: Option<String>,
+        /// Text searched for in name, model and manufacturer
+        #[arg(long)]
+        query: Option<String>,
+    },
+    /// Migrates stored data to the schema of this build
+    Migrate,
+    /// Reports records that can't be read or point to deleted records
+    Check,
+}
+
+#[derive(Subcommand)]
+pub enum UserCommand {
+    /// Creates a user, asks for the password when it's not given.
+    /// A password piped to stdin is read without asking.
+    Add {
+        username: String,
+        #[arg(long, default_value_t = Role::Viewer)]
+        role: Role,
+        /// Only for scripts, other users can see it in the list of processes
+        #[arg(long)]
+        password: Option<String>,
+    },
+    /// Sets password of a user, asks for it when it's not given.
+    /// A password piped to stdin is read without asking.
+    Passwd {
+        username: String,
+        /// Only for scripts, other users can see it in the list of processes
+        #[arg(long)]
+        password: Option<String>,
+    },
+    /// Disables a user, so they can't log in
+    Disable {
+        username: String,
+        /// Enables the user again instead
+        #[arg(long)]
+        enable: bool,
+    },
+}
+
+/// Content of a seed file
+#[derive(Deserialize)]
+struct Fixture {
+    #[serde(default)]
+    users: Vec<CreateUserProps>,
+    /// Parsed one by one, so an invalid part is reported with its position
+    #[serde(default)]
+    parts: Vec<serde_json::Value>,
+}
+
+pub async fn run(command: Command, db: Arc<DB>) -> anyhow::Result<()> {
+    let data = Data::from(db.clone());
+
+    // Other commands would read or write records this build doesn't understand
+    match command {
+        Command::Migrate | Command::Check => {},
+        _ => migrations::check_schema(&data).await?,
+    }
+
+    match command {
+        Command::Serve => serve(db).await,
+        Command::Seed { file } => seed(&std::fs::read_to_string(file)?, &data).await,
+        Command::User { command } => user(command, &data).await,
+        Command::Import { file, category, dry_run } => import(&file, category, dry_run, &data).await,
+        Command::Export { file, category, query } => export(&file, category, query, &data).await,
+        Command::Migrate => {
+            let applied = migrations::migrate(&data).await?;
+            for description in &applied {
+                println!("Migrated: {}", description);
+            }
+            println!("Database schema is version {}", migrations::SCHEMA_VERSION);
+            Ok(())
+        },
+        Command::Check => {
+            let problems = check_integrity(&data).await?;
+            for problem in &problems {
+                println!("{}", problem);
+            }
+
+            if !problems.is_empty() {
+                bail!("Found {} problems", problems.len());
+            }
+            println!("No problems found");
+            Ok(())
+        },
+    }
+}
+
+/// Users with taken usernames are skipped. Parts are added every time, so seeding twice duplicates them.
+pub async fn seed(fixture: &str, db: &Data<DB>) -> anyhow::Result<()> {
+    let fixture: Fixture = serde_json::from_str(fixture)?;
+
+    for props in &fixture.users {
+        match users::create_user_raw(props, db).await? {
+            Some(_) => println!("Added user {}", props.username),
+            None => println!("User {} already exists", props.username),
+        }
+    }
+
+    let report = import_rows(json_rows(fixture.parts), false, CLI_USER, &SurrealParts::new(db.clone())).await?;
+    for error in &report.errors {
+        println!("Part {}: {}", error.row, error.message);
+    }
+    if !report.errors.is_empty() {
+        bail!("{} of {} parts are invalid, no parts were added", report.errors.len(), report.rows);
+    }
+    println!("Added {} parts", report.imported);
+
+    Ok(())
+}
+
+fn password_or_prompt(password: Option<String>) -> anyhow::Result<String> {
+    if let Some(password) = password {
+        return Ok(password);
+    }
+
+    // Typed password isn't echoed, piped one is read as it is
+    let password = match std::io::stdin().is_terminal() {
+        true => rpassword::prompt_password("Password: ")?,
+        false => {
+            let mut password = String::new();
+            std::io::stdin().read_line(&mut password)?;
+            password.trim_end_matches(['\r', '\n']).to_string()
+        },
+    };
+
+    if password.is_empty() {
+        bail!("Password can't be empty");
+    }
+
+    Ok(password)
+}
+
+async fn user(command: UserCommand, db: &Data<DB>) -> anyhow::Result<()> {
+    let (username, set, value) = match command {
+        UserCommand::Add { username, role, password } => {
+            let props = CreateUserProps {
+                username,
+                password: password_or_prompt(password)?,
+                role,
+            };
+
+            match users::create_user_raw(&props, db).await? {
+                Some(user) => println!("Added {} {}", user.role, user.username),
+                None => bail!("User {} already exists", props.username),
+            }
+            return Ok(());
+        },
+        UserCommand::Passwd { username, password } => {
+            let password = password_or_prompt(password)?;
+            validate_password(&password).map_err(ApiError::validation)?;
+            (username, "password = crypto::argon2::generate($value)", Value::from(password))
+        },
+        UserCommand::Disable { username, enable } => (username, "disabled = $value", Value::from(!enable)),
+    };
+
+    let user = match users::find_user(&username, db).await? {
+        Some(user) => user,
+        None => bail!("User {} doesn't exist", username),
+    };
+
+    let vars = [("value".to_string(), value)].into();
+    match users::update_user_raw(&user.id, set, vars, db).await? {
+        Some(user) if user.disabled => println!("User {} is disabled", user.username),
+        Some(user) => println!("User {} is updated", user.username),
+        None => bail!("User {} doesn't exist", username),
+    }
+
+    Ok(())
+}
+
+async fn import(file: &Path, category: Option<String>, dry_run: bool, db: &Data<DB>) -> anyhow::Result<()> {
+    let report = import_file(file, category, dry_run, CLI_USER, &SurrealParts::new(db.clone())).await?;
+
+    for error in &report.errors {
+        println!("Row {}: {}", error.row, error.message);
+    }
+    if !report.errors.is_empty() {
+        bail!("{} of {} rows are invalid, nothing was imported", report.errors.len(), report.rows);
+    }
+
+    match dry_run {
+        true => println!("All {} rows are valid", report.rows),
+        false => println!("Imported {} parts", report.imported),
+    }
+
+    Ok(())
+}
+
+async fn export(file: &Path, category: Option<String>, query: Option<String>, db: &Data<DB>) -> anyhow::Result<()> {
+    let format = match file.extension().and_then(|x| x.to_str()).map(str::to_lowercase).as_deref() {
+        Some("csv") => ExportFormat::Csv,
+        Some("json") => ExportFormat::Json,
+        Some("md") => ExportFormat::Markdown,
+        _ => bail!("Unknown format of {}, expected .csv, .json or .md", file.display()),
+    };
+
+    // Every property of the category, in alphabetical order
+    let template = match &category {
+        Some(category) => PartsCategory::from_str(category).map_err(|_| anyhow::anyhow!("Unknown category: {}", category))?,
+        None => PartsCategory::Basic,
+    };
+    let mut columns: Vec<String> = property_fields(template).into_iter().map(|x| x.label.to_string()).collect();
+    columns.sort();
+
+    let props = GetPartProps {
+        category,
+        query,
+        ..Default::default()
+    };
+    let table = get_parts_table(&props, &columns, &SurrealParts::new(db.clone()), db).await?;
+    std::fs::write(file, table.render(format))?;
+    println!("Exported {} parts to {}", table.rows.len(), file.display());
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+
+use actix_web::{web::{self, Data}, HttpResponse};
+use anyhow::bail;
+use common::{error::{ApiError, ApiResult}, types::PartId, user::FavoriteProps};
+use serde::Deserialize;
+use surrealdb::sql::{Value, Thing};
+
+use crate::{DB, auth::{Authenticated, can}};
+
+#[derive(Deserialize)]
+struct Favorite {
+    part: String,
+}
+
+fn favorite_vars(user: &str, part: Option<&PartId>) -> BTreeMap<String, Value> {
+    let mut vars: BTreeMap<String, Value> = [
+        ("user".into(), Thing::from(("user", user)).into()),
+    ].into();
+    if let Some(part) = part {
+        vars.insert("part".into(), part.into());
+    }
+
+    vars
+}
+
+async fn execute(sql: &str, vars: BTreeMap<String, Value>, db: &Data<DB>) -> anyhow::Result<Value> {
+    let response = db.datastore.execute(sql, &db.session, Some(vars), false).await?;
+
+    match response.into_iter().next() {
+        Some(response) => Ok(response.result?),
+        None => bail!("No response from DB"),
+    }
+}
+
+/// Ids of favorite parts of a user, oldest first. Deleted parts are left out.
+async fn get_favorites(user: &str, db: &Data<DB>) -> anyhow::Result<Vec<String>> {
+    let sql = "SELECT part, time FROM favorite WHERE user = $user AND part.id != NONE ORDER BY time ASC";
+    let result = execute(sql, favorite_vars(user, None), db).await?;
+
+    let favorites: Vec<Favorite> = serde_json::from_value(serde_json::to_value(result)?)?;
+
+    Ok(favorites.into_iter().map(|x| x.part.replace("part:", "")).collect())
+}
+
+pub async fn favorites(auth: Authenticated<can::SaveFavorites>, db: Data<DB>) -> ApiResult<HttpResponse> {
+    Ok(HttpResponse::Ok().json(get_favorites(&auth.user.id, &db).await?))
+}
+
+/// Returns favorites after the part was added. Adding a favorite again does nothing.
+pub async fn add_favorite(auth: Authenticated<can::SaveFavorites>, props: web::Json<FavoriteProps>, db: Data<DB>) -> ApiResult<HttpResponse> {
+    let vars = favorite_vars(&auth.user.id, Some(&props.part));
+
+    if let Value::Array(parts) = execute("SELECT id FROM $part", vars.clone(), &db).await? {
+        if parts.is_empty() {
+            return Err(ApiError::not_found("Part doesn't exist"));
+        }
+    }
+
+    match execute("SELECT id FROM favorite WHERE user = $user AND part = $part", vars.clone(), &db).await? {
+        Value::Array(favorites) if !favorites.is_empty() => {},
+        _ => { execute("CREATE favorite SET user = $user, part = $part, time = time::now()", vars, &db).await?; },
+    }
+
+    favorites(auth, db).await
+}
+
+/// Returns favorites after the part was removed
+pub async fn remove_favorite(auth: Authenticated<can::SaveFavorites>, props: web::Json<FavoriteProps>, db: Data<DB>) -> ApiResult<HttpResponse> {
+    let vars = favorite_vars(&auth.user.id, Some(&props.part));
+    execute("DELETE favorite WHERE user = $user AND part = $part", vars, &db).await?;
+
+    favorites(auth, db).await
+}
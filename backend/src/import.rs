@@ -0,0 +1,246 @@
+use std::{fmt::Display, path::Path, str::FromStr};
+
+use actix_web::{web::{self, Data}, HttpResponse};
+use anyhow::bail;
+use common::{DBPartProps, PartsCategory, traits::PartProperties, validation::validate_part, error::{ApiError, ApiResult, FieldError}, import::{ImportFormat, ImportPartsProps, ImportReport, RowError}};
+use serde_json::Value as JsonValue;
+
+use crate::{auth::{Authenticated, can}, parts::with_stored_rating, repository::PartRepository};
+
+/// Largest accepted body of an import request, other requests keep the default limit
+pub const IMPORT_LIMIT: usize = 4 * 1024 * 1024;
+
+/// Problem with the whole file, as opposed to [`RowError`]
+#[derive(Debug, PartialEq)]
+pub enum ImportError {
+    MissingCategory,
+    UnknownCategory(String),
+    UnknownColumn(String),
+    Malformed(String),
+}
+
+impl std::error::Error for ImportError {}
+
+impl Display for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportError::MissingCategory => write!(f, "CSV import needs a category"),
+            ImportError::UnknownCategory(category) => write!(f, "Unknown category: {}", category),
+            ImportError::UnknownColumn(column) => write!(f, "Unknown column: {}", column),
+            ImportError::Malformed(error) => write!(f, "Malformed file: {}", error),
+        }
+    }
+}
+
+/// Parts in order of the file, or why they can't be imported
+pub type Rows = Vec<Result<DBPartProps, String>>;
+
+pub fn parse_rows(props: &ImportPartsProps) -> Result<Rows, ImportError> {
+    match props.format {
+        ImportFormat::Json => parse_json(&props.data),
+        ImportFormat::Csv => {
+            let category = props.category.as_deref().ok_or(ImportError::MissingCategory)?;
+            let category = PartsCategory::from_str(category)
+                .map_err(|_| ImportError::UnknownCategory(category.to_string()))?;
+            parse_csv(&props.data, category)
+        },
+    }
+}
+
+fn parse_json(data: &str) -> Result<Rows, ImportError> {
+    let rows: Vec<JsonValue> = serde_json::from_str(data)
+        .map_err(|error| ImportError::Malformed(error.to_string()))?;
+
+    Ok(json_rows(rows))
+}
+
+/// Reads already parsed JSON objects as parts, each one on its own
+pub fn json_rows(rows: Vec<JsonValue>) -> Rows {
+    rows.into_iter()
+        .map(|row| serde_json::from_value(row).map_err(|error| error.to_string()).and_then(check_part))
+        .collect()
+}
+
+fn parse_csv(data: &str, category: PartsCategory) -> Result<Rows, ImportError> {
+    // Default part of the category decides type of each column and fills the missing ones
+    let template = DBPartProps { category, ..Default::default() };
+
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(data.as_bytes());
+    let headers = reader.headers().map_err(|error| ImportError::Malformed(error.to_string()))?;
+
+    let mut columns = Vec::new();
+    for header in headers {
+        match template.field(header) {
+            Some(field) => columns.push(field.key),
+            None => return Err(ImportError::UnknownColumn(header.to_string())),
+        }
+    }
+
+    let rows = reader.records().map(|record| {
+        let record = record.map_err(|error| error.to_string())?;
+        let mut part = template.clone();
+        for (key, cell) in columns.iter().zip(record.iter()) {
+            if cell.is_empty() {
+                continue;
+            }
+
+            part.set_input(key, cell).map_err(|error| format!("{}: {}", error.field, error.message))?;
+        }
+
+        check_part(part)
+    }).collect();
+
+    Ok(rows)
+}
+
+fn check_part(part: DBPartProps) -> Result<DBPartProps, String> {
+    match validate_part(&part) {
+        Ok(()) => Ok(part),
+        Err(errors) => Err(errors.iter().map(|x| format!("{}: {}", x.field, x.message)).collect::<Vec<String>>().join(", ")),
+    }
+}
+
+/// Creates all parts in one transaction, unless it's a dry run or any row has an error.
+/// Like parts created one by one, they have no rating until they are reviewed.
+pub async fn import_rows<R: PartRepository>(rows: Rows, dry_run: bool, user: &str, repository: &R) -> anyhow::Result<ImportReport> {
+    let mut report = ImportReport {
+        rows: rows.len(),
+        dry_run,
+        ..Default::default()
+    };
+
+    let mut parts = Vec::new();
+    for (index, row) in rows.into_iter().enumerate() {
+        match row {
+            // New parts have no reviews, so rating of the file is dropped
+            Ok(part) => parts.push(with_stored_rating(part, None)),
+            Err(message) => report.errors.push(RowError { row: index + 1, message }),
+        }
+    }
+
+    if dry_run || parts.is_empty() || !report.errors.is_empty() {
+        return Ok(report);
+    }
+
+    repository.create_many(&parts, user).await?;
+    report.imported = parts.len();
+
+    Ok(report)
+}
+
+pub async fn import<R: PartRepository>(auth: Authenticated<can::CreateParts>, props: web::Json<ImportPartsProps>, parts: Data<R>) -> ApiResult<HttpResponse> {
+    let rows = parse_rows(&props).map_err(|error| ApiError::invalid(error.to_string()))?;
+    let report = import_rows(rows, props.dry_run, &auth.user.username, parts.as_ref()).await?;
+
+    if !report.errors.is_empty() {
+        let message = format!("{} of {} rows are invalid, nothing was imported", report.errors.len(), report.rows);
+        let fields = report.errors.into_iter()
+            .map(|error| FieldError::new(format!("row {}", error.row), error.message))
+            .collect();
+        return Err(ApiError::Validation { message, fields });
+    }
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Imports a file, format is decided by its extension
+pub async fn import_file<R: PartRepository>(path: &Path, category: Option<String>, dry_run: bool, user: &str, parts: &R) -> anyhow::Result<ImportReport> {
+    let format = match path.extension().and_then(|x| x.to_str()).map(str::to_lowercase).as_deref() {
+        Some("csv") => ImportFormat::Csv,
+        Some("json") => ImportFormat::Json,
+        _ => bail!("Unknown format of {}, expected .csv or .json", path.display()),
+    };
+
+    let props = ImportPartsProps {
+        format,
+        category,
+        data: std::fs::read_to_string(path)?,
+        dry_run,
+    };
+    let rows = parse_rows(&props)?;
+
+    import_rows(rows, dry_run, user, parts).await
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{GetPartProps, PartsCategory};
+
+    use crate::repository::{PartRepository, memory::MemoryParts};
+
+    use super::{import_rows, parse_csv, parse_json, ImportError};
+
+    #[test]
+    fn csv_columns() {
+        let data = "name,manufactuer,rating,cores,threads,max_frequency,ecc_memory_supported,socket\n\
+            Ryzen 5 7600, AMD, 4.5, 6, 12, 5.1 GHz, true, AM5\n\
+            Core i5-13600K,Intel,,14,20,5.1 GHz,false,FCLGA1700\n";
+        let rows = parse_csv(data, PartsCategory::CPU(Default::default())).unwrap();
+
+        let part = rows[0].as_ref().unwrap();
+        assert_eq!(part.manufactuer, "AMD");
+        match &part.category {
+            PartsCategory::CPU(props) => {
+                assert_eq!(props.cores, 6);
+                assert_eq!(props.max_frequency, "5100 MHz".parse().unwrap());
+                assert!(props.ecc_memory_supported);
+                assert_eq!(props.socket, "AM5");
+            },
+            _ => panic!("Not a CPU"),
+        }
+
+        // Empty cells keep the default
+        assert_eq!(rows[1].as_ref().unwrap().rating.get(), 0.0);
+    }
+
+    #[actix_web::test]
+    async fn rating_is_reset() {
+        let data = "name,rating
+            Ryzen 5 7600,4.5
+";
+        let mut rows = parse_csv(data, PartsCategory::CPU(Default::default())).unwrap();
+        if let Ok(part) = &mut rows[0] {
+            part.review_count = 12;
+        }
+
+        let parts = MemoryParts::default();
+        let report = import_rows(rows, false, "test", &parts).await.unwrap();
+        assert_eq!(report.imported, 1);
+
+        let page = parts.page(&GetPartProps { limit: 1, ..Default::default() }).await.unwrap();
+        assert_eq!((page.parts[0].rating.get(), page.parts[0].review_count), (0.0, 0));
+    }
+
+    #[test]
+    fn csv_errors() {
+        let result = parse_csv("name,wattage\nRyzen,500 W\n", PartsCategory::CPU(Default::default()));
+        assert!(matches!(result, Err(ImportError::UnknownColumn(column)) if column == "wattage"));
+
+        let data = "name,cores,max_frequency,rating\n\
+            ,8,5 GHz,4\n\
+            Ryzen,eight,5 GHz,4\n\
+            Ryzen,8,fast,4\n\
+            Ryzen,8,5 GHz,9\n\
+            Ryzen,8\n\
+            Ryzen,8,5 GHz,4\n";
+        let rows = parse_csv(data, PartsCategory::CPU(Default::default())).unwrap();
+        let errors: Vec<bool> = rows.iter().map(|x| x.is_err()).collect();
+        assert_eq!(errors, [true, true, true, true, true, false]);
+        assert_eq!(rows[1].as_ref().unwrap_err(), "cores: eight is not a number");
+    }
+
+    #[test]
+    fn json_rows() {
+        let data = r#"[
+            { "name": "Monitor", "image_url": "", "model": "", "manufactuer": "", "release_date": "", "rating": "3", "category": "Basic" },
+            { "name": "Monitor" }
+        ]"#;
+        let rows = parse_json(data).unwrap();
+        assert_eq!(rows[0].as_ref().unwrap().release_date, None);
+        assert!(rows[1].is_err());
+
+        assert!(matches!(parse_json("{}"), Err(ImportError::Malformed(_))));
+    }
+}
@@ -1,13 +1,27 @@
 use std::env;
-use std::{sync::Arc, collections::BTreeMap};
+use std::sync::Arc;
 
 use actix_cors::Cors;
-use actix_web::{web::{self, Data}, App, HttpServer, middleware, HttpResponse, dev::{ServiceFactory, ServiceRequest, ServiceResponse}, body::MessageBody, Error, Responder, http::header};
-use actix_web_httpauth::{extractors::basic::BasicAuth, headers::www_authenticate::{WwwAuthenticate, basic::Basic}};
-use anyhow::{anyhow, bail};
-use common::{StatusResponse, DBPartProps, GetPartProps, DBPart, PartsCategory, CPUProperties};
-use surrealdb::{Datastore, Session, sql::Value};
-use tokio::sync::Mutex;
+use actix_web::{web::{self, Data}, App, HttpServer, middleware, HttpResponse, dev::{ServiceFactory, ServiceRequest, ServiceResponse}, body::MessageBody, Error, http::header};
+use clap::Parser;
+use cli::{Cli, Command};
+use common::{error::ApiError, export::{ExportFormat, Table}, StatusResponse};
+use repository::{PartRepository, SurrealParts};
+use surrealdb::{Datastore, Session};
+
+mod audit;
+mod auth;
+mod builds;
+mod check;
+mod cli;
+mod favorites;
+mod import;
+mod migrations;
+mod parts;
+mod prices;
+mod repository;
+mod reviews;
+mod users;
 
 pub struct DB {
     datastore: Datastore,
@@ -22,153 +36,63 @@ async fn status() -> HttpResponse {
     )
 }
 
-async fn part(props: web::Json<GetPartProps>, db: Data<Mutex<DB>>) -> HttpResponse {
-    let props = props.into_inner();
-
-    let db_locked = db.lock().await;
-    let props_id = props.id.clone();
-    let sql;
-    if let Some(part_id) = props_id {
-        sql = format!("SELECT * FROM part:{}", part_id);
-    } else {
-        sql = format!("SELECT * FROM part LIMIT {}", props.limit);
-    }
-
-    let response = db_locked.datastore.execute(sql.as_str(), &db_locked.session, None, false).await;
-
-    drop(db_locked);
-
-    if let Err(_) = response {
-        HttpResponse::InternalServerError().finish();
-    }
-
-    let response = response.unwrap();
-
-    for response in response {
-        if let Ok(result) = response.result {
-            let json = serde_json::to_value(result.clone()).unwrap();
-            let mut parts: Vec<DBPart> = serde_json::from_value(json).unwrap();
-
-            if parts.len() == 0 {
-                return HttpResponse::NotFound().finish();
-            }
-
-            for part in parts.iter_mut() {
-                part.id = part.id.replace("part:", "");
-            }
-
-            if props.id.is_some() {
-                if parts.len() > 1 {
-                    return HttpResponse::InternalServerError().finish();
-                }
-
-                let part = parts.first().unwrap().clone();
-
-                return HttpResponse::Ok().json(part);
-            }
+/// Rendered table as a file to download
+fn export_response(table: &Table, format: ExportFormat, name: &str) -> HttpResponse {
+    // Header values have to be ASCII, and quotes would end the file name
+    let name: String = name.chars()
+        .map(|x| if x.is_ascii_alphanumeric() || matches!(x, ' ' | '-' | '_') { x } else { '_' })
+        .collect();
+
+    HttpResponse::Ok()
+        .content_type(format.mime())
+        .insert_header((header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.{}\"", name, format.extension())))
+        .body(table.render(format))
+}
 
-            return HttpResponse::Ok().json(parts);
-        }
-    }
+/// Bodies that aren't valid JSON of the expected type are rejected with a validation error
+fn json_config(limit: usize) -> web::JsonConfig {
+    web::JsonConfig::default()
+        .limit(limit)
+        .error_handler(|error, _| ApiError::invalid(error.to_string()).into())
+}
 
-    HttpResponse::InternalServerError().finish()
+/// Paths with an invalid part id are of a part that doesn't exist
+fn part_path_config() -> web::PathConfig {
+    web::PathConfig::default()
+        .error_handler(|_, _| ApiError::not_found("Part doesn't exist").into())
 }
 
-async fn create_db_connection() -> anyhow::Result<Arc<Mutex<DB>>> {
+async fn create_db_connection() -> anyhow::Result<Arc<DB>> {
     let database = env::var("PCPC_DATABASE_URL").unwrap_or("file://database.db".to_string());
     println!("Database URL: {}", database);
     let datastore = Datastore::new(&database).await?;
     let session = Session::for_db("my_ns", "my_db");
 
-    Ok(Arc::new(Mutex::new(DB {
+    Ok(Arc::new(DB {
         datastore,
         session,
-    })))
-}
-
-async fn create_part_raw(part_props: &DBPartProps, db: &Data<Mutex<DB>>) -> anyhow::Result<()> {
-    let sql = "CREATE part CONTENT $props";
-    let vars: BTreeMap<String, Value> = [
-        ("props".into(), part_props.to_owned().into()),
-    ].into();
-
-    let db_locked = db.lock().await;
-    let response = db_locked.datastore.execute(sql, &db_locked.session, Some(vars), false).await?;
-    drop(db_locked);
-
-    if let Some(first) = response.first() {
-        match &first.result {
-            Ok(_) => { return Ok(()) },
-            Err(error) => { return Err(anyhow!("DB error: {}", error.to_string())); },
-        }
-    }
-
-    Ok(())
-}
-
-async fn check_credentials(username: &str, password: &str, db: &Data<Mutex<DB>>) -> anyhow::Result<bool> {
-    let sql = "SELECT * FROM user WHERE username = $username AND password = $password";
-    let vars: BTreeMap<String, Value> = [
-        ("username".into(), username.into()),
-        ("password".into(), password.into()),
-    ].into();
-
-    let db_locked = db.lock().await;
-    let response = db_locked.datastore.execute(sql, &db_locked.session, Some(vars), true).await?;
-    drop(db_locked);
-
-    let response = response.first();
-
-    if let Some(response) = response {
-        if let Ok(result) = &response.result {
-            match result {
-                Value::Array(array) => {
-                    if !array.is_empty() {
-                        return Ok(true);
-                    }
-
-                    return Ok(false);
-                },
-                _ => return Ok(false)
-            }
-        }
-    }
-
-    Ok(false)
+    }))
 }
 
-async fn create_part(auth: BasicAuth, part_props: web::Json<DBPartProps>, db: Data<Mutex<DB>>) -> impl Responder {
-    let mut authenticated = false;
-    let user = auth.user_id();
-    let password = auth.password();
-    if let Some(password) = password {
-        let result = check_credentials(user, password, &db).await;
-        if let Ok(correct_credentials) = result {
-            if correct_credentials {
-                authenticated = true;
-            }
-        } else {
-            return HttpResponse::InternalServerError()
-                .finish();
-        }
-    }
-
-    if authenticated {
-        let result = create_part_raw(&part_props.0, &db).await;
-
-        return match result {
-            Ok(_) => HttpResponse::Ok().finish(),
-            Err(_) => HttpResponse::InternalServerError().finish(),
-        };
-    }
-
-    HttpResponse::Unauthorized()
-        .insert_header(WwwAuthenticate::<Basic>(Basic::with_realm("Admin rights")))
-        .finish()
+fn create_app(
+    db: Arc<DB>,
+) -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Response = ServiceResponse<impl MessageBody>,
+        Config = (),
+        InitError = (),
+        Error = Error,
+    >,
+> {
+    let parts = SurrealParts::new(Data::from(db.clone()));
+    create_app_with(db, parts)
 }
 
-fn create_app(
-    db: Arc<Mutex<DB>>,
+/// App with parts stored in the repository, other records are always in the database
+fn create_app_with<R: PartRepository>(
+    db: Arc<DB>,
+    parts: R,
 ) -> App<
     impl ServiceFactory<
         ServiceRequest,
@@ -185,14 +109,15 @@ fn create_app(
         .wrap(
             Cors::default()
                 .allowed_origin(&allowed_origin)
-                .allowed_methods(vec!["GET", "POST", "OPTIONS"])
+                .allowed_methods(vec!["GET", "POST", "DELETE", "OPTIONS"])
                 .allowed_headers(vec![header::AUTHORIZATION, header::ACCEPT])
                 .allowed_header(header::CONTENT_TYPE)
                 .supports_credentials()
                 .max_age(3600)
         )
-        .app_data(web::JsonConfig::default().limit(4096))
+        .app_data(json_config(4096))
         .app_data(Data::from(db))
+        .app_data(Data::new(parts))
         .service(
             web::scope("/api")
                 .service(
@@ -203,114 +128,156 @@ fn create_app(
                     web::scope("/part")
                         .service(
                             web::resource("")
-                                .route(web::post().to(part)),
+                                .route(web::post().to(parts::part::<R>)),
+                        )
+                        .service(
+                            web::resource("/create") .route(web::post().to(parts::create_part::<R>)),)
+                        .service(
+                            web::resource("/values")
+                                .route(web::post().to(parts::property_values::<R>)),
+                        )
+                        .service(
+                            web::resource("/update")
+                                .route(web::post().to(parts::update_part::<R>)),
+                        )
+                        .service(
+                            web::resource("/patch")
+                                .route(web::post().to(parts::patch_part::<R>)),
+                        )
+                        .service(
+                            web::resource("/delete")
+                                .route(web::post().to(parts::delete_part::<R>)),
+                        )
+                        .service(
+                            web::resource("/history")
+                                .route(web::post().to(audit::part_history)),
+                        )
+                        .service(
+                            web::resource("/export")
+                                .route(web::post().to(parts::export_parts::<R>)),
+                        )
+                        .service(
+                            web::resource("/import")
+                                .app_data(json_config(import::IMPORT_LIMIT))
+                                .route(web::post().to(import::import::<R>)),
+                        )
+                        .service(
+                            web::resource("/{id}/reviews")
+                                .app_data(part_path_config())
+                                .route(web::get().to(reviews::reviews::<R>))
+                                .route(web::post().to(reviews::submit_review::<R>))
+                                .route(web::delete().to(reviews::delete_review::<R>)),
+                        )
+                )
+                .service(
+                    web::resource("/compatibility")
+                        .route(web::post().to(parts::compatibility::<R>)),
+                )
+                .service(
+                    web::scope("/auth")
+                        .service(
+                            web::resource("/login")
+                                .route(web::post().to(auth::login)),
+                        )
+                        .service(
+                            web::resource("/logout")
+                                .route(web::post().to(auth::logout)),
+                        )
+                        .service(
+                            web::resource("/me")
+                                .route(web::post().to(auth::me)),
+                        )
+                )
+                .service(
+                    web::scope("/user")
+                        .service(
+                            web::resource("")
+                                .route(web::post().to(users::users)),
+                        )
+                        .service(
+                            web::resource("/create")
+                                .route(web::post().to(users::create_user)),
+                        )
+                        .service(
+                            web::resource("/disable")
+                                .route(web::post().to(users::disable_user)),
+                        )
+                        .service(
+                            web::resource("/reset")
+                                .route(web::post().to(users::reset_password)),
+                        )
+                )
+                .service(
+                    web::scope("/build")
+                        .service(
+                            web::resource("")
+                                .route(web::post().to(builds::build)),
+                        )
+                        .service(
+                            web::resource("/create")
+                                .route(web::post().to(builds::create_build)),
+                        )
+                        .service(
+                            web::resource("/update")
+                                .route(web::post().to(builds::update_build)),
+                        )
+                        .service(
+                            web::resource("/delete")
+                                .route(web::post().to(builds::delete_build)),
+                        )
+                        .service(
+                            web::resource("/export")
+                                .route(web::post().to(builds::export_build::<R>)),
+                        )
+                        .service(
+                            web::resource("/suggest")
+                                .route(web::post().to(builds::suggest_build::<R>)),
+                        )
+                )
+                .service(
+                    web::scope("/price")
+                        .service(
+                            web::resource("/add")
+                                .route(web::post().to(prices::add_price)),
+                        )
+                        .service(
+                            web::resource("/history")
+                                .route(web::post().to(prices::price_history)),
+                        )
+                        .service(
+                            web::resource("/lowest")
+                                .route(web::post().to(prices::lowest_price)),
+                        )
+                )
+                .service(
+                    web::scope("/favorites")
+                        .service(
+                            web::resource("")
+                                .route(web::post().to(favorites::favorites)),
+                        )
+                        .service(
+                            web::resource("/add")
+                                .route(web::post().to(favorites::add_favorite)),
                         )
                         .service(
-                            web::resource("/create") .route(web::post().to(create_part)),)
+                            web::resource("/remove")
+                                .route(web::post().to(favorites::remove_favorite)),
+                        )
                 )
         )
 }
 
-async fn put_temp_data_to_db(db: Arc<Mutex<DB>>) -> anyhow::Result<()> {
-    let db = Data::from(db);
-
-    let temp_parts: Vec<DBPartProps> = vec![
-        DBPartProps { 
-            name: "Monitor".into(),
-            ..Default::default()
-        },
-        DBPartProps { 
-            name: "GPU".into(),
-            ..Default::default()
-        },
-        DBPartProps { 
-            name: "CPU".into(),
-            ..Default::default()
-        },
-        DBPartProps { 
-            name: "Power Supply".into(),
-            ..Default::default()
-        },
-        DBPartProps { 
-            name: "RAM".into(),
-            ..Default::default()
-        },
-        DBPartProps { 
-            name: "SSD".into(),
-            ..Default::default()
-        },
-        DBPartProps { 
-            name: "HDD".into(),
-            ..Default::default()
-        },
-        DBPartProps { 
-            name: "Motherboard".into(),
-            ..Default::default()
-        },
-        DBPartProps { 
-            name: "Intel Core i5-13500".into(), 
-            image_url: "https://www.intel.com/content/dam/www/central-libraries/xa/en/images/intel-core-i5-badge-1440x1080.png.rendition.intel.web.64.64.png".into(), 
-            model: "i5-13500".into(), 
-            manufactuer: "Intel".into(), 
-            release_date: "22Q4".to_string(), 
-            rating: 3.5.into(), 
-            category: PartsCategory::CPU(CPUProperties { 
-                cores: 14, 
-                threads: 20, 
-                max_frequency: "4.80 GHz".to_string(),
-                base_frequency: "1.80 GHz".to_string(),
-                max_tdp: "154 W".to_string(),
-                base_tdp: "65 W".to_string(),
-                cache: "24 MB".to_string(),
-                max_ram_size: "128 GB".to_string(),
-                max_memory_channels: 2, 
-                ecc_memory_supported: true, 
-                max_pcie_lanes: 20, 
-                max_supported_pcie_version: "5.0".to_string(), 
-                socket: "FCLGA1700".into(), 
-                max_temperature: "100 C".to_string(),
-            }),
-        },
-        DBPartProps { 
-            name: "Intel Core i5-12500".into(), 
-            image_url: "https://www.intel.com/content/dam/www/central-libraries/xa/en/images/intel-core-i5-badge-1440x1080.png.rendition.intel.web.64.64.png".into(), 
-            model: "i5-12500".into(), 
-            manufactuer: "Intel".into(), 
-            release_date: "21Q4".to_string(), 
-            rating: 2.5.into(), 
-            category: PartsCategory::CPU(CPUProperties { 
-                cores: 6, 
-                threads: 12, 
-                max_frequency: "3.80 GHz".to_string(),
-                base_frequency: "1.80 GHz".to_string(),
-                max_tdp: "54 W".to_string(),
-                base_tdp: "54 W".to_string(),
-                cache: "12 MB".to_string(),
-                max_ram_size: "128 GB".to_string(),
-                max_memory_channels: 2, 
-                ecc_memory_supported: true, 
-                max_pcie_lanes: 20, 
-                max_supported_pcie_version: "4.0".to_string(), 
-                socket: "FCLGA1700".into(), 
-                max_temperature: "100 C".to_string(),
-            }),
-        }
-    ];
-
-    for temp_part in temp_parts {
-        create_part_raw(&temp_part, &db).await?;
-    }
-
-    let sql = "CREATE user SET username = 'Admin', password = 'admin'";
-    let db_locked = db.lock().await;
-    let responses = db_locked.datastore.execute(sql, &db_locked.session, None, false).await?;
-    drop(db_locked);
+/// Runs the HTTP server until it's stopped
+pub async fn serve(db: Arc<DB>) -> anyhow::Result<()> {
+    let ip = env::var("PCPC_IP").unwrap_or("127.0.0.1:8088".to_string());
 
-    for response in responses {
-        if response.output().is_err() {
-            bail!("Failed to create temp user!");
-        }
-    }
+    println!("Starting server on: {}", ip);
+    HttpServer::new(move || {
+        create_app(db.clone())
+    })
+    .bind(ip)?
+    .run()
+    .await?;
 
     Ok(())
 }
@@ -318,42 +285,76 @@ async fn put_temp_data_to_db(db: Arc<Mutex<DB>>) -> anyhow::Result<()> {
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     env_logger::init();
-    let ip = env::var("PCPC_IP").unwrap_or("127.0.0.1:8088".to_string());
+    let cli = Cli::parse();
     println!("Creating connection to DB...");
     let db = create_db_connection().await?;
     println!("Created!");
-    #[cfg(debug_assertions)]
-    {
-        println!("Putting TEMP data into DB...");
-        put_temp_data_to_db(db.clone()).await?;
-        println!("Done!");
-    }
-
-    println!("Starting server on: {}", ip);
-    HttpServer::new(move || {
-        create_app(db.clone())
-    })
-    .bind(ip)?
-    .run()
-    .await?;
 
-    Ok(())
+    cli::run(cli.command.unwrap_or(Command::Serve), db).await
 }
 
 #[cfg(test)]
 mod tests {
     use actix_web::{test, dev::Service, http::{self, StatusCode}};
-    use common::DBPart;
+    use std::collections::BTreeMap;
+
+    use common::{DBPart, DBPartProps, GetCompatibilityProps, GetPartProps, PartsCategory, CPUProperties, RAMProperties, MotherboardProperties, user::{CreateUserProps, Role, User, LoginResponse}, error::FieldError, import::{ImportFormat, ImportPartsProps, ImportReport}, export::{ExportPartsProps, ExportBuildProps}, price::{AddPriceProps, Currency, Price}, review::{PartReviews, SubmitReviewProps, average_rating}, optimizer::{BuildSuggestion, SuggestBuildProps, UseCase}, audit::{AuditAction, AuditRecord, UpdatePartProps}, types::PartId, query::{PartsPage, GetPropertyValuesProps, PropertyFilter, FilterCondition, SortBy, SortDirection}, compatibility::CompatibilityIssue, build::{DBBuild, DBBuildProps, BuildSlots, UpdateBuildProps, GetBuildProps, DeleteBuildProps}};
+    use rand::{Rng, seq::SliceRandom};
+    use surrealdb::sql::Value;
     use super::*;
+    use crate::{parts::QueryError, repository::memory::MemoryParts};
+
+    async fn create_part_raw(part_props: &DBPartProps, db: &Data<DB>) -> anyhow::Result<()> {
+        let sql = "CREATE part CONTENT $props";
+        let vars: BTreeMap<String, Value> = [
+            ("props".into(), parts::part_content(part_props)?),
+        ].into();
+
+        let response = db.datastore.execute(sql, &db.session, Some(vars), false).await?;
 
-    async fn create_local_db() -> anyhow::Result<Arc<Mutex<DB>>> {
+        if let Some(first) = response.first() {
+            match &first.result {
+                Ok(_) => { return Ok(()) },
+                Err(error) => { return Err(anyhow::anyhow!("DB error: {}", error.to_string())); },
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn create_empty_db() -> anyhow::Result<Arc<DB>> {
         let datastore = Datastore::new("memory").await?;
         let session = Session::for_db("my_ns", "my_db");
 
-        Ok(Arc::new(Mutex::new(DB { 
+        Ok(Arc::new(DB { 
             datastore, 
             session,
-        })))
+        }))
+    }
+
+    /// Migrated database with the `Admin` user. Mutations of parts require a logged in user,
+    /// so tests creating parts through the API log in as them, see [`login`].
+    async fn create_local_db() -> anyhow::Result<Arc<DB>> {
+        let db = create_empty_db().await?;
+        migrations::migrate(&Data::from(db.clone())).await?;
+
+        let admin = CreateUserProps {
+            username: "Admin".into(),
+            password: "admin".into(),
+            role: Role::Admin,
+        };
+        users::create_user_raw(&admin, &Data::from(db.clone())).await?;
+
+        Ok(db)
+    }
+
+    /// `Authorization` header of a new session of the user
+    async fn login(db: &Arc<DB>, username: &str, password: &str) -> (header::HeaderName, String) {
+        let data = Data::from(db.clone());
+        let user = users::check_credentials(username, password, &data).await.unwrap().unwrap();
+        let session = auth::create_session(user, &data).await.unwrap();
+
+        (header::AUTHORIZATION, format!("Bearer {}", session.token))
     }
 
     #[actix_web::test]
@@ -374,8 +375,9 @@ mod tests {
                     image_url: "".into(),
                     model: "Some model".into(),
                     manufactuer: "AOC".into(),
-                    release_date: "23Q1".to_string(),
+                    release_date: "2023Q1".parse().ok(),
                     rating: 4.5.into(),
+                    review_count: 0,
                     category: PartsCategory::Basic,
                 };
         let json = serde_json::to_value(&json).unwrap();
@@ -384,6 +386,7 @@ mod tests {
         let request = 
             test::TestRequest::post()
                 .uri("/api/part/create")
+                .insert_header(login(&db, "Admin", "admin").await)
                 .set_json(json);
 
         let response = test::call_service(&app, request.to_request()).await;
@@ -403,13 +406,15 @@ mod tests {
                     GetPartProps {
                         id: None,
                         limit: 1,
+                        ..Default::default()
                     }
                 );
 
-        let response: Vec<DBPart> = test::call_and_read_body_json(&app, request.to_request()).await;
-        assert!(response.len() == 1);
+        let response: PartsPage = test::call_and_read_body_json(&app, request.to_request()).await;
+        assert!(response.parts.len() == 1);
+        assert!(response.total == 1);
 
-        let first_part = response.first().unwrap();
+        let first_part = response.parts.first().unwrap();
         assert!(part_name == first_part.name);
 
         let request = 
@@ -417,8 +422,9 @@ mod tests {
                 .uri("/api/part")
                 .set_json(
                     GetPartProps {
-                        id: Some(first_part.id.clone()),
+                        id: Some(first_part.id.parse().unwrap()),
                         limit: 1,
+                        ..Default::default()
                     }
                 );
 
@@ -426,6 +432,1398 @@ mod tests {
         assert!(*first_part == response);
     }
 
+    #[actix_web::test]
+    async fn test_compatibility() {
+        let db = create_local_db().await.unwrap();
+        let data = Data::from(db.clone());
+
+        let cpu = DBPartProps {
+            name: "CPU".into(),
+            category: PartsCategory::CPU(CPUProperties {
+                socket: "FCLGA1700".into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let motherboard = DBPartProps {
+            name: "Motherboard".into(),
+            category: PartsCategory::Motherboard(MotherboardProperties {
+                socket: "AM5".into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        create_part_raw(&cpu, &data).await.unwrap();
+        create_part_raw(&motherboard, &data).await.unwrap();
+
+        let app =
+            test::init_service(create_app(db.clone()))
+            .await;
+
+        let request = 
+            test::TestRequest::post()
+                .uri("/api/part")
+                .set_json(
+                    GetPartProps {
+                        id: None,
+                        limit: 10,
+                        ..Default::default()
+                    }
+                );
+
+        let page: PartsPage = test::call_and_read_body_json(&app, request.to_request()).await;
+        let ids: Vec<String> = page.parts.iter().map(|x| x.id.clone()).collect();
+
+        let request = 
+            test::TestRequest::post()
+                .uri("/api/compatibility")
+                .set_json(GetCompatibilityProps { ids });
+
+        let issues: Vec<CompatibilityIssue> = test::call_and_read_body_json(&app, request.to_request()).await;
+        assert_eq!(issues.len(), 1);
+        assert!(matches!(issues[0], CompatibilityIssue::SocketMismatch { .. }));
+    }
+
+    #[actix_web::test]
+    async fn test_part_query() {
+        let db = create_local_db().await.unwrap();
+        cli::seed(include_str!("../fixtures/dev.json"), &Data::from(db.clone())).await.unwrap();
+
+        let app =
+            test::init_service(create_app(db.clone()))
+            .await;
+
+        let query = |props: GetPartProps| test::TestRequest::post()
+            .uri("/api/part")
+            .set_json(props)
+            .to_request();
+
+        let page: PartsPage = test::call_and_read_body_json(&app, query(GetPartProps {
+            limit: 1,
+            category: Some("CPU".into()),
+            sort: Some(SortBy { key: "max_frequency".into(), direction: SortDirection::Descending }),
+            ..Default::default()
+        })).await;
+        assert_eq!(page.total, 2);
+        assert_eq!(page.parts.len(), 1);
+        assert_eq!(page.parts[0].name, "Intel Core i5-13500");
+
+        let page: PartsPage = test::call_and_read_body_json(&app, query(GetPartProps {
+            limit: 10,
+            offset: 1,
+            category: Some("Storage".into()),
+            sort: Some(SortBy { key: "capacity".into(), direction: SortDirection::Ascending }),
+            ..Default::default()
+        })).await;
+        assert_eq!(page.total, 2);
+        assert_eq!(page.parts.len(), 1);
+        assert_eq!(page.parts[0].name, "Seagate BarraCuda 2TB");
+
+        let page: PartsPage = test::call_and_read_body_json(&app, query(GetPartProps {
+            limit: 10,
+            query: Some("intel".into()),
+            filters: vec![
+                PropertyFilter {
+                    key: "cores".into(),
+                    condition: FilterCondition::Range { min: Some(8.0), max: None },
+                },
+                PropertyFilter {
+                    key: "socket".into(),
+                    condition: FilterCondition::Equals("FCLGA1700".into()),
+                },
+            ],
+            ..Default::default()
+        })).await;
+        assert_eq!(page.total, 1);
+        assert_eq!(page.parts[0].model, "i5-13500");
+
+        let page: PartsPage = test::call_and_read_body_json(&app, query(GetPartProps {
+            limit: 10,
+            filters: vec![
+                PropertyFilter {
+                    key: "max_tdp".into(),
+                    condition: FilterCondition::OneOf(vec!["54 W".into()]),
+                },
+            ],
+            ..Default::default()
+        })).await;
+        assert_eq!(page.total, 1);
+        assert_eq!(page.parts[0].model, "i5-12500");
+
+        let page: PartsPage = test::call_and_read_body_json(&app, query(GetPartProps {
+            limit: 10,
+            query: Some("nothing like this".into()),
+            ..Default::default()
+        })).await;
+        assert_eq!(page.total, 0);
+        assert!(page.parts.is_empty());
+
+        let request = 
+            test::TestRequest::post()
+                .uri("/api/part/values")
+                .set_json(GetPropertyValuesProps { category: Some("Storage".into()), key: "storage_type".into() });
+
+        let values: Vec<serde_json::Value> = test::call_and_read_body_json(&app, request.to_request()).await;
+        assert_eq!(values, vec![serde_json::json!("HDD"), serde_json::json!("SSD")]);
+
+        let page: PartsPage = test::call_and_read_body_json(&app, query(GetPartProps {
+            limit: 10,
+            filters: vec![
+                PropertyFilter {
+                    key: "ecc_memory_supported".into(),
+                    condition: FilterCondition::Equals(true.into()),
+                },
+            ],
+            ..Default::default()
+        })).await;
+        assert_eq!(page.total, 2);
+
+        // Released since 2022, newest first
+        let page: PartsPage = test::call_and_read_body_json(&app, query(GetPartProps {
+            limit: 10,
+            category: Some("CPU".into()),
+            sort: Some(SortBy { key: "release_date".into(), direction: SortDirection::Descending }),
+            ..Default::default()
+        })).await;
+        assert_eq!(names(&page), ["Intel Core i5-13500", "Intel Core i5-12500"]);
+
+        let page: PartsPage = test::call_and_read_body_json(&app, query(GetPartProps {
+            limit: 10,
+            filters: vec![
+                PropertyFilter {
+                    key: "release_date".into(),
+                    condition: FilterCondition::Range { min: Some(20220101.0), max: None },
+                },
+            ],
+            ..Default::default()
+        })).await;
+        assert_eq!(page.total, 2);
+        assert!(page.parts.iter().all(|x| x.release_date == "2022Q4".parse().ok()));
+
+        let response = test::call_service(&app, query(GetPartProps {
+            limit: 10,
+            sort: Some(SortBy { key: "id".into(), direction: SortDirection::Ascending }),
+            ..Default::default()
+        })).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_part_crud() {
+        let db = create_local_db().await.unwrap();
+        let app =
+            test::init_service(create_app(db.clone()))
+            .await;
+
+        let admin = login(&db, "Admin", "admin").await;
+        let post = |uri: &str, json: serde_json::Value| test::TestRequest::post()
+            .uri(uri)
+            .insert_header(admin.clone())
+            .set_json(json)
+            .to_request();
+
+        let props = DBPartProps {
+            name: "Intel Core i5-13500".into(),
+            model: "i5-13500".into(),
+            manufactuer: "Itnel".into(),
+            category: PartsCategory::CPU(CPUProperties {
+                cores: 14,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let response = test::call_service(&app, post("/api/part/create", serde_json::to_value(&props).unwrap())).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let page: PartsPage = test::call_and_read_body_json(&app, post("/api/part", serde_json::json!({ "id": null, "limit": 1 }))).await;
+        let id = page.parts[0].id.clone();
+
+        let part: DBPart = test::call_and_read_body_json(&app, post("/api/part/patch", serde_json::json!({
+            "id": id,
+            "changes": { "manufactuer": "Intel", "category": { "CPU": { "cores": 16 } } },
+        }))).await;
+        assert_eq!(part.manufactuer, "Intel");
+        assert_eq!(part.model, "i5-13500");
+        assert!(matches!(part.category, PartsCategory::CPU(CPUProperties { cores: 16, .. })));
+
+        // Patched part is still found by its indexed properties
+        let page: PartsPage = test::call_and_read_body_json(&app, post("/api/part", serde_json::to_value(GetPartProps {
+            limit: 10,
+            filters: vec![PropertyFilter { key: "cores".into(), condition: FilterCondition::Equals(16.into()) }],
+            ..Default::default()
+        }).unwrap())).await;
+        assert_eq!(page.total, 1);
+
+        let mut updated = props.clone();
+        updated.model = "i5-13600".into();
+        let part: DBPart = test::call_and_read_body_json(&app, post("/api/part/update", serde_json::to_value(UpdatePartProps {
+            id: id.parse().unwrap(),
+            props: updated,
+        }).unwrap())).await;
+        assert_eq!(part.model, "i5-13600");
+        assert_eq!(part.manufactuer, "Itnel");
+
+        let response = test::call_service(&app, post("/api/part/patch", serde_json::json!({
+            "id": id,
+            "changes": { "rating": [] },
+        }))).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = test::call_service(&app, post("/api/part/delete", serde_json::json!({ "id": id }))).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        for uri in ["/api/part/delete", "/api/part/patch", "/api/part/update"] {
+            let response = test::call_service(&app, post(uri, serde_json::json!({ "id": id, "changes": {}, "props": props }))).await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+            let error: ApiError = test::read_body_json(response).await;
+            assert_eq!(error, ApiError::not_found("Part doesn't exist"));
+        }
+
+        // Bodies that can't be read are validation errors too
+        let response = test::call_service(&app, post("/api/part/delete", serde_json::json!({ "part": id }))).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let error: ApiError = test::read_body_json(response).await;
+        assert!(matches!(error, ApiError::Validation { .. }));
+
+        let history: Vec<AuditRecord> = test::call_and_read_body_json(&app, post("/api/part/history", serde_json::json!({ "id": id }))).await;
+        let actions: Vec<AuditAction> = history.iter().map(|x| x.action).collect();
+        assert_eq!(actions, vec![AuditAction::Create, AuditAction::Update, AuditAction::Update, AuditAction::Delete]);
+        assert!(history.iter().all(|x| x.user == "Admin" && x.part == id));
+
+        let keys: Vec<&str> = history[1].changes.iter().map(|x| x.key.as_str()).collect();
+        assert_eq!(keys, vec!["category.CPU.cores", "manufactuer"]);
+
+        let request = test::TestRequest::post()
+            .uri("/api/part/delete")
+            .set_json(serde_json::json!({ "id": id }))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn test_part_validation() {
+        let db = create_local_db().await.unwrap();
+        let app =
+            test::init_service(create_app_with(db.clone(), MemoryParts::default()))
+            .await;
+
+        let admin = login(&db, "Admin", "admin").await;
+        let post = |uri: &str, json: serde_json::Value| test::TestRequest::post()
+            .uri(uri)
+            .insert_header(admin.clone())
+            .set_json(json)
+            .to_request();
+        let invalid_fields = |error: ApiError| -> Vec<String> {
+            error.fields().iter().map(|x| x.field.clone()).collect()
+        };
+
+        let props = DBPartProps {
+            name: "".into(),
+            rating: 57.0.into(),
+            category: PartsCategory::CPU(CPUProperties {
+                cores: 14,
+                threads: 8,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let response = test::call_service(&app, post("/api/part/create", serde_json::to_value(&props).unwrap())).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let error: ApiError = test::read_body_json(response).await;
+        assert_eq!(invalid_fields(error), ["name", "rating", "threads"]);
+
+        let props = DBPartProps {
+            name: "Intel Core i5-13500".into(),
+            release_date: "2022Q4".parse().ok(),
+            rating: 3.5.into(),
+            category: PartsCategory::CPU(CPUProperties {
+                cores: 14,
+                threads: 20,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let part: DBPart = test::call_and_read_body_json(&app, post("/api/part/create", serde_json::to_value(&props).unwrap())).await;
+
+        let response = test::call_service(&app, post("/api/part/patch", serde_json::json!({
+            "id": part.id,
+            "changes": { "category": { "CPU": { "cores": 24 } } },
+        }))).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let error: ApiError = test::read_body_json(response).await;
+        assert_eq!(error, ApiError::validation(vec![FieldError::new("threads", "must be at least the number of cores")]));
+
+        let mut updated = props.clone();
+        updated.rating = (-1.0).into();
+        let response = test::call_service(&app, post("/api/part/update", serde_json::to_value(UpdatePartProps {
+            id: part.id.parse().unwrap(),
+            props: updated,
+        }).unwrap())).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        // Rejected changes leave the part as it was
+        let stored: DBPart = test::call_and_read_body_json(&app, post("/api/part", serde_json::json!({ "id": part.id, "limit": 1 }))).await;
+        assert_eq!(stored, part);
+    }
+
+    /// Parts of the development fixture
+    fn fixture_rows() -> import::Rows {
+        let fixture: serde_json::Value = serde_json::from_str(include_str!("../fixtures/dev.json")).unwrap();
+        import::json_rows(fixture["parts"].as_array().unwrap().clone())
+    }
+
+    fn names(page: &PartsPage) -> Vec<&str> {
+        page.parts.iter().map(|x| x.name.as_str()).collect()
+    }
+
+    #[actix_web::test]
+    async fn test_memory_repository() {
+        let db = create_local_db().await.unwrap();
+        let surreal = SurrealParts::new(Data::from(db.clone()));
+        let memory = MemoryParts::default();
+        import::import_rows(fixture_rows(), false, "test", &surreal).await.unwrap();
+        import::import_rows(fixture_rows(), false, "test", &memory).await.unwrap();
+
+        // Both repositories answer every query the same way
+        let filter = |key: &str, condition| PropertyFilter { key: key.into(), condition };
+        let sort = |key: &str, direction| Some(SortBy { key: key.into(), direction });
+        let queries = vec![
+            GetPartProps { limit: 100, ..Default::default() },
+            GetPartProps { limit: 3, offset: 2, ..Default::default() },
+            GetPartProps { limit: 100, category: Some("Basic".into()), ..Default::default() },
+            GetPartProps { limit: 100, category: Some("Storage".into()), sort: sort("capacity", SortDirection::Descending), ..Default::default() },
+            GetPartProps { limit: 100, query: Some("INTEL".into()), sort: sort("max_frequency", SortDirection::Ascending), ..Default::default() },
+            GetPartProps { limit: 100, filters: vec![filter("rating", FilterCondition::Range { min: Some(4.0), max: None })], ..Default::default() },
+            GetPartProps { limit: 100, filters: vec![filter("socket", FilterCondition::Equals("FCLGA1700".into()))], ..Default::default() },
+            GetPartProps { limit: 100, filters: vec![filter("cores", FilterCondition::Equals(14.into()))], ..Default::default() },
+            GetPartProps { limit: 100, filters: vec![filter("memory_type", FilterCondition::OneOf(vec!["DDR5".into(), "GDDR6".into()]))], ..Default::default() },
+            GetPartProps { limit: 100, category: Some("CPU".into()), filters: vec![filter("max_frequency", FilterCondition::Range { min: None, max: Some(4e9) })], ..Default::default() },
+            GetPartProps { limit: 100, category: Some("CPU".into()), sort: sort("release_date", SortDirection::Descending), ..Default::default() },
+            GetPartProps { limit: 100, filters: vec![filter("release_date", FilterCondition::Range { min: Some(20210101.0), max: Some(20211231.0) })], ..Default::default() },
+            GetPartProps { limit: 100, filters: vec![filter("release_date", FilterCondition::Equals("2021Q4".into()))], ..Default::default() },
+        ];
+        for props in &queries {
+            let expected = surreal.page(props).await.unwrap();
+            let page = memory.page(props).await.unwrap();
+            assert_eq!(names(&page), names(&expected));
+            assert_eq!(page.total, expected.total);
+        }
+
+        for (category, key) in [(None, "memory_type"), (Some("CPU"), "cores"), (Some("Storage"), "storage_type")] {
+            let props = GetPropertyValuesProps { category: category.map(String::from), key: key.into() };
+            assert_eq!(memory.property_values(&props).await.unwrap(), surreal.property_values(&props).await.unwrap());
+        }
+
+        let props = GetPartProps { category: Some("Monitor".into()), ..Default::default() };
+        let error = memory.page(&props).await.unwrap_err();
+        assert_eq!(error.downcast::<QueryError>().unwrap(), QueryError::UnknownCategory("Monitor".into()));
+
+        // Handlers only use the repository
+        let app =
+            test::init_service(create_app_with(db.clone(), MemoryParts::default()))
+            .await;
+        let admin = login(&db, "Admin", "admin").await;
+        let post = |uri: &str, json: serde_json::Value| test::TestRequest::post()
+            .uri(uri)
+            .insert_header(admin.clone())
+            .set_json(json)
+            .to_request();
+
+        let props = DBPartProps { name: "Monitor".into(), ..Default::default() };
+        let created: DBPart = test::call_and_read_body_json(&app, post("/api/part/create", serde_json::to_value(&props).unwrap())).await;
+        let part: DBPart = test::call_and_read_body_json(&app, post("/api/part", serde_json::json!({ "id": created.id, "limit": 1 }))).await;
+        assert_eq!(part, created);
+
+        let part: DBPart = test::call_and_read_body_json(&app, post("/api/part/patch", serde_json::json!({ "id": created.id, "changes": { "model": "24G2" } }))).await;
+        assert_eq!(part.model, "24G2");
+
+        let response = test::call_service(&app, post("/api/part/delete", serde_json::json!({ "id": created.id }))).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = test::call_service(&app, post("/api/part", serde_json::json!({ "id": created.id, "limit": 1 }))).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // Parts of the database weren't touched
+        assert_eq!(surreal.page(&GetPartProps { limit: 100, ..Default::default() }).await.unwrap().total, 10);
+    }
+
+    /// Parts read per second by threads with their own runtime, like workers of the server
+    fn read_throughput<R: PartRepository + Send + Sync>(parts: &R, workers: usize, reads: usize) -> f64 {
+        let props = GetPartProps {
+            limit: 10,
+            category: Some("CPU".into()),
+            sort: Some(SortBy { key: "max_frequency".into(), direction: SortDirection::Descending }),
+            ..Default::default()
+        };
+
+        let start = std::time::Instant::now();
+        std::thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| {
+                    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+                    runtime.block_on(async {
+                        for _ in 0..reads {
+                            parts.page(&props).await.unwrap();
+                        }
+                    });
+                });
+            }
+        });
+
+        (workers * reads) as f64 / start.elapsed().as_secs_f64()
+    }
+
+    /// Run with `cargo test bench_part_reads -- --ignored --nocapture`
+    #[actix_web::test]
+    #[ignore]
+    async fn bench_part_reads() {
+        let db = create_local_db().await.unwrap();
+        let surreal = SurrealParts::new(Data::from(db.clone()));
+        let memory = MemoryParts::default();
+        for _ in 0..10 {
+            import::import_rows(fixture_rows(), false, "test", &surreal).await.unwrap();
+            import::import_rows(fixture_rows(), false, "test", &memory).await.unwrap();
+        }
+
+        for workers in [1, 4] {
+            println!(
+                "{} workers: {:.0} reads/s from SurrealDB, {:.0} reads/s from memory",
+                workers,
+                read_throughput(&surreal, workers, 20),
+                read_throughput(&memory, workers, 20),
+            );
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_malicious_part_ids() {
+        let db = create_local_db().await.unwrap();
+        cli::seed(include_str!("../fixtures/dev.json"), &Data::from(db.clone())).await.unwrap();
+        let app =
+            test::init_service(create_app(db.clone()))
+            .await;
+
+        let admin = login(&db, "Admin", "admin").await;
+        let post = |uri: &str, json: serde_json::Value| test::TestRequest::post()
+            .uri(uri)
+            .insert_header(admin.clone())
+            .set_json(json)
+            .to_request();
+
+        let all = serde_json::json!({ "id": null, "limit": 1000 });
+        let before: PartsPage = test::call_and_read_body_json(&app, post("/api/part", all.clone())).await;
+        assert!(before.total > 0);
+
+        let mut ids: Vec<String> = [
+            "",
+            "*",
+            "x; DELETE part",
+            "x; DELETE part;",
+            "`x`; DELETE part; --",
+            "⟨x⟩; REMOVE TABLE part",
+            "x WHERE true",
+            "part:x, part",
+            "part:*",
+            "part",
+            "x\n; DELETE part",
+            "x' OR '1' = '1",
+            "$part",
+            "type::thing('part', 'x')",
+        ].iter().map(|x| x.to_string()).collect();
+        ids.push("a".repeat(PartId::MAX_LEN + 1));
+        ids.push(format!("{}; DELETE part", before.parts[0].id));
+
+        // Random ids made mostly of characters with a meaning in SurrealQL
+        let alphabet: Vec<char> = "ab01_:;`'\"$*()[]{}⟨⟩<>=!,.-/\\ \n\tž🙂".chars().collect();
+        let mut rng = rand::thread_rng();
+        for _ in 0..200 {
+            let len = rng.gen_range(1..40);
+            ids.push((0..len).map(|_| *alphabet.choose(&mut rng).unwrap()).collect());
+        }
+
+        for id in &ids {
+            let valid = id.parse::<PartId>().is_ok();
+            for uri in ["/api/part", "/api/part/delete", "/api/part/history", "/api/part/patch"] {
+                let response = test::call_service(&app, post(uri, serde_json::json!({ "id": id, "limit": 10, "changes": {} }))).await;
+                let status = response.status();
+                match (valid, uri) {
+                    // Ids that pass validation can't match any generated id
+                    (true, "/api/part/history") => assert_eq!(status, StatusCode::OK, "{} {:?}", uri, id),
+                    (true, _) => assert_eq!(status, StatusCode::NOT_FOUND, "{} {:?}", uri, id),
+                    (false, _) => assert_eq!(status, StatusCode::BAD_REQUEST, "{} {:?}", uri, id),
+                }
+            }
+        }
+
+        let after: PartsPage = test::call_and_read_body_json(&app, post("/api/part", all)).await;
+        assert_eq!(after.total, before.total);
+        assert_eq!(after.parts, before.parts);
+    }
+
+    #[actix_web::test]
+    async fn test_import() {
+        let db = create_local_db().await.unwrap();
+        let app =
+            test::init_service(create_app(db.clone()))
+            .await;
+
+        let admin = login(&db, "Admin", "admin").await;
+        let post = |uri: &str, json: serde_json::Value| test::TestRequest::post()
+            .uri(uri)
+            .insert_header(admin.clone())
+            .set_json(json)
+            .to_request();
+        let count = || async {
+            let page: PartsPage = test::call_and_read_body_json(&app, post("/api/part", serde_json::json!({ "id": null, "limit": 100 }))).await;
+            page.total
+        };
+
+        // Bigger than the default body limit
+        let mut data = String::from("name,manufactuer,cores,threads,max_frequency,socket\n");
+        for index in 0..100 {
+            data += &format!("Ryzen 5 7600 #{},AMD,6,12,5.1 GHz,AM5\n", index);
+        }
+        let csv = |data: &str, dry_run: bool| ImportPartsProps {
+            format: ImportFormat::Csv,
+            category: Some("CPU".into()),
+            data: data.into(),
+            dry_run,
+        };
+
+        let report: ImportReport = test::call_and_read_body_json(&app, post("/api/part/import", serde_json::to_value(csv(&data, true)).unwrap())).await;
+        assert_eq!((report.rows, report.imported), (100, 0));
+        assert_eq!(count().await, 0);
+
+        // One invalid row fails the whole import
+        let invalid = format!("{}Ryzen,AMD,six,12,5.1 GHz,AM5\n", data);
+        let response = test::call_service(&app, post("/api/part/import", serde_json::to_value(csv(&invalid, false)).unwrap())).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let error: ApiError = test::read_body_json(response).await;
+        assert_eq!(error.fields(), [FieldError::new("row 101", "cores: six is not a number")]);
+        assert_eq!(count().await, 0);
+
+        let response = test::call_service(&app, post("/api/part/import", serde_json::to_value(csv("name,wattage\n", false)).unwrap())).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let report: ImportReport = test::call_and_read_body_json(&app, post("/api/part/import", serde_json::to_value(csv(&data, false)).unwrap())).await;
+        assert_eq!(report.imported, 100);
+
+        // Rating is computed from reviews, the one in the file is dropped
+        let parts = vec![DBPartProps { name: "Monitor".into(), rating: 4.5.into(), review_count: 3, ..Default::default() }];
+        let json = ImportPartsProps {
+            format: ImportFormat::Json,
+            category: None,
+            data: serde_json::to_string(&parts).unwrap(),
+            dry_run: false,
+        };
+        let report: ImportReport = test::call_and_read_body_json(&app, post("/api/part/import", serde_json::to_value(json).unwrap())).await;
+        assert_eq!(report.imported, 1);
+        assert_eq!(count().await, 101);
+
+        let page: PartsPage = test::call_and_read_body_json(&app, post("/api/part", serde_json::json!({ "id": null, "limit": 1, "query": "Monitor" }))).await;
+        assert_eq!((page.parts[0].rating.get(), page.parts[0].review_count), (0.0, 0));
+        let history: Vec<AuditRecord> = test::call_and_read_body_json(&app, post("/api/part/history", serde_json::json!({ "id": page.parts[0].id }))).await;
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].user, "Admin");
+
+        let viewer = CreateUserProps {
+            username: "Viewer".into(),
+            password: "viewer".into(),
+            role: Role::Viewer,
+        };
+        users::create_user_raw(&viewer, &Data::from(db.clone())).await.unwrap();
+        let request = test::TestRequest::post()
+            .uri("/api/part/import")
+            .insert_header(login(&db, "Viewer", "viewer").await)
+            .set_json(csv(&data, true))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[actix_web::test]
+    async fn test_export() {
+        let db = create_local_db().await.unwrap();
+        let app =
+            test::init_service(create_app(db.clone()))
+            .await;
+
+        let admin = login(&db, "Admin", "admin").await;
+        let post = |uri: &str, json: serde_json::Value| test::TestRequest::post()
+            .uri(uri)
+            .insert_header(admin.clone())
+            .set_json(json)
+            .to_request();
+
+        let data = Data::from(db.clone());
+        for (name, cores) in [("Ryzen 5 7600", 6), ("Ryzen 7 7700", 8), ("Core i5-13600K", 14)] {
+            let props = DBPartProps {
+                name: name.into(),
+                category: PartsCategory::CPU(CPUProperties {
+                    cores,
+                    socket: match name.starts_with("Ryzen") { true => "AM5", false => "FCLGA1700" }.into(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            create_part_raw(&props, &data).await.unwrap();
+        }
+
+        let query = GetPartProps {
+            limit: 1,
+            category: Some("CPU".into()),
+            filters: vec![PropertyFilter { key: "socket".into(), condition: FilterCondition::Equals("AM5".into()) }],
+            sort: Some(SortBy { key: "cores".into(), direction: SortDirection::Descending }),
+            ..Default::default()
+        };
+        let props = ExportPartsProps {
+            query,
+            columns: vec!["Socket".into(), "Cores".into()],
+            format: ExportFormat::Csv,
+        };
+        let response = test::call_service(&app, post("/api/part/export", serde_json::to_value(&props).unwrap())).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get(header::CONTENT_TYPE).unwrap(), "text/csv");
+        assert_eq!(response.headers().get(header::CONTENT_DISPOSITION).unwrap(), "attachment; filename=\"parts.csv\"");
+        // Limit of the query doesn't apply
+        let body = test::read_body(response).await;
+        assert_eq!(body, "Name,Price,Socket,Cores\nRyzen 7 7700,-,AM5,8\nRyzen 5 7600,-,AM5,6\n");
+
+        let page: PartsPage = test::call_and_read_body_json(&app, post("/api/part", serde_json::json!({ "id": null, "limit": 10, "query": "Ryzen 5" }))).await;
+        let ryzen = page.parts[0].id.clone();
+        let price = AddPriceProps {
+            part: ryzen.parse().unwrap(),
+            amount: 22990,
+            currency: Currency::EUR,
+            retailer: "Alza".into(),
+            url: String::new(),
+            observed_at: None,
+        };
+        test::call_service(&app, post("/api/price/add", serde_json::to_value(&price).unwrap())).await;
+
+        let build = DBBuildProps {
+            name: "Ryzen \"build\"".into(),
+            slots: BuildSlots { cpu: Some(ryzen), ..Default::default() },
+        };
+        let build: DBBuild = test::call_and_read_body_json(&app, post("/api/build/create", serde_json::to_value(&build).unwrap())).await;
+
+        let props = ExportBuildProps { id: build.id, format: ExportFormat::Markdown };
+        let response = test::call_service(&app, post("/api/build/export", serde_json::to_value(&props).unwrap())).await;
+        assert_eq!(response.headers().get(header::CONTENT_DISPOSITION).unwrap(), "attachment; filename=\"Ryzen _build_.md\"");
+        let body = test::read_body(response).await;
+        assert_eq!(body, "| Slot | Part | Manufacturer | Rating | Price |\n\
+            | --- | --- | --- | --- | --- |\n\
+            | CPU | Ryzen 5 7600 |  | 0 | 229.90 EUR |\n\
+            | Total | 1 part |  |  | 229.90 EUR |\n");
+
+        let props = ExportBuildProps { id: "missing".into(), format: ExportFormat::Json };
+        let response = test::call_service(&app, post("/api/build/export", serde_json::to_value(&props).unwrap())).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_users() {
+        let db = create_local_db().await.unwrap();
+        let data = Data::from(db.clone());
+        assert!(users::check_credentials("Admin", "admin", &data).await.unwrap().is_some());
+
+        let app =
+            test::init_service(create_app(db.clone()))
+            .await;
+
+        let post = |uri: &str, auth: &(header::HeaderName, String), json: serde_json::Value| test::TestRequest::post()
+            .uri(uri)
+            .insert_header(auth.clone())
+            .set_json(json)
+            .to_request();
+        let admin = &login(&db, "Admin", "admin").await;
+
+        let props = CreateUserProps {
+            username: "Viewer".into(),
+            password: "viewer".into(),
+            role: Role::Viewer,
+        };
+        let user: User = test::call_and_read_body_json(&app, post("/api/user/create", admin, serde_json::to_value(&props).unwrap())).await;
+        assert_eq!(user.username, "Viewer");
+        assert!(!user.disabled);
+        let viewer = &login(&db, "Viewer", "viewer").await;
+
+        let response = test::call_service(&app, post("/api/user/create", admin, serde_json::to_value(&props).unwrap())).await;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let response = test::call_service(&app, post("/api/user", viewer, serde_json::json!(null))).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let users: Vec<User> = test::call_and_read_body_json(&app, post("/api/user", admin, serde_json::json!(null))).await;
+        let names: Vec<&str> = users.iter().map(|x| x.username.as_str()).collect();
+        assert_eq!(names, vec!["Admin", "Viewer"]);
+
+        let response = test::call_service(&app, post("/api/user/reset", admin, serde_json::json!({ "id": user.id, "password": "secret" }))).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(users::check_credentials("Viewer", "viewer", &data).await.unwrap().is_none());
+        assert!(users::check_credentials("Viewer", "secret", &data).await.unwrap().is_some());
+        let viewer_reset = &login(&db, "Viewer", "secret").await;
+
+        let disabled: User = test::call_and_read_body_json(&app, post("/api/user/disable", admin, serde_json::json!({ "id": user.id, "disabled": true }))).await;
+        assert!(disabled.disabled);
+        let response = test::call_service(&app, post("/api/user", viewer_reset, serde_json::json!(null))).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = test::call_service(&app, post("/api/auth/login", admin, serde_json::json!({ "username": "Viewer", "password": "secret" }))).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = test::call_service(&app, post("/api/user/disable", admin, serde_json::json!({ "id": "missing", "disabled": true }))).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = test::call_service(&app, post("/api/user/create", admin, serde_json::json!({ "username": " ", "password": "", "role": "Viewer" }))).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let error: ApiError = test::read_body_json(response).await;
+        assert_eq!(error.fields(), [FieldError::new("username", "is required"), FieldError::new("password", "is required")]);
+
+        let response = test::call_service(&app, post("/api/user/reset", admin, serde_json::json!({ "id": user.id, "password": "" }))).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(users::check_credentials("Viewer", "secret", &data).await.unwrap().is_some());
+
+        let props = CreateUserProps {
+            username: "".into(),
+            password: "empty".into(),
+            role: Role::Viewer,
+        };
+        assert!(matches!(users::create_user_raw(&props, &data).await, Err(ApiError::Validation { .. })));
+    }
+
+    #[actix_web::test]
+    async fn test_check() {
+        let db = create_local_db().await.unwrap();
+        let data = Data::from(db.clone());
+        cli::seed(include_str!("../fixtures/dev.json"), &data).await.unwrap();
+        assert_eq!(check::check_integrity(&data).await.unwrap(), Vec::<String>::new());
+
+        let sql = "
+            CREATE part:kept SET name = 'Kept', image_url = '', model = '', manufactuer = '', rating = '0', category = 'Basic';
+            CREATE build:broken SET name = 'Broken', slots = { cpu: 'kept', gpu: 'deleted', ram: [], storage: [] };
+            CREATE price:orphan SET part = part:deleted, amount = 100, currency = 'EUR', retailer = '', url = '', observed_at = time::now();
+            UPDATE schema_version:current SET version = 1;
+        ";
+        db.datastore.execute(sql, &db.session, None, false).await.unwrap();
+
+        let problems = check::check_integrity(&data).await.unwrap();
+        assert_eq!(problems.len(), 3, "{:?}", problems);
+        assert_eq!(problems[0], format!("Schema is version 1, expected {}", migrations::SCHEMA_VERSION));
+        assert!(problems[1].starts_with("build:broken has missing part deleted"));
+        assert!(problems[2].starts_with("price:orphan is of missing part"));
+
+        migrations::migrate(&data).await.unwrap();
+        assert_eq!(check::check_integrity(&data).await.unwrap().len(), 2);
+    }
+
+    #[actix_web::test]
+    async fn test_migrations() {
+        let db = create_empty_db().await.unwrap();
+        let data = Data::from(db.clone());
+        migrations::check_schema(&data).await.unwrap();
+        assert_eq!(migrations::schema_version(&data).await.unwrap(), Some(migrations::SCHEMA_VERSION));
+
+        // Rows stored before passwords were hashed, before CPUs had most of their properties and while release dates were text
+        let db = create_empty_db().await.unwrap();
+        let data = Data::from(db.clone());
+        let sql = "
+            CREATE user SET username = 'Legacy', password = 'legacy';
+            CREATE part:ryzen SET name = 'Ryzen 7 2700', release_date = '18Q2', rating = '4', category = { CPU: { cores: 8, socket: 'AM4' } };
+            CREATE part:monitor SET name = 'Monitor', model = '', manufactuer = '', release_date = '', rating = '3', category = 'Basic';
+            CREATE part:banana SET name = 'Banana', model = '', manufactuer = '', release_date = 'banana', rating = '2', category = 'Basic';
+        ";
+        db.datastore.execute(sql, &db.session, None, false).await.unwrap();
+        assert!(users::check_credentials("Legacy", "legacy", &data).await.unwrap().is_none());
+        assert!(migrations::check_schema(&data).await.is_err());
+
+        let applied = migrations::migrate(&data).await.unwrap();
+        assert_eq!(applied.len(), migrations::SCHEMA_VERSION as usize);
+        assert!(migrations::migrate(&data).await.unwrap().is_empty());
+        migrations::check_schema(&data).await.unwrap();
+        assert_eq!(check::check_integrity(&data).await.unwrap(), Vec::<String>::new());
+
+        let legacy = users::check_credentials("Legacy", "legacy", &data).await.unwrap().unwrap();
+        assert_eq!(legacy.role, Role::Editor);
+
+        let app =
+            test::init_service(create_app(db.clone()))
+            .await;
+
+        let request = test::TestRequest::post()
+            .uri("/api/part")
+            .set_json(GetPartProps {
+                limit: 10,
+                category: Some("CPU".into()),
+                filters: vec![PropertyFilter { key: "cores".into(), condition: FilterCondition::Range { min: Some(8.0), max: Some(8.0) } }],
+                ..Default::default()
+            });
+        let page: PartsPage = test::call_and_read_body_json(&app, request.to_request()).await;
+        assert_eq!(page.total, 1);
+        assert!(matches!(&page.parts[0].category, PartsCategory::CPU(CPUProperties { cores: 8, threads: 0, .. })));
+        assert_eq!(page.parts[0].release_date, "2018Q2".parse().ok());
+
+        let request = test::TestRequest::post()
+            .uri("/api/part")
+            .set_json(GetPartProps { limit: 10, category: Some("Basic".into()), ..Default::default() });
+        let page: PartsPage = test::call_and_read_body_json(&app, request.to_request()).await;
+        assert_eq!(page.total, 2);
+        // Free text release date doesn't stop the migration, it's unknown like an empty one
+        assert!(page.parts.iter().all(|part| part.release_date.is_none()));
+        // Ratings entered before parts had reviews are dropped
+        assert!(page.parts.iter().all(|part| (part.rating.get(), part.review_count) == (0.0, 0)));
+
+        // Schema is enforced once migrated
+        let sql = "CREATE user SET username = 'Nobody', password = 'nobody'";
+        let response = db.datastore.execute(sql, &db.session, None, false).await.unwrap();
+        assert!(response.into_iter().next().unwrap().result.is_err());
+    }
+
+    #[actix_web::test]
+    async fn test_auth() {
+        let db = create_local_db().await.unwrap();
+        let app =
+            test::init_service(create_app(db.clone()))
+            .await;
+
+        let post = |uri: &str, token: Option<&str>, json: serde_json::Value| {
+            let mut request = test::TestRequest::post().uri(uri).set_json(json);
+            if let Some(token) = token {
+                request = request.insert_header((header::AUTHORIZATION, format!("Bearer {}", token)));
+            }
+            request.to_request()
+        };
+
+        let response = test::call_service(&app, post("/api/auth/login", None, serde_json::json!({ "username": "Admin", "password": "wrong" }))).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let session: LoginResponse = test::call_and_read_body_json(&app, post("/api/auth/login", None, serde_json::json!({ "username": "Admin", "password": "admin" }))).await;
+        assert_eq!(session.user.username, "Admin");
+        assert_eq!(session.user.role, Role::Admin);
+
+        let user: User = test::call_and_read_body_json(&app, post("/api/auth/me", Some(&session.token), serde_json::json!(null))).await;
+        assert_eq!(user, session.user);
+
+        let response = test::call_service(&app, post("/api/auth/me", Some("forged.token"), serde_json::json!(null))).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = test::call_service(&app, post("/api/part/delete", None, serde_json::json!({ "id": "missing" }))).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = test::call_service(&app, post("/api/auth/logout", Some(&session.token), serde_json::json!(null))).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = test::call_service(&app, post("/api/auth/me", Some(&session.token), serde_json::json!(null))).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn test_permissions() {
+        let db = create_local_db().await.unwrap();
+        let app =
+            test::init_service(create_app(db.clone()))
+            .await;
+
+        for (username, role) in [("Editor", Role::Editor), ("Viewer", Role::Viewer)] {
+            let props = CreateUserProps {
+                username: username.into(),
+                password: username.to_lowercase(),
+                role,
+            };
+            users::create_user_raw(&props, &Data::from(db.clone())).await.unwrap();
+        }
+        let editor = &login(&db, "Editor", "editor").await;
+        let viewer = &login(&db, "Viewer", "viewer").await;
+
+        let post = |uri: &str, auth: &(header::HeaderName, String), json: serde_json::Value| test::TestRequest::post()
+            .uri(uri)
+            .insert_header(auth.clone())
+            .set_json(json)
+            .to_request();
+
+        let props = serde_json::to_value(DBPartProps {
+            name: "Monitor".into(),
+            ..Default::default()
+        }).unwrap();
+        let response = test::call_service(&app, post("/api/part/create", viewer, props.clone())).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = test::call_service(&app, post("/api/part/create", editor, props)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let page: PartsPage = test::call_and_read_body_json(&app, post("/api/part", viewer, serde_json::json!({ "id": null, "limit": 1 }))).await;
+        let id = page.parts[0].id.clone();
+
+        let response = test::call_service(&app, post("/api/part/patch", editor, serde_json::json!({ "id": id, "changes": { "model": "24G2" } }))).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = test::call_service(&app, post("/api/part/delete", editor, serde_json::json!({ "id": id }))).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = test::call_service(&app, post("/api/user", editor, serde_json::json!(null))).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = test::call_service(&app, post("/api/part/history", viewer, serde_json::json!({ "id": id }))).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[actix_web::test]
+    async fn test_favorites() {
+        let db = create_local_db().await.unwrap();
+        let app =
+            test::init_service(create_app(db.clone()))
+            .await;
+
+        let viewer = CreateUserProps {
+            username: "Viewer".into(),
+            password: "viewer".into(),
+            role: Role::Viewer,
+        };
+        users::create_user_raw(&viewer, &Data::from(db.clone())).await.unwrap();
+        let viewer = &login(&db, "Viewer", "viewer").await;
+        let admin = &login(&db, "Admin", "admin").await;
+
+        let data = Data::from(db.clone());
+        for name in ["Monitor", "Keyboard"] {
+            let props = DBPartProps {
+                name: name.into(),
+                ..Default::default()
+            };
+            create_part_raw(&props, &data).await.unwrap();
+        }
+
+        let post = |uri: &str, auth: &(header::HeaderName, String), json: serde_json::Value| test::TestRequest::post()
+            .uri(uri)
+            .insert_header(auth.clone())
+            .set_json(json)
+            .to_request();
+
+        let page: PartsPage = test::call_and_read_body_json(&app, post("/api/part", viewer, serde_json::json!({ "id": null, "limit": 2 }))).await;
+        let ids: Vec<String> = page.parts.into_iter().map(|x| x.id).collect();
+
+        for id in ids.iter().chain(ids.iter()) {
+            let response = test::call_service(&app, post("/api/favorites/add", viewer, serde_json::json!({ "part": id }))).await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let favorites: Vec<String> = test::call_and_read_body_json(&app, post("/api/favorites", viewer, serde_json::json!(null))).await;
+        assert_eq!(favorites, ids);
+
+        // Favorites are per user
+        let favorites: Vec<String> = test::call_and_read_body_json(&app, post("/api/favorites", admin, serde_json::json!(null))).await;
+        assert!(favorites.is_empty());
+
+        let favorites: Vec<String> = test::call_and_read_body_json(&app, post("/api/favorites/remove", viewer, serde_json::json!({ "part": ids[0] }))).await;
+        assert_eq!(favorites, vec![ids[1].clone()]);
+
+        let response = test::call_service(&app, post("/api/part/delete", admin, serde_json::json!({ "id": ids[1] }))).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let favorites: Vec<String> = test::call_and_read_body_json(&app, post("/api/favorites", viewer, serde_json::json!(null))).await;
+        assert!(favorites.is_empty());
+
+        let response = test::call_service(&app, post("/api/favorites/add", viewer, serde_json::json!({ "part": "missing" }))).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let request = test::TestRequest::post()
+            .uri("/api/favorites")
+            .set_json(serde_json::json!(null))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn test_reviews() {
+        let db = create_local_db().await.unwrap();
+        let app =
+            test::init_service(create_app(db.clone()))
+            .await;
+
+        let viewer = CreateUserProps {
+            username: "Viewer".into(),
+            password: "viewer".into(),
+            role: Role::Viewer,
+        };
+        users::create_user_raw(&viewer, &Data::from(db.clone())).await.unwrap();
+        let viewer = &login(&db, "Viewer", "viewer").await;
+        let admin = &login(&db, "Admin", "admin").await;
+
+        create_part_raw(&DBPartProps { name: "Monitor".into(), ..Default::default() }, &Data::from(db.clone())).await.unwrap();
+        let page: PartsPage = test::call_and_read_body_json(&app, test::TestRequest::post().uri("/api/part").set_json(serde_json::json!({ "id": null, "limit": 1 })).to_request()).await;
+        let id = page.parts[0].id.clone();
+        let uri = format!("/api/part/{}/reviews", id);
+
+        let reviews: PartReviews = test::call_and_read_body_json(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+        assert_eq!((reviews.rating.get(), reviews.review_count), (0.0, 0));
+        assert!(reviews.reviews.is_empty());
+
+        let submit = |auth: &(header::HeaderName, String), stars: u8, text: &str| test::TestRequest::post()
+            .uri(&uri)
+            .insert_header(auth.clone())
+            .set_json(SubmitReviewProps { stars, text: text.into() })
+            .to_request();
+
+        let reviews: PartReviews = test::call_and_read_body_json(&app, submit(viewer, 4, "Sharp")).await;
+        assert_eq!((reviews.rating.get(), reviews.review_count), (4.0, 1));
+        let reviews: PartReviews = test::call_and_read_body_json(&app, submit(admin, 5, "")).await;
+        assert_eq!((reviews.rating.get(), reviews.review_count), (4.5, 2));
+
+        // Another review of the same user replaces the first one
+        let reviews: PartReviews = test::call_and_read_body_json(&app, submit(viewer, 2, "Dead pixels after a month")).await;
+        assert_eq!((reviews.rating.get(), reviews.review_count), (3.5, 2));
+        let texts: Vec<(&str, &str)> = reviews.reviews.iter().map(|x| (x.username.as_str(), x.text.as_str())).collect();
+        assert_eq!(texts, [("Viewer", "Dead pixels after a month"), ("Admin", "")]);
+
+        // Rating of the part is kept up to date, including its indexed value
+        let query = GetPartProps {
+            limit: 10,
+            filters: vec![PropertyFilter { key: "rating".into(), condition: FilterCondition::Range { min: Some(3.5), max: Some(3.5) } }],
+            ..Default::default()
+        };
+        let page: PartsPage = test::call_and_read_body_json(&app, test::TestRequest::post().uri("/api/part").set_json(query).to_request()).await;
+        assert_eq!(page.total, 1);
+        assert_eq!(page.parts[0].review_count, 2);
+
+        // Editors can't change the rating
+        let mut props = DBPartProps::from(page.parts[0].clone());
+        props.rating = 1.0.into();
+        let update = serde_json::json!({ "id": id, "props": props });
+        let part: DBPart = test::call_and_read_body_json(&app, test::TestRequest::post().uri("/api/part/update").insert_header(admin.clone()).set_json(update).to_request()).await;
+        assert_eq!((part.rating.get(), part.review_count), (3.5, 2));
+
+        let response = test::call_service(&app, submit(viewer, 6, "")).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let request = test::TestRequest::post().uri(&uri).set_json(SubmitReviewProps { stars: 5, text: "".into() }).to_request();
+        assert_eq!(test::call_service(&app, request).await.status(), StatusCode::UNAUTHORIZED);
+
+        for missing in ["/api/part/missing/reviews", "/api/part/no-such-id/reviews"] {
+            let response = test::call_service(&app, test::TestRequest::get().uri(missing).to_request()).await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        }
+
+        let request = test::TestRequest::delete().uri(&uri).insert_header(viewer.clone()).to_request();
+        let reviews: PartReviews = test::call_and_read_body_json(&app, request).await;
+        assert_eq!((reviews.rating.get(), reviews.review_count), (5.0, 1));
+        assert_eq!(check::check_integrity(&Data::from(db.clone())).await.unwrap(), Vec::<String>::new());
+
+        // Browsers ask before the frontend can send DELETE
+        let origin = env::var("PCPC_ALLOWED_ORIGIN").unwrap_or("http://127.0.0.1:8080".to_string());
+        let request = test::TestRequest::default()
+            .method(http::Method::OPTIONS)
+            .uri(&uri)
+            .insert_header((header::ORIGIN, origin))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "DELETE"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_HEADERS, "authorization"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let methods = response.headers().get(header::ACCESS_CONTROL_ALLOW_METHODS).unwrap().to_str().unwrap();
+        assert!(methods.contains("DELETE"));
+    }
+
+    #[actix_web::test]
+    async fn test_concurrent_reviews() {
+        let db = create_local_db().await.unwrap();
+        let app =
+            test::init_service(create_app(db.clone()))
+            .await;
+
+        let viewer = CreateUserProps {
+            username: "Viewer".into(),
+            password: "viewer".into(),
+            role: Role::Viewer,
+        };
+        users::create_user_raw(&viewer, &Data::from(db.clone())).await.unwrap();
+        let viewer = &login(&db, "Viewer", "viewer").await;
+        let admin = &login(&db, "Admin", "admin").await;
+
+        create_part_raw(&DBPartProps { name: "Monitor".into(), ..Default::default() }, &Data::from(db.clone())).await.unwrap();
+        let page: PartsPage = test::call_and_read_body_json(&app, test::TestRequest::post().uri("/api/part").set_json(serde_json::json!({ "id": null, "limit": 1 })).to_request()).await;
+        let uri = format!("/api/part/{}/reviews", page.parts[0].id);
+
+        let submit = |auth: &(header::HeaderName, String), stars: u8| test::TestRequest::post()
+            .uri(&uri)
+            .insert_header(auth.clone())
+            .set_json(SubmitReviewProps { stars, text: "".into() })
+            .to_request();
+
+        // First reviews of the same user submitted at once don't conflict on the author index
+        let (first, second, third) = tokio::join!(
+            test::call_service(&app, submit(viewer, 4)),
+            test::call_service(&app, submit(viewer, 2)),
+            test::call_service(&app, submit(admin, 5)),
+        );
+        for response in [first, second, third] {
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        // Rating matches the reviews that were kept, whichever review of the viewer was the last one
+        let reviews: PartReviews = test::call_and_read_body_json(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+        let stars: Vec<u8> = reviews.reviews.iter().map(|x| x.stars).collect();
+        assert_eq!(reviews.review_count, 2);
+        assert_eq!(reviews.rating, average_rating(&stars));
+    }
+
+    #[actix_web::test]
+    async fn test_prices() {
+        let db = create_local_db().await.unwrap();
+        let app =
+            test::init_service(create_app(db.clone()))
+            .await;
+        let admin = &login(&db, "Admin", "admin").await;
+
+        let data = Data::from(db.clone());
+        for name in ["Intel Core i5-13500", "Intel Core i5-13600"] {
+            let props = DBPartProps {
+                name: name.into(),
+                ..Default::default()
+            };
+            create_part_raw(&props, &data).await.unwrap();
+        }
+
+        let post = |uri: &str, json: serde_json::Value| test::TestRequest::post()
+            .uri(uri)
+            .insert_header(admin.clone())
+            .set_json(json)
+            .to_request();
+
+        let page: PartsPage = test::call_and_read_body_json(&app, post("/api/part", serde_json::json!({ "id": null, "limit": 2 }))).await;
+        let ids: Vec<String> = page.parts.into_iter().map(|x| x.id).collect();
+
+        let observations = [
+            (&ids[0], "Alza", 20000, Currency::EUR, "2023-03-01T10:00:00Z"),
+            (&ids[0], "Czc", 25000, Currency::EUR, "2023-03-02T10:00:00Z"),
+            (&ids[0], "Alza", 30000, Currency::EUR, "2023-03-03T10:00:00Z"),
+            (&ids[0], "Newegg", 21000, Currency::USD, "2023-03-03T10:00:00Z"),
+        ];
+        for (part, retailer, amount, currency, observed_at) in observations {
+            let props = AddPriceProps {
+                part: part.parse().unwrap(),
+                amount,
+                currency,
+                retailer: retailer.into(),
+                url: format!("https://{}.example/{}", retailer.to_lowercase(), part),
+                observed_at: Some(observed_at.into()),
+            };
+            let price: Price = test::call_and_read_body_json(&app, post("/api/price/add", serde_json::to_value(&props).unwrap())).await;
+            assert_eq!(price.part, *part);
+            assert_eq!(price.amount, amount);
+        }
+
+        let history: Vec<Price> = test::call_and_read_body_json(&app, post("/api/price/history", serde_json::json!({ "part": ids[0], "currency": "EUR" }))).await;
+        let amounts: Vec<u64> = history.iter().map(|x| x.amount).collect();
+        assert_eq!(amounts, vec![20000, 25000, 30000]);
+        let history: Vec<Price> = test::call_and_read_body_json(&app, post("/api/price/history", serde_json::json!({ "part": ids[0] }))).await;
+        assert_eq!(history.len(), 4);
+
+        let lowest: BTreeMap<String, Price> = test::call_and_read_body_json(&app, post("/api/price/lowest", serde_json::json!({ "parts": ids, "currency": "EUR" }))).await;
+        assert_eq!(lowest.len(), 1);
+        assert_eq!(lowest[&ids[0]].retailer, "Czc");
+        assert_eq!(lowest[&ids[0]].to_string(), "250.00 EUR");
+
+        let props = AddPriceProps {
+            part: ids[1].parse().unwrap(),
+            amount: 100,
+            currency: Currency::EUR,
+            retailer: "Alza".into(),
+            url: "".into(),
+            observed_at: Some("yesterday".into()),
+        };
+        let response = test::call_service(&app, post("/api/price/add", serde_json::to_value(&props).unwrap())).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let error: ApiError = test::read_body_json(response).await;
+        assert_eq!(error.fields()[0].field, "observed_at");
+
+        let props = AddPriceProps {
+            part: "missing".parse().unwrap(),
+            observed_at: None,
+            ..props
+        };
+        let response = test::call_service(&app, post("/api/price/add", serde_json::to_value(&props).unwrap())).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_suggest_build() {
+        let db = create_local_db().await.unwrap();
+        let app =
+            test::init_service(create_app(db.clone()))
+            .await;
+        let admin = &login(&db, "Admin", "admin").await;
+
+        let data = Data::from(db.clone());
+        let parts = [
+            ("Intel Core i5-13500", PartsCategory::CPU(CPUProperties { cores: 14, socket: "FCLGA1700".into(), ..Default::default() })),
+            ("AMD Ryzen 5 7600", PartsCategory::CPU(CPUProperties { cores: 6, socket: "AM5".into(), ..Default::default() })),
+            ("ASUS PRIME B650", PartsCategory::Motherboard(MotherboardProperties { socket: "AM5".into(), memory_type: "DDR5".into(), ..Default::default() })),
+            ("Kingston FURY 32 GB", PartsCategory::RAM(RAMProperties { memory_type: "DDR5".into(), ..Default::default() })),
+            // Without price, never suggested
+            ("MSI PRO B650", PartsCategory::Motherboard(MotherboardProperties { socket: "AM5".into(), memory_type: "DDR5".into(), ..Default::default() })),
+        ];
+        for (name, category) in parts.clone() {
+            let props = DBPartProps {
+                name: name.into(),
+                rating: 4.0.into(),
+                category,
+                ..Default::default()
+            };
+            create_part_raw(&props, &data).await.unwrap();
+        }
+
+        let post = |uri: &str, json: serde_json::Value| test::TestRequest::post()
+            .uri(uri)
+            .insert_header(admin.clone())
+            .set_json(json)
+            .to_request();
+
+        let page: PartsPage = test::call_and_read_body_json(&app, post("/api/part", serde_json::json!({ "id": null, "limit": 10 }))).await;
+        let id = |name: &str| page.parts.iter().find(|x| x.name == name).unwrap().id.clone();
+        for (name, amount) in [("Intel Core i5-13500", 25000), ("AMD Ryzen 5 7600", 22000), ("ASUS PRIME B650", 15000), ("Kingston FURY 32 GB", 10000)] {
+            let json = serde_json::json!({ "part": id(name), "amount": amount, "currency": "EUR", "retailer": "Alza", "url": "" });
+            let response = test::call_service(&app, post("/api/price/add", json)).await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let props = SuggestBuildProps {
+            budget: 50000,
+            currency: Currency::EUR,
+            use_case: UseCase::Office,
+            constraints: Vec::new(),
+            limit: 3,
+        };
+        let suggestions: Vec<BuildSuggestion> = test::call_and_read_body_json(&app, post("/api/build/suggest", serde_json::to_value(&props).unwrap())).await;
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].slots.cpu, Some(id("AMD Ryzen 5 7600")));
+        assert_eq!(suggestions[0].slots.motherboard, Some(id("ASUS PRIME B650")));
+        assert_eq!(suggestions[0].total, 47000);
+        assert_eq!(suggestions[0].missing, vec!["PSU", "Storage", "Case"]);
+
+        let props = SuggestBuildProps {
+            budget: 40000,
+            ..props
+        };
+        let suggestions: Vec<BuildSuggestion> = test::call_and_read_body_json(&app, post("/api/build/suggest", serde_json::to_value(&props).unwrap())).await;
+        assert!(suggestions.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_build() {
+        let db = create_local_db().await.unwrap();
+        let app =
+            test::init_service(create_app(db.clone()))
+            .await;
+        let viewer = CreateUserProps {
+            username: "Viewer".into(),
+            password: "viewer".into(),
+            role: Role::Viewer,
+        };
+        users::create_user_raw(&viewer, &Data::from(db.clone())).await.unwrap();
+        let viewer = login(&db, "Viewer", "viewer").await;
+
+        let props = DBBuildProps {
+            name: "Gaming".into(),
+            slots: BuildSlots {
+                cpu: Some("cpu".into()),
+                ram: vec!["ram1".into(), "ram2".into()],
+                ..Default::default()
+            },
+        };
+
+        let request = 
+            test::TestRequest::post()
+                .uri("/api/build/create")
+                .insert_header(viewer.clone())
+                .set_json(&props);
+
+        let created: DBBuild = test::call_and_read_body_json(&app, request.to_request()).await;
+        assert_eq!(created.slots, props.slots);
+        let viewer_user = users::find_user("Viewer", &Data::from(db.clone())).await.unwrap().unwrap();
+        assert_eq!(created.owner, viewer_user.id);
+
+        let request = 
+            test::TestRequest::post()
+                .uri("/api/build")
+                .set_json(GetBuildProps { id: None, limit: 10 });
+
+        let builds: Vec<DBBuild> = test::call_and_read_body_json(&app, request.to_request()).await;
+        assert_eq!(builds, vec![created.clone()]);
+
+        // Builds of other users can't be changed, except by admins
+        let other = CreateUserProps {
+            username: "Other".into(),
+            password: "other".into(),
+            role: Role::Editor,
+        };
+        users::create_user_raw(&other, &Data::from(db.clone())).await.unwrap();
+        let other = login(&db, "Other", "other").await;
+        let mut stolen = props.clone();
+        stolen.name = "Stolen".into();
+        let requests = [
+            test::TestRequest::post()
+                .uri("/api/build/update")
+                .set_json(UpdateBuildProps { id: created.id.clone(), props: stolen.clone() }),
+            test::TestRequest::post()
+                .uri("/api/build/delete")
+                .set_json(DeleteBuildProps { id: created.id.clone() }),
+        ];
+        for request in requests {
+            let response = test::call_service(&app, request.insert_header(other.clone()).to_request()).await;
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        }
+
+        let admin = login(&db, "Admin", "admin").await;
+        let request = 
+            test::TestRequest::post()
+                .uri("/api/build/update")
+                .insert_header(admin)
+                .set_json(UpdateBuildProps { id: created.id.clone(), props: stolen });
+
+        let build: DBBuild = test::call_and_read_body_json(&app, request.to_request()).await;
+        assert_eq!(build.name, "Stolen");
+        assert_eq!(build.owner, viewer_user.id);
+
+        let mut updated = props.clone();
+        updated.name = "Office".into();
+        let request = 
+            test::TestRequest::post()
+                .uri("/api/build/update")
+                .insert_header(viewer.clone())
+                .set_json(UpdateBuildProps { id: created.id.clone(), props: updated });
+
+        let response = test::call_service(&app, request.to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request = 
+            test::TestRequest::post()
+                .uri("/api/build")
+                .set_json(GetBuildProps { id: Some(created.id.clone()), limit: 1 });
+
+        let build: DBBuild = test::call_and_read_body_json(&app, request.to_request()).await;
+        assert_eq!(build.name, "Office");
+        assert_eq!(build.id, created.id);
+
+        let request = 
+            test::TestRequest::post()
+                .uri("/api/build/delete")
+                .insert_header(viewer.clone())
+                .set_json(DeleteBuildProps { id: created.id.clone() });
+
+        let response = test::call_service(&app, request.to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        for uri in ["/api/build/delete", "/api/build/update"] {
+            let request = 
+                test::TestRequest::post()
+                    .uri(uri)
+                    .insert_header(viewer.clone())
+                    .set_json(UpdateBuildProps { id: created.id.clone(), props: props.clone() });
+
+            let response = test::call_service(&app, request.to_request()).await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        }
+
+        let request = 
+            test::TestRequest::post()
+                .uri("/api/build/create")
+                .set_json(&props);
+
+        let response = test::call_service(&app, request.to_request()).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
     #[actix_web::test]
     async fn test_status() {
         let app = 
@@ -0,0 +1,294 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use actix_web::web::Data;
+use anyhow::bail;
+use common::{DBPartProps, PartsCategory, types::ReleaseDate};
+use serde_json::Value as JsonValue;
+use surrealdb::sql::{Thing, Value};
+
+use crate::{DB, check::select, parts::part_content, users};
+
+/// What each migration does. Migration at index `n` moves the schema from version `n` to `n + 1`.
+/// Migrations must be safe to run again, the version is saved only after a migration finishes.
+const MIGRATIONS: [&str; 5] = [
+    "Hash plaintext passwords and fill roles of users",
+    "Fill properties missing in stored parts with defaults of their category",
+    "Define tables and fields",
+    "Store release dates of parts as dates, unknown ones as NONE",
+    "Define reviews of parts and compute ratings of parts from them",
+];
+
+/// Version of the schema this build reads and writes
+pub const SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// Fields every record has to have. Tables stay schemaless, so properties of new categories don't need a definition.
+/// Statements of a migration are frozen once it's released, later changes go into a new migration.
+/// Constants are named after the version their migration moves the schema to.
+const SCHEMA_V3_TABLES: &str = "
+    DEFINE TABLE part SCHEMALESS;
+    DEFINE FIELD name ON part TYPE string ASSERT $value != NONE;
+    DEFINE FIELD model ON part TYPE string ASSERT $value != NONE;
+    DEFINE FIELD manufactuer ON part TYPE string ASSERT $value != NONE;
+    DEFINE FIELD release_date ON part TYPE string ASSERT $value != NONE;
+    DEFINE FIELD rating ON part TYPE string ASSERT $value != NONE;
+    DEFINE FIELD category ON part ASSERT $value != NONE;
+
+    DEFINE TABLE user SCHEMALESS;
+    DEFINE FIELD username ON user TYPE string ASSERT $value != NONE;
+    DEFINE FIELD password ON user TYPE string ASSERT $value != NONE;
+    DEFINE FIELD role ON user TYPE string ASSERT $value INSIDE ['Admin', 'Editor', 'Viewer'];
+    DEFINE FIELD disabled ON user TYPE bool ASSERT $value != NONE;
+    DEFINE INDEX username ON user FIELDS username UNIQUE;
+
+    DEFINE TABLE build SCHEMALESS;
+    DEFINE FIELD name ON build TYPE string ASSERT $value != NONE;
+    DEFINE FIELD slots ON build TYPE object ASSERT $value != NONE;
+
+    DEFINE TABLE price SCHEMALESS;
+    DEFINE FIELD part ON price TYPE record(part) ASSERT $value != NONE;
+    DEFINE FIELD amount ON price TYPE int ASSERT $value != NONE;
+    DEFINE FIELD currency ON price TYPE string ASSERT $value != NONE;
+
+    DEFINE TABLE favorite SCHEMALESS;
+    DEFINE FIELD part ON favorite TYPE record(part) ASSERT $value != NONE;
+    DEFINE FIELD user ON favorite TYPE record(user) ASSERT $value != NONE;
+
+    DEFINE TABLE audit SCHEMALESS;
+    DEFINE FIELD part ON audit TYPE record(part) ASSERT $value != NONE;
+    DEFINE FIELD user ON audit TYPE string ASSERT $value != NONE;
+    DEFINE FIELD action ON audit TYPE string ASSERT $value != NONE;
+";
+
+/// Unknown release dates are NONE
+const SCHEMA_V4_RELEASE_DATE: &str = "
+    DEFINE FIELD release_date ON part TYPE string;
+";
+
+const SCHEMA_V5_REVIEWS: &str = "
+    DEFINE TABLE review SCHEMALESS;
+    DEFINE FIELD part ON review TYPE record(part) ASSERT $value != NONE;
+    DEFINE FIELD user ON review TYPE record(user) ASSERT $value != NONE;
+    DEFINE FIELD stars ON review TYPE int ASSERT $value >= 1 AND $value <= 5;
+    DEFINE FIELD text ON review TYPE string ASSERT $value != NONE;
+    DEFINE INDEX author ON review FIELDS part, user UNIQUE;
+";
+
+/// Rating of `$part` computed from its reviews, ratings entered before reviews existed are dropped
+const RATING_V5: &str = "
+    BEGIN TRANSACTION;
+    LET $reviews = (SELECT stars FROM review WHERE part = $part AND user.id != NONE);
+    LET $rating = IF count($reviews) > 0 THEN math::fixed(math::mean($reviews.stars), 2) ELSE 0 END;
+    UPDATE $part SET rating = <string> $rating, review_count = count($reviews), properties.rating = $rating;
+    COMMIT TRANSACTION;
+";
+
+async fn execute(sql: &str, vars: BTreeMap<String, Value>, db: &Data<DB>) -> anyhow::Result<()> {
+    let responses = db.datastore.execute(sql, &db.session, Some(vars), false).await?;
+
+    for response in responses {
+        response.result?;
+    }
+
+    Ok(())
+}
+
+/// Version saved by the last migration, `None` if the database was never migrated
+pub async fn schema_version(db: &Data<DB>) -> anyhow::Result<Option<u32>> {
+    let rows = select("SELECT version FROM schema_version:current", db).await?;
+
+    Ok(rows.first().and_then(|row| row["version"].as_u64()).map(|version| version as u32))
+}
+
+/// Database without any records, it has nothing to migrate
+async fn is_empty(db: &Data<DB>) -> anyhow::Result<bool> {
+    let response = db.datastore.execute("INFO FOR DB", &db.session, None, false).await?;
+
+    let info = match response.into_iter().next() {
+        Some(response) => serde_json::to_value(response.result?)?,
+        None => bail!("No response from DB"),
+    };
+
+    Ok(info["tb"].as_object().is_none_or(|tables| tables.is_empty()))
+}
+
+/// Runs migrations the database is missing, returns what they did
+pub async fn migrate(db: &Data<DB>) -> anyhow::Result<Vec<&'static str>> {
+    let version = schema_version(db).await?.unwrap_or(0);
+    if version > SCHEMA_VERSION {
+        bail!("Database schema is version {}, newer than version {} of this build", version, SCHEMA_VERSION);
+    }
+
+    let mut applied = Vec::new();
+    for (index, description) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        match index {
+            0 => users::hash_plaintext_passwords(db).await?,
+            1 => rewrite_parts(db).await?,
+            2 => execute(SCHEMA_V3_TABLES, BTreeMap::new(), db).await?,
+            3 => {
+                execute(SCHEMA_V4_RELEASE_DATE, BTreeMap::new(), db).await?;
+                rewrite_parts(db).await?;
+            },
+            4 => {
+                execute(SCHEMA_V5_REVIEWS, BTreeMap::new(), db).await?;
+                compute_ratings(db).await?;
+            },
+            _ => bail!("Migration {} is not implemented", index + 1),
+        }
+
+        let vars = [("version".to_string(), Value::from(index as i64 + 1))].into();
+        execute("UPDATE schema_version:current SET version = $version, migrated_at = time::now()", vars, db).await?;
+        applied.push(*description);
+    }
+
+    Ok(applied)
+}
+
+/// Fails unless the database has the schema of this build. An empty database is migrated right away.
+pub async fn check_schema(db: &Data<DB>) -> anyhow::Result<()> {
+    match schema_version(db).await? {
+        Some(SCHEMA_VERSION) => Ok(()),
+        None if is_empty(db).await? => migrate(db).await.map(|_| ()),
+        Some(version) => bail!("Database schema is version {}, this build needs version {}. Run `backend migrate` first.", version, SCHEMA_VERSION),
+        None => bail!("Database schema has no version, this build needs version {}. Run `backend migrate` first.", SCHEMA_VERSION),
+    }
+}
+
+/// Adds fields missing in a stored part, with values of the default part of its category
+fn fill_defaults(part: &mut JsonValue) -> anyhow::Result<()> {
+    let category = match &part["category"] {
+        JsonValue::String(category) => category.clone(),
+        JsonValue::Object(category) if category.len() == 1 => category.keys().next().cloned().unwrap_or_default(),
+        category => bail!("Category {} is not a category", category),
+    };
+    let category = match PartsCategory::from_str(&category) {
+        Ok(category) => category,
+        Err(_) => bail!("Unknown category: {}", category),
+    };
+
+    let template = serde_json::to_value(DBPartProps { category, ..Default::default() })?;
+    merge_missing(part, &template);
+
+    Ok(())
+}
+
+/// Empty release dates were stored for unknown ones, they are NONE now.
+/// Free text that isn't a date, like "soon", is unknown too. It's returned, so it can be reported.
+fn clear_invalid_release_date(part: &mut JsonValue) -> Option<String> {
+    let date = match &part["release_date"] {
+        JsonValue::String(date) => date.clone(),
+        _ => return None,
+    };
+    if date.parse::<ReleaseDate>().is_ok() {
+        return None;
+    }
+
+    part["release_date"] = JsonValue::Null;
+    match date.is_empty() {
+        true => None,
+        false => Some(date),
+    }
+}
+
+fn merge_missing(value: &mut JsonValue, template: &JsonValue) {
+    if let (JsonValue::Object(value), JsonValue::Object(template)) = (value, template) {
+        for (key, default) in template {
+            match value.get_mut(key) {
+                Some(field) => merge_missing(field, default),
+                None => { value.insert(key.clone(), default.clone()); },
+            }
+        }
+    }
+}
+
+/// Stores every part again as the current `DBPartProps`, which also rebuilds its indexed properties
+async fn rewrite_parts(db: &Data<DB>) -> anyhow::Result<()> {
+    let mut sql = vec!["BEGIN TRANSACTION".to_string()];
+    let mut vars: BTreeMap<String, Value> = BTreeMap::new();
+
+    for (index, mut row) in select("SELECT * FROM part", db).await?.into_iter().enumerate() {
+        let id = row["id"].as_str().unwrap_or_default().replace("part:", "");
+        if let Some(row) = row.as_object_mut() {
+            row.remove("id");
+            row.remove("properties");
+        }
+
+        if let Some(date) = clear_invalid_release_date(&mut row) {
+            log::warn!("part:{} has release date {:?}, which isn't a date. It's unknown now.", id, date);
+        }
+        let props: DBPartProps = match fill_defaults(&mut row).and_then(|_| Ok(serde_json::from_value(row)?)) {
+            Ok(props) => props,
+            Err(error) => bail!("part:{} can't be migrated: {}", id, error),
+        };
+
+        vars.insert(format!("part{}", index), Thing::from(("part", id.as_str())).into());
+        vars.insert(format!("props{}", index), part_content(&props)?);
+        sql.push(format!("UPDATE $part{index} CONTENT $props{index}"));
+    }
+
+    sql.push("COMMIT TRANSACTION".to_string());
+    execute(&sql.join(";\n"), vars, db).await
+}
+
+/// Computes rating of every part from its reviews, a part without reviews has rating 0
+async fn compute_ratings(db: &Data<DB>) -> anyhow::Result<()> {
+    for row in select("SELECT id FROM part", db).await? {
+        let id = row["id"].as_str().unwrap_or_default().replace("part:", "");
+        let vars = [("part".to_string(), Value::from(Thing::from(("part", id.as_str()))))].into();
+        execute(RATING_V5, vars, db).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{DBPartProps, PartsCategory};
+    use serde_json::json;
+
+    use super::{clear_invalid_release_date, fill_defaults};
+
+    #[test]
+    fn part_defaults() {
+        // Stored before threads and most other properties of CPUs existed
+        let mut part = json!({ "name": "Ryzen", "rating": "4", "category": { "CPU": { "cores": 8, "socket": "AM4" } } });
+        fill_defaults(&mut part).unwrap();
+
+        let props: DBPartProps = serde_json::from_value(part).unwrap();
+        assert_eq!(props.name, "Ryzen");
+        assert_eq!(props.rating.get(), 4.0);
+        match props.category {
+            PartsCategory::CPU(cpu) => {
+                assert_eq!(cpu.cores, 8);
+                assert_eq!(cpu.socket, "AM4");
+                assert_eq!(cpu.threads, 0);
+            },
+            _ => panic!("Not a CPU"),
+        }
+
+        let mut basic = json!({ "name": "Monitor", "category": "Basic" });
+        fill_defaults(&mut basic).unwrap();
+        assert!(serde_json::from_value::<DBPartProps>(basic).is_ok());
+
+        // Stored while release dates were text
+        let mut gpu = json!({ "name": "RTX 3060", "release_date": "21Q1", "category": { "GPU": {} } });
+        fill_defaults(&mut gpu).unwrap();
+        assert_eq!(serde_json::from_value::<DBPartProps>(gpu).unwrap().release_date, "2021Q1".parse().ok());
+
+        let mut unknown = json!({ "name": "Monitor", "release_date": "", "category": "Basic" });
+        assert_eq!(clear_invalid_release_date(&mut unknown), None);
+        fill_defaults(&mut unknown).unwrap();
+        assert_eq!(serde_json::from_value::<DBPartProps>(unknown).unwrap().release_date, None);
+
+        let mut invalid = json!({ "name": "Monitor", "release_date": "banana", "category": "Basic" });
+        assert_eq!(clear_invalid_release_date(&mut invalid).as_deref(), Some("banana"));
+        fill_defaults(&mut invalid).unwrap();
+        assert_eq!(serde_json::from_value::<DBPartProps>(invalid).unwrap().release_date, None);
+
+        let mut valid = json!({ "name": "RTX 3060", "release_date": "21Q1", "category": { "GPU": {} } });
+        assert_eq!(clear_invalid_release_date(&mut valid), None);
+        assert_eq!(valid["release_date"], "21Q1");
+
+        assert!(fill_defaults(&mut json!({ "name": "Fan", "category": "Fan" })).is_err());
+        assert!(fill_defaults(&mut json!({ "name": "Fan" })).is_err());
+    }
+}
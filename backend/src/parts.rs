@@ -0,0 +1,397 @@
+use std::{collections::BTreeMap, fmt::Display, str::FromStr};
+
+use actix_web::{web::{self, Data}, HttpResponse};
+use common::{error::{ApiError, ApiResult}, audit::{DeletePartProps, PatchPartProps, UpdatePartProps, merge}, compatibility::check_compatibility, export::{ExportPartsProps, Table, parts_table}, price::Currency, types::PartId, validation::validate_part, DBPart, DBPartProps, GetCompatibilityProps, GetPartProps, PartsCategory, query::{FilterCondition, GetPropertyValuesProps, SortDirection, numeric_value, property_keys}};
+use surrealdb::sql::{Value, json};
+
+use crate::{DB, auth::{Authenticated, can}, export_response, prices::get_lowest_prices, repository::PartRepository};
+
+/// Most parts in one export
+const EXPORT_LIMIT: u32 = 10_000;
+
+#[derive(Debug, PartialEq)]
+pub enum QueryError {
+    UnknownCategory(String),
+    UnknownKey(String),
+    NotNumeric(String),
+}
+
+impl std::error::Error for QueryError {}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueryError::UnknownCategory(category) => write!(f, "Unknown category: {}", category),
+            QueryError::UnknownKey(key) => write!(f, "Unknown property: {}", key),
+            QueryError::NotNumeric(key) => write!(f, "Property {} is not numeric", key),
+        }
+    }
+}
+
+impl From<QueryError> for ApiError {
+    fn from(error: QueryError) -> Self {
+        ApiError::invalid(error.to_string())
+    }
+}
+
+/// Keeps errors of the query as a bad request, anything else is internal
+fn query_error(error: anyhow::Error) -> ApiError {
+    match error.downcast::<QueryError>() {
+        Ok(error) => error.into(),
+        Err(error) => error.into(),
+    }
+}
+
+/// Parameterized SurrealQL built from [`GetPartProps`].
+/// Only whitelisted property keys and category names are put into the query text,
+/// every value is passed as a variable.
+pub struct PartQuery {
+    conditions: Vec<String>,
+    order: String,
+    limit: u32,
+    offset: u32,
+    pub vars: BTreeMap<String, Value>,
+}
+
+impl PartQuery {
+    pub fn new(props: &GetPartProps) -> Result<Self, QueryError> {
+        let keys = property_keys();
+        let mut query = Self {
+            conditions: Vec::new(),
+            order: "properties.name ASC".to_string(),
+            limit: props.limit,
+            offset: props.offset,
+            vars: BTreeMap::new(),
+        };
+
+        if let Some(category) = &props.category {
+            let category = PartsCategory::from_str(category)
+                .map_err(|_| QueryError::UnknownCategory(category.clone()))?;
+            let condition = match category {
+                PartsCategory::Basic => format!("category = '{}'", category),
+                _ => format!("category.{} != NONE", category),
+            };
+            query.conditions.push(condition);
+        }
+
+        if let Some(text) = &props.query {
+            let text = query.bind(text.to_lowercase().into());
+            query.conditions.push(format!(
+                "(string::lowercase(name) CONTAINS {text} OR string::lowercase(model) CONTAINS {text} OR string::lowercase(manufactuer) CONTAINS {text})"
+            ));
+        }
+
+        for filter in &props.filters {
+            let numeric = keys.get(&filter.key)
+                .ok_or_else(|| QueryError::UnknownKey(filter.key.clone()))?
+                .is_numeric();
+            let field = format!("properties.{}", filter.key);
+
+            match &filter.condition {
+                FilterCondition::Range { min, max } => {
+                    if !numeric {
+                        return Err(QueryError::NotNumeric(filter.key.clone()));
+                    }
+
+                    if let Some(min) = min {
+                        let min = query.bind((*min).into());
+                        query.conditions.push(format!("{} >= {}", field, min));
+                    }
+
+                    if let Some(max) = max {
+                        let max = query.bind((*max).into());
+                        query.conditions.push(format!("{} <= {}", field, max));
+                    }
+                },
+                FilterCondition::Equals(value) => {
+                    let value = property_value(value, numeric, &filter.key)?;
+                    let value = query.bind(value);
+                    query.conditions.push(format!("{} = {}", field, value));
+                },
+                FilterCondition::OneOf(values) => {
+                    let values: Vec<Value> = values.iter()
+                        .map(|value| property_value(value, numeric, &filter.key))
+                        .collect::<Result<_, _>>()?;
+                    let values = query.bind(values.into());
+                    query.conditions.push(format!("{} INSIDE {}", field, values));
+                },
+            }
+        }
+
+        if let Some(sort) = &props.sort {
+            if !keys.contains_key(&sort.key) {
+                return Err(QueryError::UnknownKey(sort.key.clone()));
+            }
+
+            let direction = match sort.direction {
+                SortDirection::Ascending => "ASC",
+                SortDirection::Descending => "DESC",
+            };
+            query.order = format!("properties.{} {}", sort.key, direction);
+        }
+
+        Ok(query)
+    }
+
+    /// Adds variable and returns its name to be put into query
+    fn bind(&mut self, value: Value) -> String {
+        let name = format!("value{}", self.vars.len());
+        self.vars.insert(name.clone(), value);
+        format!("${}", name)
+    }
+
+    fn condition(&self) -> String {
+        if self.conditions.is_empty() {
+            return String::new();
+        }
+
+        format!(" WHERE {}", self.conditions.join(" AND "))
+    }
+
+    pub fn select_sql(&self) -> String {
+        // LIMIT and START don't accept variables
+        format!("SELECT * FROM part{} ORDER BY {} LIMIT {} START {}", self.condition(), self.order, self.limit, self.offset)
+    }
+
+    /// Distinct values of a property, key has to be checked beforehand
+    pub fn values_sql(&self, key: &str) -> String {
+        // Aliased fields can't be grouped by
+        format!("SELECT properties.{key} FROM part{} GROUP BY properties.{key}", self.condition())
+    }
+
+    pub fn count_sql(&self) -> String {
+        format!("SELECT count() AS total FROM part{} GROUP BY total", self.condition())
+    }
+}
+
+fn property_value(value: &serde_json::Value, numeric: bool, key: &str) -> Result<Value, QueryError> {
+    if numeric {
+        return numeric_value(value)
+            .map(Value::from)
+            .ok_or_else(|| QueryError::NotNumeric(key.to_string()));
+    }
+
+    json(&value.to_string()).map_err(|_| QueryError::UnknownKey(key.to_string()))
+}
+
+/// Content of a part record, with flattened properties used for filtering and sorting
+pub fn part_content(props: &DBPartProps) -> anyhow::Result<Value> {
+    let mut content = serde_json::to_value(props)?;
+    content["properties"] = serde_json::Value::Object(props.indexed_properties());
+
+    Ok(json(&content.to_string())?)
+}
+
+pub fn into_parts(result: Value) -> anyhow::Result<Vec<DBPart>> {
+    let json = serde_json::to_value(result)?;
+    let mut parts: Vec<DBPart> = serde_json::from_value(json)?;
+    for part in parts.iter_mut() {
+        part.id = part.id.replace("part:", "");
+    }
+
+    Ok(parts)
+}
+
+fn part_not_found() -> ApiError {
+    ApiError::not_found("Part doesn't exist")
+}
+
+/// Existing part, UPDATE on a missing record would create it
+pub async fn existing_part<R: PartRepository>(id: &PartId, parts: &R) -> ApiResult<DBPart> {
+    parts.get(id).await?.ok_or_else(part_not_found)
+}
+
+pub async fn part<R: PartRepository>(props: web::Json<GetPartProps>, parts: Data<R>) -> ApiResult<HttpResponse> {
+    let props = props.into_inner();
+
+    if let Some(id) = &props.id {
+        return Ok(HttpResponse::Ok().json(existing_part(id, parts.as_ref()).await?));
+    }
+
+    Ok(HttpResponse::Ok().json(parts.page(&props).await.map_err(query_error)?))
+}
+
+/// Parts matching the query with properties in given order, pagination of the query is ignored
+pub async fn get_parts_table<R: PartRepository>(props: &GetPartProps, columns: &[String], parts: &R, db: &Data<DB>) -> anyhow::Result<Table> {
+    let props = GetPartProps {
+        id: None,
+        limit: EXPORT_LIMIT,
+        offset: 0,
+        category: props.category.clone(),
+        query: props.query.clone(),
+        filters: props.filters.clone(),
+        sort: props.sort.clone(),
+    };
+    let page = parts.page(&props).await?;
+
+    let ids: Vec<String> = page.parts.iter().map(|x| x.id.clone()).collect();
+    let prices = get_lowest_prices(&ids, Currency::default(), db).await?.into_iter().collect();
+
+    Ok(parts_table(&page.parts, &prices, columns))
+}
+
+/// Parts matching the query as a file, with properties in requested order
+pub async fn export_parts<R: PartRepository>(props: web::Json<ExportPartsProps>, parts: Data<R>, db: Data<DB>) -> ApiResult<HttpResponse> {
+    let table = get_parts_table(&props.query, &props.columns, parts.as_ref(), &db).await.map_err(query_error)?;
+    Ok(export_response(&table, props.format, "parts"))
+}
+
+/// Distinct values of a property, used as options of filters
+pub async fn property_values<R: PartRepository>(props: web::Json<GetPropertyValuesProps>, parts: Data<R>) -> ApiResult<HttpResponse> {
+    if !property_keys().contains_key(&props.key) {
+        return Err(QueryError::UnknownKey(props.key.clone()).into());
+    }
+
+    let values = parts.property_values(&props).await.map_err(query_error)?;
+    Ok(HttpResponse::Ok().json(values))
+}
+
+/// Rating is computed from reviews, so the one sent by clients is replaced with the stored one
+pub fn with_stored_rating(mut props: DBPartProps, stored: Option<&DBPartProps>) -> DBPartProps {
+    let stored = stored.cloned().unwrap_or_default();
+    props.rating = stored.rating;
+    props.review_count = stored.review_count;
+
+    props
+}
+
+/// Returns the created part, it has no rating until it's reviewed
+pub async fn create_part<R: PartRepository>(auth: Authenticated<can::CreateParts>, props: web::Json<DBPartProps>, parts: Data<R>) -> ApiResult<HttpResponse> {
+    validate_part(&props).map_err(ApiError::validation)?;
+    let props = with_stored_rating(props.into_inner(), None);
+    let part = parts.create(&props, &auth.user.username).await?;
+    Ok(HttpResponse::Ok().json(part))
+}
+
+/// Replaces whole part, except its rating
+pub async fn update_part<R: PartRepository>(auth: Authenticated<can::UpdateParts>, props: web::Json<UpdatePartProps>, parts: Data<R>) -> ApiResult<HttpResponse> {
+    validate_part(&props.props).map_err(ApiError::validation)?;
+    let before = DBPartProps::from(existing_part(&props.id, parts.as_ref()).await?);
+    let after = with_stored_rating(props.props.clone(), Some(&before));
+
+    match parts.replace(&props.id, &before, &after, &auth.user.username).await? {
+        Some(part) => Ok(HttpResponse::Ok().json(part)),
+        None => Err(ApiError::internal()),
+    }
+}
+
+/// Changes only fields sent in the patch
+pub async fn patch_part<R: PartRepository>(auth: Authenticated<can::UpdateParts>, props: web::Json<PatchPartProps>, parts: Data<R>) -> ApiResult<HttpResponse> {
+    let before = DBPartProps::from(existing_part(&props.id, parts.as_ref()).await?);
+
+    let props = props.into_inner();
+    let mut after = serde_json::to_value(&before).map_err(anyhow::Error::from)?;
+    merge(&mut after, &serde_json::Value::Object(props.changes));
+    let after: DBPartProps = serde_json::from_value(after)
+        .map_err(|error| ApiError::invalid(format!("Patched part is invalid: {}", error)))?;
+    validate_part(&after).map_err(ApiError::validation)?;
+    let after = with_stored_rating(after, Some(&before));
+
+    match parts.replace(&props.id, &before, &after, &auth.user.username).await? {
+        Some(part) => Ok(HttpResponse::Ok().json(part)),
+        None => Err(ApiError::internal()),
+    }
+}
+
+pub async fn delete_part<R: PartRepository>(auth: Authenticated<can::DeleteParts>, props: web::Json<DeletePartProps>, parts: Data<R>) -> ApiResult<HttpResponse> {
+    let before = DBPartProps::from(existing_part(&props.id, parts.as_ref()).await?);
+    parts.delete(&props.id, &before, &auth.user.username).await?;
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Parts of the ids checked against each other
+pub async fn compatibility<R: PartRepository>(props: web::Json<GetCompatibilityProps>, parts: Data<R>) -> ApiResult<HttpResponse> {
+    let parts = parts.get_many(&props.ids).await?;
+    Ok(HttpResponse::Ok().json(check_compatibility(&parts)))
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{GetPartProps, query::{PropertyFilter, FilterCondition, SortBy, SortDirection}, types::PartId};
+    use proptest::{proptest, prop_assert, prop_assert_eq, prop_assume};
+    use serde_json::json;
+
+    use super::{PartQuery, QueryError};
+
+    #[test]
+    fn query_sql() {
+        let props = GetPartProps {
+            limit: 10,
+            category: Some("CPU".into()),
+            query: Some("Intel".into()),
+            filters: vec![
+                PropertyFilter {
+                    key: "cores".into(),
+                    condition: FilterCondition::Range { min: Some(8.0), max: None },
+                },
+                PropertyFilter {
+                    key: "socket".into(),
+                    condition: FilterCondition::OneOf(vec![json!("AM5"), json!("FCLGA1700")]),
+                },
+            ],
+            sort: Some(SortBy { key: "max_frequency".into(), direction: SortDirection::Descending }),
+            ..Default::default()
+        };
+
+        let query = PartQuery::new(&props).unwrap();
+        assert_eq!(
+            query.select_sql(),
+            "SELECT * FROM part WHERE category.CPU != NONE \
+            AND (string::lowercase(name) CONTAINS $value0 OR string::lowercase(model) CONTAINS $value0 OR string::lowercase(manufactuer) CONTAINS $value0) \
+            AND properties.cores >= $value1 AND properties.socket INSIDE $value2 \
+            ORDER BY properties.max_frequency DESC LIMIT 10 START 0"
+        );
+        assert_eq!(query.vars["value0"], "intel".into());
+    }
+
+    #[test]
+    fn query_rejects_unknown_keys() {
+        let filter = |key: &str, condition| GetPartProps {
+            filters: vec![PropertyFilter { key: key.into(), condition }],
+            ..Default::default()
+        };
+
+        let props = filter("cores; DELETE part", FilterCondition::Equals(json!(1)));
+        assert!(matches!(PartQuery::new(&props), Err(QueryError::UnknownKey(_))));
+
+        let props = filter("socket", FilterCondition::Range { min: Some(1.0), max: None });
+        assert!(matches!(PartQuery::new(&props), Err(QueryError::NotNumeric(_))));
+
+        let props = filter("cores", FilterCondition::Equals(json!("many")));
+        assert!(matches!(PartQuery::new(&props), Err(QueryError::NotNumeric(_))));
+
+        let props = GetPartProps {
+            category: Some("Monitor".into()),
+            ..Default::default()
+        };
+        assert!(matches!(PartQuery::new(&props), Err(QueryError::UnknownCategory(_))));
+    }
+
+    proptest! {
+        #[test]
+        fn part_id_accepts_record_ids(id in "[A-Za-z0-9_]{1,64}") {
+            let part_id: PartId = id.parse().unwrap();
+            prop_assert_eq!(part_id.as_str(), id.as_str());
+            prop_assert_eq!(&format!("part:{}", id).parse::<PartId>().unwrap(), &part_id);
+
+            let json = serde_json::to_string(&part_id).unwrap();
+            prop_assert_eq!(serde_json::from_str::<PartId>(&json).unwrap(), part_id);
+        }
+
+        #[test]
+        fn part_id_rejects_other_characters(prefix in "[A-Za-z0-9_]{0,20}", bad in "[^A-Za-z0-9_]", suffix in "\\PC{0,20}") {
+            // `part:` is the only accepted prefix
+            prop_assume!(!(prefix == "part" && bad == ":"));
+
+            let id = format!("{}{}{}", prefix, bad, suffix);
+            prop_assert!(id.parse::<PartId>().is_err());
+            prop_assert!(serde_json::from_value::<PartId>(serde_json::Value::String(id)).is_err());
+        }
+
+        #[test]
+        fn part_id_rejects_long_ids(id in "[A-Za-z0-9_]{65,100}") {
+            prop_assert!(id.parse::<PartId>().is_err());
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+
+use actix_web::{web::{self, Data}, HttpResponse};
+use anyhow::bail;
+use chrono::{DateTime, Utc};
+use common::{error::{ApiError, ApiResult, FieldError}, price::{AddPriceProps, Currency, GetLowestPricesProps, GetPriceHistoryProps, Price, lowest_prices}};
+use surrealdb::sql::{Value, Thing, Datetime};
+
+use crate::{DB, auth::{Authenticated, can}};
+
+async fn execute_price_query(sql: &str, vars: BTreeMap<String, Value>, db: &Data<DB>) -> anyhow::Result<Vec<Price>> {
+    let response = db.datastore.execute(sql, &db.session, Some(vars), false).await?;
+
+    let result = match response.into_iter().next() {
+        Some(response) => response.result?,
+        None => bail!("No response from DB"),
+    };
+
+    let json = serde_json::to_value(result)?;
+    let mut prices: Vec<Price> = serde_json::from_value(json)?;
+    for price in prices.iter_mut() {
+        price.part = price.part.replace("part:", "");
+    }
+
+    Ok(prices)
+}
+
+fn part_thing(id: &str) -> Value {
+    Thing::from(("part", id)).into()
+}
+
+/// Records a price observation. Prices are never updated, so they form a history.
+pub async fn add_price(_auth: Authenticated<can::UpdateParts>, props: web::Json<AddPriceProps>, db: Data<DB>) -> ApiResult<HttpResponse> {
+    let props = props.into_inner();
+
+    // Datetime::from(&str) would silently use current time for invalid timestamps
+    let observed_at = match props.observed_at.as_deref().map(DateTime::parse_from_rfc3339) {
+        Some(Ok(observed_at)) => Value::from(Datetime::from(observed_at.with_timezone(&Utc))),
+        Some(Err(_)) => return Err(ApiError::validation(vec![FieldError::new("observed_at", "is not an RFC 3339 timestamp")])),
+        None => Value::from(Datetime::default()),
+    };
+
+    let vars: BTreeMap<String, Value> = [
+        ("part".into(), (&props.part).into()),
+        ("amount".into(), props.amount.into()),
+        ("currency".into(), props.currency.to_string().into()),
+        ("retailer".into(), props.retailer.into()),
+        ("url".into(), props.url.into()),
+        ("observed_at".into(), observed_at),
+    ].into();
+
+    let response = db.datastore.execute("SELECT id FROM $part", &db.session, Some(vars.clone()), false).await;
+    match response.map_err(anyhow::Error::from)?.into_iter().next().map(|x| x.result) {
+        Some(Ok(Value::Array(parts))) if parts.is_empty() => return Err(ApiError::not_found("Part doesn't exist")),
+        Some(Ok(_)) => {},
+        _ => return Err(ApiError::internal()),
+    }
+
+    let sql = "CREATE price SET part = $part, amount = $amount, currency = $currency, retailer = $retailer, url = $url, observed_at = $observed_at";
+    match execute_price_query(sql, vars, &db).await?.into_iter().next() {
+        Some(price) => Ok(HttpResponse::Ok().json(price)),
+        None => Err(ApiError::internal()),
+    }
+}
+
+pub async fn price_history(props: web::Json<GetPriceHistoryProps>, db: Data<DB>) -> ApiResult<HttpResponse> {
+    let mut vars: BTreeMap<String, Value> = [
+        ("part".into(), (&props.part).into()),
+    ].into();
+
+    let sql = match props.currency {
+        Some(currency) => {
+            vars.insert("currency".into(), currency.to_string().into());
+            "SELECT * FROM price WHERE part = $part AND currency = $currency ORDER BY observed_at ASC"
+        },
+        None => "SELECT * FROM price WHERE part = $part ORDER BY observed_at ASC",
+    };
+
+    Ok(HttpResponse::Ok().json(execute_price_query(sql, vars, &db).await?))
+}
+
+/// Current lowest price of every part that has a price in the currency
+pub async fn get_all_lowest_prices(currency: Currency, db: &Data<DB>) -> anyhow::Result<BTreeMap<String, Price>> {
+    let vars: BTreeMap<String, Value> = [
+        ("currency".into(), currency.to_string().into()),
+    ].into();
+
+    let sql = "SELECT * FROM price WHERE currency = $currency ORDER BY observed_at ASC";
+    let prices = execute_price_query(sql, vars, db).await?;
+
+    Ok(lowest_prices(&prices))
+}
+
+/// Current lowest price of the parts, parts without price in the currency are left out
+pub async fn get_lowest_prices(parts: &[String], currency: Currency, db: &Data<DB>) -> anyhow::Result<BTreeMap<String, Price>> {
+    let parts: Vec<Value> = parts.iter().map(|x| part_thing(x)).collect();
+    let vars: BTreeMap<String, Value> = [
+        ("parts".into(), parts.into()),
+        ("currency".into(), currency.to_string().into()),
+    ].into();
+
+    let sql = "SELECT * FROM price WHERE part INSIDE $parts AND currency = $currency ORDER BY observed_at ASC";
+    let prices = execute_price_query(sql, vars, db).await?;
+
+    Ok(lowest_prices(&prices))
+}
+
+pub async fn lowest_price(props: web::Json<GetLowestPricesProps>, db: Data<DB>) -> ApiResult<HttpResponse> {
+    Ok(HttpResponse::Ok().json(get_lowest_prices(&props.parts, props.currency, &db).await?))
+}
@@ -0,0 +1,196 @@
+use std::collections::BTreeMap;
+
+use actix_web::web::Data;
+use anyhow::bail;
+use common::{DBPart, DBPartProps, GetPartProps, audit::{AuditAction, diff}, query::{GetPropertyValuesProps, PartsPage}, types::PartId};
+use serde_json::Value as JsonValue;
+use surrealdb::sql::{Id, Thing, Value};
+
+use crate::{DB, audit::{AuditEntry, execute_audited, execute_audited_batch}, parts::{PartQuery, into_parts, part_content}};
+
+#[cfg(test)]
+pub mod memory;
+
+/// Storage of parts. Handlers of parts only use this trait, so they work the same with any storage,
+/// e.g. [`memory::MemoryParts`] in tests.
+pub trait PartRepository: 'static {
+    async fn get(&self, id: &PartId) -> anyhow::Result<Option<DBPart>>;
+
+    /// Existing parts of the ids, unknown ids are left out
+    async fn get_many(&self, ids: &[String]) -> anyhow::Result<Vec<DBPart>>;
+
+    /// Page of parts matching the query, id of the query is ignored.
+    /// Invalid queries fail with [`crate::parts::QueryError`].
+    async fn page(&self, props: &GetPartProps) -> anyhow::Result<PartsPage>;
+
+    /// Distinct values of a property, key has to be checked beforehand
+    async fn property_values(&self, props: &GetPropertyValuesProps) -> anyhow::Result<Vec<JsonValue>>;
+
+    async fn create(&self, props: &DBPartProps, user: &str) -> anyhow::Result<DBPart>;
+
+    /// Creates all parts or none of them
+    async fn create_many(&self, parts: &[DBPartProps], user: &str) -> anyhow::Result<()>;
+
+    /// Replaces content of an existing part, `before` is its current content
+    async fn replace(&self, id: &PartId, before: &DBPartProps, after: &DBPartProps, user: &str) -> anyhow::Result<Option<DBPart>>;
+
+    async fn delete(&self, id: &PartId, before: &DBPartProps, user: &str) -> anyhow::Result<()>;
+}
+
+/// Parts stored in SurrealDB, every mutation is recorded in the audit log in the same transaction.
+/// Nothing is locked around queries, so requests of all workers are handled concurrently.
+#[derive(Clone)]
+pub struct SurrealParts {
+    db: Data<DB>,
+}
+
+impl SurrealParts {
+    pub fn new(db: Data<DB>) -> Self {
+        Self { db }
+    }
+
+    async fn select(&self, sql: &str, vars: BTreeMap<String, Value>) -> anyhow::Result<Vec<DBPart>> {
+        let response = self.db.datastore.execute(sql, &self.db.session, Some(vars), false).await?;
+
+        match response.into_iter().next() {
+            Some(response) => into_parts(response.result?),
+            None => bail!("No response from DB"),
+        }
+    }
+}
+
+impl PartRepository for SurrealParts {
+    async fn get(&self, id: &PartId) -> anyhow::Result<Option<DBPart>> {
+        let vars: BTreeMap<String, Value> = [
+            ("part".into(), id.into()),
+        ].into();
+
+        Ok(self.select("SELECT * FROM $part", vars).await?.into_iter().next())
+    }
+
+    async fn get_many(&self, ids: &[String]) -> anyhow::Result<Vec<DBPart>> {
+        let things: Vec<Value> = ids.iter()
+            .map(|id| Value::from(Thing::from(("part", id.as_str()))))
+            .collect();
+        let vars: BTreeMap<String, Value> = [
+            ("ids".into(), things.into()),
+        ].into();
+
+        self.select("SELECT * FROM $ids", vars).await
+    }
+
+    async fn page(&self, props: &GetPartProps) -> anyhow::Result<PartsPage> {
+        let query = PartQuery::new(props)?;
+        let sql = format!("{}; {}", query.select_sql(), query.count_sql());
+
+        let response = self.db.datastore.execute(&sql, &self.db.session, Some(query.vars), false).await?;
+
+        let mut response = response.into_iter();
+        let (parts, count) = match (response.next(), response.next()) {
+            (Some(parts), Some(count)) => (parts.result?, count.result?),
+            _ => bail!("No response from DB"),
+        };
+
+        // Grouped count returns no rows when nothing matches
+        let total = match count.first().pick(&["total".into()]) {
+            Value::Number(total) => total.as_int() as u32,
+            _ => 0,
+        };
+
+        Ok(PartsPage {
+            parts: into_parts(parts)?,
+            total,
+            offset: props.offset,
+            limit: props.limit,
+        })
+    }
+
+    async fn property_values(&self, props: &GetPropertyValuesProps) -> anyhow::Result<Vec<JsonValue>> {
+        let query = PartQuery::new(&GetPartProps {
+            category: props.category.clone(),
+            ..Default::default()
+        })?;
+
+        let response = self.db.datastore.execute(&query.values_sql(&props.key), &self.db.session, Some(query.vars), false).await?;
+
+        let result = match response.into_iter().next() {
+            Some(response) => response.result?,
+            None => bail!("No response from DB"),
+        };
+
+        #[derive(serde::Deserialize)]
+        struct Row {
+            #[serde(default)]
+            properties: serde_json::Map<String, JsonValue>,
+        }
+
+        let rows: Vec<Row> = serde_json::from_value(serde_json::to_value(result)?)?;
+        // Parts without the property are grouped as null
+        Ok(rows.into_iter()
+            .filter_map(|mut row| row.properties.remove(&props.key))
+            .filter(|value| !value.is_null())
+            .collect())
+    }
+
+    async fn create(&self, props: &DBPartProps, user: &str) -> anyhow::Result<DBPart> {
+        let vars: BTreeMap<String, Value> = [
+            ("props".into(), part_content(props)?),
+        ].into();
+        let entry = AuditEntry {
+            part: Id::rand().to_raw(),
+            user: user.to_string(),
+            action: AuditAction::Create,
+            changes: diff(None, Some(props)),
+        };
+
+        let result = execute_audited("CREATE $part CONTENT $props", vars, entry, &self.db).await?;
+        match into_parts(result)?.into_iter().next() {
+            Some(part) => Ok(part),
+            None => bail!("Created part wasn't returned"),
+        }
+    }
+
+    async fn create_many(&self, parts: &[DBPartProps], user: &str) -> anyhow::Result<()> {
+        let mut vars: BTreeMap<String, Value> = BTreeMap::new();
+        let mut mutations = Vec::new();
+        for (index, part) in parts.iter().enumerate() {
+            vars.insert(format!("props{}", index), part_content(part)?);
+            let entry = AuditEntry {
+                part: Id::rand().to_raw(),
+                user: user.to_string(),
+                action: AuditAction::Create,
+                changes: diff(None, Some(part)),
+            };
+            mutations.push((format!("CREATE $part{index} CONTENT $props{index}"), entry));
+        }
+
+        execute_audited_batch(mutations, vars, &self.db).await
+    }
+
+    async fn replace(&self, id: &PartId, before: &DBPartProps, after: &DBPartProps, user: &str) -> anyhow::Result<Option<DBPart>> {
+        let vars: BTreeMap<String, Value> = [
+            ("props".into(), part_content(after)?),
+        ].into();
+        let entry = AuditEntry {
+            part: id.to_string(),
+            user: user.to_string(),
+            action: AuditAction::Update,
+            changes: diff(Some(before), Some(after)),
+        };
+
+        let result = execute_audited("UPDATE $part CONTENT $props", vars, entry, &self.db).await?;
+        Ok(into_parts(result)?.into_iter().next())
+    }
+
+    async fn delete(&self, id: &PartId, before: &DBPartProps, user: &str) -> anyhow::Result<()> {
+        let entry = AuditEntry {
+            part: id.to_string(),
+            user: user.to_string(),
+            action: AuditAction::Delete,
+            changes: diff(Some(before), None),
+        };
+
+        execute_audited("DELETE $part", BTreeMap::new(), entry, &self.db).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+use std::{cmp::Ordering, collections::BTreeMap, sync::RwLock};
+
+use common::{DBPart, DBPartProps, GetPartProps, query::{GetPropertyValuesProps, PartsPage, PropertyKind, SortDirection, property_keys}, types::PartId};
+use serde_json::{Map, Value as JsonValue};
+use surrealdb::sql::Id;
+
+use crate::parts::PartQuery;
+
+use super::PartRepository;
+
+/// Parts kept in memory, evaluated the way the database evaluates [`PartQuery`].
+/// Mutations aren't recorded in the audit log.
+#[derive(Default)]
+pub struct MemoryParts {
+    parts: RwLock<BTreeMap<String, Record>>,
+}
+
+/// Part with its indexed properties, like a record in the database
+struct Record {
+    props: DBPartProps,
+    properties: Map<String, JsonValue>,
+}
+
+impl From<&DBPartProps> for Record {
+    fn from(props: &DBPartProps) -> Self {
+        Self {
+            props: props.clone(),
+            properties: props.indexed_properties(),
+        }
+    }
+}
+
+fn with_id(id: &str, props: &DBPartProps) -> DBPart {
+    let props = props.clone();
+    DBPart {
+        id: id.to_string(),
+        name: props.name,
+        image_url: props.image_url,
+        model: props.model,
+        manufactuer: props.manufactuer,
+        release_date: props.release_date,
+        rating: props.rating,
+        review_count: props.review_count,
+        category: props.category,
+    }
+}
+
+/// Order of indexed values, missing values come first like NONE in the database
+fn compare(a: Option<&JsonValue>, b: Option<&JsonValue>) -> Ordering {
+    match (a, b) {
+        (Some(JsonValue::Number(a)), Some(JsonValue::Number(b))) => a.as_f64().partial_cmp(&b.as_f64()).unwrap_or(Ordering::Equal),
+        (Some(JsonValue::String(a)), Some(JsonValue::String(b))) => a.cmp(b),
+        (Some(a), Some(b)) => a.to_string().cmp(&b.to_string()),
+        (a, b) => a.is_some().cmp(&b.is_some()),
+    }
+}
+
+fn matches(record: &Record, props: &GetPartProps, keys: &BTreeMap<String, PropertyKind>) -> bool {
+    let part = &record.props;
+    if props.category.as_ref().is_some_and(|category| *category != part.category.to_string()) {
+        return false;
+    }
+
+    if let Some(text) = &props.query {
+        let text = text.to_lowercase();
+        if ![&part.name, &part.model, &part.manufactuer].iter().any(|x| x.to_lowercase().contains(&text)) {
+            return false;
+        }
+    }
+
+    props.filters.iter().all(|filter| {
+        let numeric = keys.get(&filter.key).is_some_and(|x| x.is_numeric());
+        record.properties.get(&filter.key).is_some_and(|value| filter.condition.matches(value, numeric))
+    })
+}
+
+impl MemoryParts {
+    /// Matching parts with their indexed properties, in no particular order
+    fn matching(&self, props: &GetPartProps) -> anyhow::Result<Vec<(DBPart, Map<String, JsonValue>)>> {
+        // Rejects the same queries as the database
+        PartQuery::new(props)?;
+
+        let keys = property_keys();
+        let parts = self.parts.read().unwrap();
+        Ok(parts.iter()
+            .filter(|(_, record)| matches(record, props, &keys))
+            .map(|(id, record)| (with_id(id, &record.props), record.properties.clone()))
+            .collect())
+    }
+}
+
+impl PartRepository for MemoryParts {
+    async fn get(&self, id: &PartId) -> anyhow::Result<Option<DBPart>> {
+        Ok(self.parts.read().unwrap().get(id.as_str()).map(|record| with_id(id.as_str(), &record.props)))
+    }
+
+    async fn get_many(&self, ids: &[String]) -> anyhow::Result<Vec<DBPart>> {
+        let parts = self.parts.read().unwrap();
+        Ok(ids.iter().filter_map(|id| parts.get(id).map(|record| with_id(id, &record.props))).collect())
+    }
+
+    async fn page(&self, props: &GetPartProps) -> anyhow::Result<PartsPage> {
+        let mut parts = self.matching(props)?;
+
+        let (key, direction) = match &props.sort {
+            Some(sort) => (sort.key.as_str(), sort.direction),
+            None => ("name", SortDirection::Ascending),
+        };
+        parts.sort_by(|(_, a), (_, b)| {
+            let order = compare(a.get(key), b.get(key));
+            match direction {
+                SortDirection::Ascending => order,
+                SortDirection::Descending => order.reverse(),
+            }
+        });
+
+        Ok(PartsPage {
+            total: parts.len() as u32,
+            parts: parts.into_iter()
+                .skip(props.offset as usize)
+                .take(props.limit as usize)
+                .map(|(part, _)| part)
+                .collect(),
+            offset: props.offset,
+            limit: props.limit,
+        })
+    }
+
+    async fn property_values(&self, props: &GetPropertyValuesProps) -> anyhow::Result<Vec<JsonValue>> {
+        let parts = self.matching(&GetPartProps {
+            category: props.category.clone(),
+            ..Default::default()
+        })?;
+
+        let mut values: Vec<JsonValue> = parts.into_iter()
+            .filter_map(|(_, mut properties)| properties.remove(&props.key))
+            .collect();
+        values.sort_by(|a, b| compare(Some(a), Some(b)));
+        values.dedup();
+
+        Ok(values)
+    }
+
+    async fn create(&self, props: &DBPartProps, _user: &str) -> anyhow::Result<DBPart> {
+        let id = Id::rand().to_raw();
+        self.parts.write().unwrap().insert(id.clone(), props.into());
+
+        Ok(with_id(&id, props))
+    }
+
+    async fn create_many(&self, parts: &[DBPartProps], user: &str) -> anyhow::Result<()> {
+        for part in parts {
+            self.create(part, user).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn replace(&self, id: &PartId, _before: &DBPartProps, after: &DBPartProps, _user: &str) -> anyhow::Result<Option<DBPart>> {
+        // Like UPDATE, a missing part is created
+        self.parts.write().unwrap().insert(id.to_string(), after.into());
+
+        Ok(Some(with_id(id.as_str(), after)))
+    }
+
+    async fn delete(&self, id: &PartId, _before: &DBPartProps, _user: &str) -> anyhow::Result<()> {
+        self.parts.write().unwrap().remove(id.as_str());
+
+        Ok(())
+    }
+}
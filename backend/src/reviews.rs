@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+
+use actix_web::{web::{self, Data}, HttpResponse};
+use anyhow::bail;
+use common::{error::{ApiError, ApiResult}, review::{PartReviews, Review, SubmitReviewProps}, types::PartId, validation::validate_review};
+use surrealdb::sql::{Value, Thing};
+
+use crate::{DB, auth::{Authenticated, can}, parts::existing_part, repository::PartRepository};
+
+fn review_vars(part: &PartId, user: Option<&str>) -> BTreeMap<String, Value> {
+    let mut vars: BTreeMap<String, Value> = [
+        ("part".into(), part.into()),
+    ].into();
+    if let Some(user) = user {
+        vars.insert("user".into(), Thing::from(("user", user)).into());
+    }
+
+    vars
+}
+
+async fn execute(sql: &str, vars: BTreeMap<String, Value>, db: &Data<DB>) -> anyhow::Result<Value> {
+    let response = db.datastore.execute(sql, &db.session, Some(vars), false).await?;
+
+    match response.into_iter().next() {
+        Some(response) => Ok(response.result?),
+        None => bail!("No response from DB"),
+    }
+}
+
+/// Reviews of the part, newest first. Reviews of deleted users are left out.
+async fn get_reviews(part: &PartId, db: &Data<DB>) -> anyhow::Result<Vec<Review>> {
+    let sql = "SELECT part, user.username AS username, stars, text, updated_at FROM review WHERE part = $part AND user.id != NONE ORDER BY updated_at DESC";
+    let result = execute(sql, review_vars(part, None), db).await?;
+
+    let mut reviews: Vec<Review> = serde_json::from_value(serde_json::to_value(result)?)?;
+    for review in reviews.iter_mut() {
+        review.part = review.part.replace("part:", "");
+    }
+
+    Ok(reviews)
+}
+
+/// Recomputes rating of `$part` from its reviews, the same way as [`common::review::average_rating`].
+/// It's run in the transaction that changes the reviews, so concurrent reviews can't leave a stale rating.
+/// It's not recorded in the audit log, reviews are kept on their own.
+const RECOMPUTE_RATING: &str = "
+    LET $reviews = (SELECT stars FROM review WHERE part = $part AND user.id != NONE);
+    LET $rating = IF count($reviews) > 0 THEN math::fixed(math::mean($reviews.stars), 2) ELSE 0 END;
+    UPDATE $part SET rating = <string> $rating, review_count = count($reviews), properties.rating = $rating;
+";
+
+/// Runs change of reviews of `$part` and recomputes its rating in one transaction
+async fn execute_with_rating(sql: &str, vars: BTreeMap<String, Value>, db: &Data<DB>) -> anyhow::Result<()> {
+    let sql = format!("BEGIN TRANSACTION; {}; {} COMMIT TRANSACTION;", sql, RECOMPUTE_RATING);
+
+    let responses = db.datastore.execute(&sql, &db.session, Some(vars), false).await?;
+    for response in responses {
+        response.result?;
+    }
+
+    Ok(())
+}
+
+/// Reviews of the part with its rating
+async fn part_reviews<R: PartRepository>(part: &PartId, parts: &R, db: &Data<DB>) -> ApiResult<HttpResponse> {
+    let reviews = get_reviews(part, db).await?;
+    let part = existing_part(part, parts).await?;
+
+    Ok(HttpResponse::Ok().json(PartReviews {
+        rating: part.rating,
+        review_count: part.review_count,
+        reviews,
+    }))
+}
+
+pub async fn reviews<R: PartRepository>(part: web::Path<PartId>, parts: Data<R>, db: Data<DB>) -> ApiResult<HttpResponse> {
+    part_reviews(&part, parts.as_ref(), &db).await
+}
+
+/// Creates review of the logged in user, or replaces the one they already wrote
+pub async fn submit_review<R: PartRepository>(auth: Authenticated<can::WriteReviews>, part: web::Path<PartId>, props: web::Json<SubmitReviewProps>, parts: Data<R>, db: Data<DB>) -> ApiResult<HttpResponse> {
+    validate_review(&props).map_err(ApiError::validation)?;
+    existing_part(&part, parts.as_ref()).await?;
+
+    let props = props.into_inner();
+    let mut vars = review_vars(&part, Some(&auth.user.id));
+    vars.insert("stars".into(), (props.stars as i64).into());
+    vars.insert("text".into(), props.text.into());
+
+    // Review has an id made of the part and the user, so updating it creates it when it's missing.
+    // Reviews created before that have random ids, they are removed so the author index doesn't fail.
+    vars.insert("review".into(), Thing::from(("review", format!("{}-{}", part.as_str(), auth.user.id).as_str())).into());
+    let sql = "
+        DELETE review WHERE part = $part AND user = $user AND id != $review;
+        UPDATE $review SET part = $part, user = $user, stars = $stars, text = $text, updated_at = time::now()
+    ";
+    execute_with_rating(sql, vars, &db).await?;
+
+    part_reviews(&part, parts.as_ref(), &db).await
+}
+
+/// Removes review of the logged in user, removing a missing review does nothing
+pub async fn delete_review<R: PartRepository>(auth: Authenticated<can::WriteReviews>, part: web::Path<PartId>, parts: Data<R>, db: Data<DB>) -> ApiResult<HttpResponse> {
+    existing_part(&part, parts.as_ref()).await?;
+    execute_with_rating("DELETE review WHERE part = $part AND user = $user", review_vars(&part, Some(&auth.user.id)), &db).await?;
+
+    part_reviews(&part, parts.as_ref(), &db).await
+}
@@ -0,0 +1,156 @@
+use std::collections::BTreeMap;
+
+use actix_web::{web::{self, Data}, HttpResponse};
+use anyhow::bail;
+use common::{error::{ApiError, ApiResult}, user::{CreateUserProps, DisableUserProps, ResetPasswordProps, User}, validation::{validate_password, validate_user}};
+use surrealdb::sql::{Value, Thing};
+
+use crate::{DB, auth::{Authenticated, can}};
+
+/// Password hash is never selected, so it can't end up in a response
+const USER_FIELDS: &str = "id, username, role, disabled";
+
+pub async fn execute_user_query(sql: &str, vars: BTreeMap<String, Value>, db: &Data<DB>) -> anyhow::Result<Vec<User>> {
+    let response = db.datastore.execute(sql, &db.session, Some(vars), false).await?;
+
+    let result = match response.into_iter().next() {
+        Some(response) => response.result?,
+        None => bail!("No response from DB"),
+    };
+
+    let json = serde_json::to_value(result)?;
+    let mut users: Vec<User> = serde_json::from_value(json)?;
+    for user in users.iter_mut() {
+        user.id = user.id.replace("user:", "");
+    }
+
+    Ok(users)
+}
+
+fn user_thing(id: &str) -> Value {
+    Thing::from(("user", id)).into()
+}
+
+/// Returns None if username is already taken
+pub async fn create_user_raw(props: &CreateUserProps, db: &Data<DB>) -> ApiResult<Option<User>> {
+    validate_user(props).map_err(ApiError::validation)?;
+
+    let vars: BTreeMap<String, Value> = [
+        ("username".into(), props.username.clone().into()),
+        ("password".into(), props.password.clone().into()),
+        ("role".into(), props.role.to_string().into()),
+    ].into();
+
+    if find_user(&props.username, db).await?.is_some() {
+        return Ok(None);
+    }
+
+    let sql = format!(
+        "CREATE user SET username = $username, password = crypto::argon2::generate($password), role = $role, disabled = false RETURN {}",
+        USER_FIELDS
+    );
+    let users = execute_user_query(&sql, vars, db).await?;
+
+    Ok(users.into_iter().next())
+}
+
+/// User with given credentials, None if they are wrong or user is disabled
+pub async fn check_credentials(username: &str, password: &str, db: &Data<DB>) -> anyhow::Result<Option<User>> {
+    let sql = format!(
+        "SELECT {} FROM user WHERE username = $username AND disabled != true AND crypto::argon2::compare(password, $password)",
+        USER_FIELDS
+    );
+    let vars: BTreeMap<String, Value> = [
+        ("username".into(), username.into()),
+        ("password".into(), password.into()),
+    ].into();
+
+    let users = execute_user_query(&sql, vars, db).await?;
+
+    Ok(users.into_iter().next())
+}
+
+/// Hashes passwords of users stored before passwords were hashed.
+/// Those users could create parts, so they become editors. Does nothing once all rows are migrated.
+pub async fn hash_plaintext_passwords(db: &Data<DB>) -> anyhow::Result<()> {
+    let sql = "
+        UPDATE user SET password = crypto::argon2::generate(password) WHERE string::startsWith(password, '$argon2') = false;
+        UPDATE user SET role = 'Editor' WHERE role = NONE;
+        UPDATE user SET disabled = false WHERE disabled = NONE;
+    ";
+
+    let responses = db.datastore.execute(sql, &db.session, None, false).await?;
+
+    for response in responses {
+        response.result?;
+    }
+
+    Ok(())
+}
+
+pub async fn users(_auth: Authenticated<can::ManageUsers>, db: Data<DB>) -> ApiResult<HttpResponse> {
+    let sql = format!("SELECT {} FROM user ORDER BY username", USER_FIELDS);
+    Ok(HttpResponse::Ok().json(execute_user_query(&sql, BTreeMap::new(), &db).await?))
+}
+
+pub async fn create_user(_auth: Authenticated<can::ManageUsers>, props: web::Json<CreateUserProps>, db: Data<DB>) -> ApiResult<HttpResponse> {
+    match create_user_raw(&props, &db).await? {
+        Some(user) => Ok(HttpResponse::Ok().json(user)),
+        None => Err(ApiError::conflict(format!("Username {} is taken", props.username))),
+    }
+}
+
+pub async fn find_user(username: &str, db: &Data<DB>) -> anyhow::Result<Option<User>> {
+    let sql = format!("SELECT {} FROM user WHERE username = $username", USER_FIELDS);
+    let vars: BTreeMap<String, Value> = [
+        ("username".into(), username.into()),
+    ].into();
+
+    Ok(execute_user_query(&sql, vars, db).await?.into_iter().next())
+}
+
+/// Updates existing user, UPDATE on a missing record would create it. Returns None if user doesn't exist.
+pub async fn update_user_raw(id: &str, set: &str, mut vars: BTreeMap<String, Value>, db: &Data<DB>) -> anyhow::Result<Option<User>> {
+    vars.insert("user".into(), user_thing(id));
+
+    let sql = format!("SELECT {} FROM $user", USER_FIELDS);
+    if execute_user_query(&sql, vars.clone(), db).await?.is_empty() {
+        return Ok(None);
+    }
+
+    let sql = format!("UPDATE $user SET {} RETURN {}", set, USER_FIELDS);
+    match execute_user_query(&sql, vars, db).await?.into_iter().next() {
+        Some(user) => Ok(Some(user)),
+        None => bail!("No response from DB"),
+    }
+}
+
+async fn update_user(id: &str, set: &str, vars: BTreeMap<String, Value>, db: &Data<DB>) -> ApiResult<HttpResponse> {
+    match update_user_raw(id, set, vars, db).await? {
+        Some(user) => Ok(HttpResponse::Ok().json(user)),
+        None => Err(ApiError::not_found("User doesn't exist")),
+    }
+}
+
+pub async fn disable_user(auth: Authenticated<can::ManageUsers>, props: web::Json<DisableUserProps>, db: Data<DB>) -> ApiResult<HttpResponse> {
+    // Last admin could lock everyone out
+    if props.disabled && auth.user.id == props.id {
+        return Err(ApiError::invalid("Can't disable yourself"));
+    }
+
+    let vars: BTreeMap<String, Value> = [
+        ("disabled".into(), props.disabled.into()),
+    ].into();
+
+    update_user(&props.id, "disabled = $disabled", vars, &db).await
+}
+
+pub async fn reset_password(_auth: Authenticated<can::ManageUsers>, props: web::Json<ResetPasswordProps>, db: Data<DB>) -> ApiResult<HttpResponse> {
+    validate_password(&props.password).map_err(ApiError::validation)?;
+
+    let vars: BTreeMap<String, Value> = [
+        ("password".into(), props.password.clone().into()),
+    ].into();
+
+    update_user(&props.id, "password = crypto::argon2::generate($password)", vars, &db).await
+}
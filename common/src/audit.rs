@@ -0,0 +1,178 @@
+use serde::{Serialize, Deserialize};
+use serde_json::{Map, Value};
+
+use crate::{DBPartProps, types::PartId};
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+}
+
+/// Change of a single field. Nested fields are joined with dots, e.g. `category.CPU.cores`
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct FieldChange {
+    pub key: String,
+    /// None: field didn't exist before
+    pub before: Option<Value>,
+    /// None: field doesn't exist after
+    pub after: Option<Value>,
+}
+
+/// Mutation of a part, who made it and when
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct AuditRecord {
+    pub id: String,
+    pub part: String,
+    pub user: String,
+    pub action: AuditAction,
+    /// RFC 3339 timestamp
+    pub time: String,
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UpdatePartProps {
+    pub id: PartId,
+    pub props: DBPartProps,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PatchPartProps {
+    pub id: PartId,
+    /// Merged into the part as JSON merge patch, so only changed fields have to be sent
+    pub changes: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DeletePartProps {
+    pub id: PartId,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetPartHistoryProps {
+    pub id: PartId,
+}
+
+/// Fields that differ between two versions of a part.
+/// None stands for a part that doesn't exist, e.g. before it was created.
+pub fn diff(before: Option<&DBPartProps>, after: Option<&DBPartProps>) -> Vec<FieldChange> {
+    let mut before_fields = Map::new();
+    let mut after_fields = Map::new();
+    if let Some(Value::Object(before)) = before.and_then(|x| serde_json::to_value(x).ok()) {
+        flatten("", before, &mut before_fields);
+    }
+    if let Some(Value::Object(after)) = after.and_then(|x| serde_json::to_value(x).ok()) {
+        flatten("", after, &mut after_fields);
+    }
+
+    let mut keys: Vec<&String> = before_fields.keys().chain(after_fields.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    keys.into_iter()
+        .filter(|key| before_fields.get(*key) != after_fields.get(*key))
+        .map(|key| FieldChange {
+            key: key.clone(),
+            before: before_fields.get(key).cloned(),
+            after: after_fields.get(key).cloned(),
+        })
+        .collect()
+}
+
+fn flatten(prefix: &str, object: Map<String, Value>, fields: &mut Map<String, Value>) {
+    for (key, value) in object {
+        let key = match prefix.is_empty() {
+            true => key,
+            false => format!("{}.{}", prefix, key),
+        };
+
+        match value {
+            Value::Object(object) => flatten(&key, object, fields),
+            value => { fields.insert(key, value); },
+        }
+    }
+}
+
+/// Applies JSON merge patch (RFC 7396): objects are merged recursively, null removes a field
+pub fn merge(target: &mut Value, patch: &Value) {
+    let patch = match patch {
+        Value::Object(patch) => patch,
+        patch => {
+            *target = patch.clone();
+            return;
+        },
+    };
+
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+
+    if let Value::Object(target) = target {
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(key);
+                continue;
+            }
+
+            merge(target.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{DBPartProps, PartsCategory, CPUProperties};
+
+    use super::{diff, merge};
+
+    #[test]
+    fn part_diff() {
+        let before = DBPartProps {
+            model: "i5-13500".into(),
+            category: PartsCategory::CPU(CPUProperties {
+                cores: 14,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut after = before.clone();
+        after.model = "i5-13600".into();
+        if let PartsCategory::CPU(props) = &mut after.category {
+            props.cores = 16;
+        }
+
+        let changes = diff(Some(&before), Some(&after));
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].key, "category.CPU.cores");
+        assert_eq!(changes[0].before, Some(json!(14)));
+        assert_eq!(changes[0].after, Some(json!(16)));
+        assert_eq!(changes[1].key, "model");
+
+        assert!(diff(Some(&before), Some(&before)).is_empty());
+        assert!(diff(None, Some(&before)).iter().all(|x| x.before.is_none()));
+    }
+
+    #[test]
+    fn merge_patch() {
+        let mut part = json!({
+            "model": "i5-13500",
+            "category": { "CPU": { "cores": 14, "threads": 20 } },
+        });
+        merge(&mut part, &json!({
+            "model": "i5-13600",
+            "category": { "CPU": { "cores": 16 } },
+        }));
+
+        assert_eq!(part, json!({
+            "model": "i5-13600",
+            "category": { "CPU": { "cores": 16, "threads": 20 } },
+        }));
+
+        merge(&mut part, &json!({ "category": null }));
+        assert_eq!(part, json!({ "model": "i5-13600" }));
+    }
+}
@@ -0,0 +1,156 @@
+use serde::{Serialize, Deserialize};
+#[cfg(feature = "surreal")]
+use surrealdb::sql::Value;
+
+use crate::PartsCategory;
+
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct DBBuild {
+    pub id: String,
+    pub name: String,
+    pub slots: BuildSlots,
+    /// Id of the user who created the build, empty for builds stored before builds had owners
+    #[serde(default)]
+    pub owner: String,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Debug)]
+pub struct DBBuildProps {
+    pub name: String,
+    pub slots: BuildSlots,
+}
+
+#[cfg(feature = "surreal")]
+impl From<DBBuildProps> for Value {
+    fn from(value: DBBuildProps) -> Self {
+        crate::convert_to_value(&value).unwrap()
+    }
+}
+
+/// Ids of parts in a build. Every slot holds at most one part,
+/// except RAM and storage which can hold many.
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Debug)]
+pub struct BuildSlots {
+    pub cpu: Option<String>,
+    pub gpu: Option<String>,
+    pub motherboard: Option<String>,
+    pub ram: Vec<String>,
+    pub storage: Vec<String>,
+    pub psu: Option<String>,
+    pub case: Option<String>,
+    pub cooler: Option<String>,
+}
+
+impl BuildSlots {
+    /// Puts part id into slot matching its category, replacing part already in a single slot.
+    /// Returns false if category of the part doesn't have a slot.
+    pub fn insert(&mut self, id: String, category: &PartsCategory) -> bool {
+        match category {
+            PartsCategory::Basic => return false,
+            PartsCategory::CPU(_) => self.cpu = Some(id),
+            PartsCategory::GPU(_) => self.gpu = Some(id),
+            PartsCategory::Motherboard(_) => self.motherboard = Some(id),
+            PartsCategory::RAM(_) => self.ram.push(id),
+            PartsCategory::Storage(_) => self.storage.push(id),
+            PartsCategory::PSU(_) => self.psu = Some(id),
+            PartsCategory::Case(_) => self.case = Some(id),
+            PartsCategory::Cooler(_) => self.cooler = Some(id),
+        }
+
+        true
+    }
+
+    pub fn remove(&mut self, id: &str) {
+        for slot in [&mut self.cpu, &mut self.gpu, &mut self.motherboard, &mut self.psu, &mut self.case, &mut self.cooler] {
+            if slot.as_deref() == Some(id) {
+                *slot = None;
+            }
+        }
+
+        self.ram.retain(|x| x != id);
+        self.storage.retain(|x| x != id);
+    }
+
+    /// All part ids, paired with name of the slot they are in
+    pub fn slots(&self) -> Vec<(&'static str, String)> {
+        let mut slots: Vec<(&'static str, String)> = Vec::new();
+        let single = [
+            ("CPU", &self.cpu),
+            ("GPU", &self.gpu),
+            ("Motherboard", &self.motherboard),
+        ];
+        for (name, id) in single {
+            if let Some(id) = id {
+                slots.push((name, id.clone()));
+            }
+        }
+
+        for id in &self.ram {
+            slots.push(("RAM", id.clone()));
+        }
+
+        for id in &self.storage {
+            slots.push(("Storage", id.clone()));
+        }
+
+        let single = [
+            ("PSU", &self.psu),
+            ("Case", &self.case),
+            ("Cooler", &self.cooler),
+        ];
+        for (name, id) in single {
+            if let Some(id) = id {
+                slots.push((name, id.clone()));
+            }
+        }
+
+        slots
+    }
+
+    pub fn ids(&self) -> Vec<String> {
+        self.slots().into_iter().map(|(_, id)| id).collect()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetBuildProps {
+    /// None: Get all builds
+    /// Some: Get build with specified id
+    pub id: Option<String>,
+    pub limit: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UpdateBuildProps {
+    pub id: String,
+    pub props: DBBuildProps,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DeleteBuildProps {
+    pub id: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{PartsCategory, RAMProperties, CPUProperties};
+
+    use super::BuildSlots;
+
+    #[test]
+    fn slots() {
+        let cpu = PartsCategory::CPU(CPUProperties::default());
+        let ram = PartsCategory::RAM(RAMProperties::default());
+
+        let mut slots = BuildSlots::default();
+        assert!(slots.insert("cpu".into(), &cpu));
+        assert!(slots.insert("ram".into(), &ram));
+        assert!(slots.insert("ram".into(), &ram));
+        assert!(!slots.insert("monitor".into(), &PartsCategory::Basic));
+
+        assert_eq!(slots.ids(), vec!["cpu", "ram", "ram"]);
+
+        slots.remove("ram");
+        assert_eq!(slots.slots(), vec![("CPU", "cpu".to_string())]);
+    }
+}
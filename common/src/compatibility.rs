@@ -0,0 +1,375 @@
+use std::fmt::Display;
+
+use serde::{Serialize, Deserialize};
+
+use crate::{types::{Power, DataSize}, DBPart, PartsCategory, CPUProperties, GPUProperties, RAMProperties, MotherboardProperties, PSUProperties, CoolerProperties};
+
+/// Power drawn by everything that isn't a CPU or GPU (drives, fans, board, RAM)
+const BASE_SYSTEM_POWER: Power = Power::new(100_000);
+/// DIMMs that can be populated per memory channel
+const MODULES_PER_CHANNEL: u32 = 2;
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub enum CompatibilityIssue {
+    SocketMismatch {
+        cpu: String,
+        cpu_socket: String,
+        part: String,
+        part_sockets: String,
+    },
+    MemoryTypeMismatch {
+        ram: String,
+        ram_type: String,
+        motherboard: String,
+        motherboard_type: String,
+    },
+    PcieVersionMismatch {
+        gpu: String,
+        gpu_version: String,
+        part: String,
+        part_version: String,
+    },
+    CoolerInsufficient {
+        cooler: String,
+        cooler_tdp: Power,
+        cpu: String,
+        cpu_tdp: Power,
+    },
+    PsuInsufficient {
+        psu: String,
+        wattage: Power,
+        required: Power,
+    },
+    RamOverCapacity {
+        total: DataSize,
+        max: DataSize,
+        part: String,
+    },
+    TooManyMemoryModules {
+        modules: u32,
+        max: u32,
+        part: String,
+    },
+}
+
+impl CompatibilityIssue {
+    pub fn severity(&self) -> Severity {
+        match self {
+            CompatibilityIssue::PcieVersionMismatch { .. } => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl Display for CompatibilityIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompatibilityIssue::SocketMismatch { cpu, cpu_socket, part, part_sockets } =>
+                write!(f, "{} uses socket {}, but {} supports {}", cpu, cpu_socket, part, part_sockets),
+            CompatibilityIssue::MemoryTypeMismatch { ram, ram_type, motherboard, motherboard_type } =>
+                write!(f, "{} is {}, but {} supports {}", ram, ram_type, motherboard, motherboard_type),
+            CompatibilityIssue::PcieVersionMismatch { gpu, gpu_version, part, part_version } =>
+                write!(f, "{} uses PCIe {}, but {} only supports PCIe {}", gpu, gpu_version, part, part_version),
+            CompatibilityIssue::CoolerInsufficient { cooler, cooler_tdp, cpu, cpu_tdp } =>
+                write!(f, "{} handles up to {}, but {} draws up to {}", cooler, cooler_tdp, cpu, cpu_tdp),
+            CompatibilityIssue::PsuInsufficient { psu, wattage, required } =>
+                write!(f, "{} provides {}, but the build needs about {}", psu, wattage, required),
+            CompatibilityIssue::RamOverCapacity { total, max, part } =>
+                write!(f, "Selected RAM totals {}, but {} supports up to {}", total, part, max),
+            CompatibilityIssue::TooManyMemoryModules { modules, max, part } =>
+                write!(f, "Selected RAM uses {} modules, but {} supports up to {}", modules, part, max),
+        }
+    }
+}
+
+/// Checks every pair of selected parts against each other and returns all found issues.
+/// Parts in categories without any rules (like `Basic`) are ignored.
+pub fn check_compatibility(parts: &[DBPart]) -> Vec<CompatibilityIssue> {
+    let mut cpus: Vec<(&DBPart, &CPUProperties)> = Vec::new();
+    let mut gpus: Vec<(&DBPart, &GPUProperties)> = Vec::new();
+    let mut rams: Vec<(&DBPart, &RAMProperties)> = Vec::new();
+    let mut motherboards: Vec<(&DBPart, &MotherboardProperties)> = Vec::new();
+    let mut psus: Vec<(&DBPart, &PSUProperties)> = Vec::new();
+    let mut coolers: Vec<(&DBPart, &CoolerProperties)> = Vec::new();
+
+    for part in parts {
+        match &part.category {
+            PartsCategory::CPU(props) => cpus.push((part, props)),
+            PartsCategory::GPU(props) => gpus.push((part, props)),
+            PartsCategory::RAM(props) => rams.push((part, props)),
+            PartsCategory::Motherboard(props) => motherboards.push((part, props)),
+            PartsCategory::PSU(props) => psus.push((part, props)),
+            PartsCategory::Cooler(props) => coolers.push((part, props)),
+            _ => {},
+        }
+    }
+
+    let mut issues: Vec<CompatibilityIssue> = Vec::new();
+
+    for (cpu, cpu_props) in &cpus {
+        for (motherboard, motherboard_props) in &motherboards {
+            if !same_value(&cpu_props.socket, &motherboard_props.socket) {
+                issues.push(CompatibilityIssue::SocketMismatch {
+                    cpu: cpu.name.clone(),
+                    cpu_socket: cpu_props.socket.clone(),
+                    part: motherboard.name.clone(),
+                    part_sockets: motherboard_props.socket.clone(),
+                });
+            }
+        }
+
+        for (cooler, cooler_props) in &coolers {
+            let supported = cooler_props.supported_sockets
+                .split(',')
+                .any(|socket| same_value(socket, &cpu_props.socket));
+            if !supported {
+                issues.push(CompatibilityIssue::SocketMismatch {
+                    cpu: cpu.name.clone(),
+                    cpu_socket: cpu_props.socket.clone(),
+                    part: cooler.name.clone(),
+                    part_sockets: cooler_props.supported_sockets.clone(),
+                });
+            }
+
+            if cooler_props.max_tdp < cpu_props.max_tdp {
+                issues.push(CompatibilityIssue::CoolerInsufficient {
+                    cooler: cooler.name.clone(),
+                    cooler_tdp: cooler_props.max_tdp,
+                    cpu: cpu.name.clone(),
+                    cpu_tdp: cpu_props.max_tdp,
+                });
+            }
+        }
+    }
+
+    for (ram, ram_props) in &rams {
+        for (motherboard, motherboard_props) in &motherboards {
+            if !same_value(&ram_props.memory_type, &motherboard_props.memory_type) {
+                issues.push(CompatibilityIssue::MemoryTypeMismatch {
+                    ram: ram.name.clone(),
+                    ram_type: ram_props.memory_type.clone(),
+                    motherboard: motherboard.name.clone(),
+                    motherboard_type: motherboard_props.memory_type.clone(),
+                });
+            }
+        }
+    }
+
+    if !rams.is_empty() {
+        let modules: u32 = rams.iter().map(|(_, props)| props.modules.max(1)).sum();
+        let total: DataSize = rams.iter().map(|(_, props)| props.capacity).sum();
+
+        let mut limits: Vec<(&DBPart, DataSize, u32)> = Vec::new();
+        for (cpu, cpu_props) in &cpus {
+            limits.push((cpu, cpu_props.max_ram_size, cpu_props.max_memory_channels * MODULES_PER_CHANNEL));
+        }
+        for (motherboard, motherboard_props) in &motherboards {
+            limits.push((motherboard, motherboard_props.max_memory, motherboard_props.memory_slots));
+        }
+
+        for (part, max_size, max_modules) in limits {
+            // Zero means the limit is unknown
+            if max_size.get() > 0 && total > max_size {
+                issues.push(CompatibilityIssue::RamOverCapacity {
+                    total,
+                    max: max_size,
+                    part: part.name.clone(),
+                });
+            }
+
+            if max_modules > 0 && modules > max_modules {
+                issues.push(CompatibilityIssue::TooManyMemoryModules {
+                    modules,
+                    max: max_modules,
+                    part: part.name.clone(),
+                });
+            }
+        }
+    }
+
+    for (gpu, gpu_props) in &gpus {
+        let mut supported: Vec<(&DBPart, &str)> = Vec::new();
+        for (cpu, cpu_props) in &cpus {
+            supported.push((cpu, &cpu_props.max_supported_pcie_version));
+        }
+        for (motherboard, motherboard_props) in &motherboards {
+            supported.push((motherboard, &motherboard_props.pcie_version));
+        }
+
+        for (part, version) in supported {
+            if let (Some(gpu_version), Some(part_version)) = (parse_number(&gpu_props.pcie_version), parse_number(version)) {
+                if gpu_version > part_version {
+                    issues.push(CompatibilityIssue::PcieVersionMismatch {
+                        gpu: gpu.name.clone(),
+                        gpu_version: gpu_props.pcie_version.clone(),
+                        part: part.name.clone(),
+                        part_version: version.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    for (psu, psu_props) in &psus {
+        let mut required = estimated_power(parts);
+        for (_, gpu_props) in &gpus {
+            required = required.max(gpu_props.recommended_psu);
+        }
+
+        if psu_props.wattage < required {
+            issues.push(CompatibilityIssue::PsuInsufficient {
+                psu: psu.name.clone(),
+                wattage: psu_props.wattage,
+                required,
+            });
+        }
+    }
+
+    issues
+}
+
+/// Estimates peak power draw of the parts
+pub fn estimated_power(parts: &[DBPart]) -> Power {
+    let mut power = BASE_SYSTEM_POWER;
+    for part in parts {
+        match &part.category {
+            PartsCategory::CPU(props) => power = power + props.max_tdp,
+            PartsCategory::GPU(props) => power = power + props.tdp,
+            _ => {},
+        }
+    }
+
+    power
+}
+
+fn same_value(first: &str, second: &str) -> bool {
+    first.trim().eq_ignore_ascii_case(second.trim())
+}
+
+/// Reads the leading number of version strings like "5.0"
+fn parse_number(value: &str) -> Option<f64> {
+    let number: String = value
+        .trim()
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+
+    number.parse::<f64>().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DBPart, PartsCategory, CPUProperties, MotherboardProperties, RAMProperties, PSUProperties, GPUProperties};
+
+    use crate::types::Power;
+
+    use super::{check_compatibility, estimated_power, CompatibilityIssue, Severity};
+
+    fn part(name: &str, category: PartsCategory) -> DBPart {
+        DBPart {
+            id: name.to_lowercase(),
+            name: name.into(),
+            category,
+            ..Default::default()
+        }
+    }
+
+    fn cpu() -> DBPart {
+        part("i5-13500", PartsCategory::CPU(CPUProperties {
+            max_tdp: "154 W".parse().unwrap(),
+            max_ram_size: "128 GB".parse().unwrap(),
+            max_memory_channels: 2,
+            max_supported_pcie_version: "5.0".into(),
+            socket: "FCLGA1700".into(),
+            ..Default::default()
+        }))
+    }
+
+    fn motherboard(socket: &str) -> DBPart {
+        part("Z790", PartsCategory::Motherboard(MotherboardProperties {
+            socket: socket.into(),
+            memory_type: "DDR5".into(),
+            memory_slots: 4,
+            max_memory: "128 GB".parse().unwrap(),
+            pcie_version: "4.0".into(),
+            ..Default::default()
+        }))
+    }
+
+    fn ram(memory_type: &str, capacity: &str, modules: u32) -> DBPart {
+        part("RAM", PartsCategory::RAM(RAMProperties {
+            memory_type: memory_type.into(),
+            capacity: capacity.parse().unwrap(),
+            modules,
+            ..Default::default()
+        }))
+    }
+
+    #[test]
+    fn compatible_parts() {
+        let parts = vec![cpu(), motherboard("FCLGA1700"), ram("DDR5", "32 GB", 2)];
+
+        assert!(check_compatibility(&parts).is_empty());
+    }
+
+    #[test]
+    fn socket_mismatch() {
+        let parts = vec![cpu(), motherboard("AM5")];
+        let issues = check_compatibility(&parts);
+
+        assert_eq!(issues.len(), 1);
+        assert!(matches!(issues[0], CompatibilityIssue::SocketMismatch { .. }));
+        assert_eq!(issues[0].severity(), Severity::Error);
+    }
+
+    #[test]
+    fn memory_limits() {
+        let parts = vec![cpu(), motherboard("FCLGA1700"), ram("DDR4", "96 GB", 3), ram("DDR5", "64 GB", 3)];
+        let issues = check_compatibility(&parts);
+
+        assert!(issues.iter().any(|x| matches!(x, CompatibilityIssue::MemoryTypeMismatch { .. })));
+        assert!(issues.iter().any(|x| matches!(x, CompatibilityIssue::RamOverCapacity { .. })));
+        assert!(issues.iter().any(|x| matches!(x, CompatibilityIssue::TooManyMemoryModules { .. })));
+    }
+
+    #[test]
+    fn psu_and_pcie() {
+        let gpu = part("RTX", PartsCategory::GPU(GPUProperties {
+            tdp: "320 W".parse().unwrap(),
+            recommended_psu: "750 W".parse().unwrap(),
+            pcie_version: "5.0".into(),
+            ..Default::default()
+        }));
+        let psu = part("PSU", PartsCategory::PSU(PSUProperties {
+            wattage: "550 W".parse().unwrap(),
+            ..Default::default()
+        }));
+        let parts = vec![cpu(), motherboard("FCLGA1700"), gpu, psu];
+        let issues = check_compatibility(&parts);
+
+        assert!(issues.contains(&CompatibilityIssue::PsuInsufficient {
+            psu: "PSU".into(),
+            wattage: "550 W".parse().unwrap(),
+            required: "750 W".parse().unwrap(),
+        }));
+        let pcie = issues.iter().find(|x| matches!(x, CompatibilityIssue::PcieVersionMismatch { .. }));
+        assert_eq!(pcie.map(|x| x.severity()), Some(Severity::Warning));
+    }
+
+    #[test]
+    fn power_of_huge_tdp_saturates() {
+        let gpu = part("RTX", PartsCategory::GPU(GPUProperties {
+            tdp: Power::new(i64::MAX),
+            ..Default::default()
+        }));
+        let parts = vec![cpu(), gpu.clone(), gpu];
+
+        assert_eq!(estimated_power(&parts), Power::new(i64::MAX));
+    }
+}
@@ -0,0 +1,173 @@
+use std::fmt::Display;
+
+use serde::{Serialize, Deserialize};
+
+/// Why a single field of a request is invalid
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct FieldError {
+    /// Key of the field, e.g. `rating` or `cores`
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Error of any endpoint. It's sent as JSON with status of its kind,
+/// e.g. `{ "kind": "NotFound", "message": "Part doesn't exist" }`.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+#[serde(tag = "kind")]
+pub enum ApiError {
+    /// 404
+    NotFound { message: String },
+    /// 400 for requests that can't be read, 422 for ones that break rules of their fields
+    Validation {
+        message: String,
+        #[serde(default)]
+        fields: Vec<FieldError>,
+    },
+    /// 401, no valid session
+    Unauthorized { message: String },
+    /// 403, role of the user doesn't allow it
+    Forbidden { message: String },
+    /// 409
+    Conflict { message: String },
+    /// 500, details are only logged by the server
+    Internal { message: String },
+}
+
+/// Result of a handler, `T` is usually `HttpResponse`
+pub type ApiResult<T> = Result<T, ApiError>;
+
+impl ApiError {
+    pub fn not_found(message: impl Into<String>) -> Self {
+        ApiError::NotFound { message: message.into() }
+    }
+
+    /// Problem with the request as a whole
+    pub fn invalid(message: impl Into<String>) -> Self {
+        ApiError::Validation { message: message.into(), fields: Vec::new() }
+    }
+
+    pub fn validation(fields: Vec<FieldError>) -> Self {
+        let message = match fields.as_slice() {
+            [field] => format!("{}: {}", field.field, field.message),
+            fields => format!("{} fields are invalid", fields.len()),
+        };
+
+        ApiError::Validation { message, fields }
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        ApiError::Unauthorized { message: message.into() }
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        ApiError::Forbidden { message: message.into() }
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        ApiError::Conflict { message: message.into() }
+    }
+
+    pub fn internal() -> Self {
+        ApiError::Internal { message: "Internal server error".into() }
+    }
+
+    /// Error of a response without a readable body, e.g. from a proxy
+    pub fn from_status(status: u16, message: impl Into<String>) -> Self {
+        let message = message.into();
+        match status {
+            400 | 422 => ApiError::Validation { message, fields: Vec::new() },
+            401 => ApiError::Unauthorized { message },
+            403 => ApiError::Forbidden { message },
+            404 => ApiError::NotFound { message },
+            409 => ApiError::Conflict { message },
+            _ => ApiError::Internal { message },
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            ApiError::NotFound { message }
+            | ApiError::Validation { message, .. }
+            | ApiError::Unauthorized { message }
+            | ApiError::Forbidden { message }
+            | ApiError::Conflict { message }
+            | ApiError::Internal { message } => message,
+        }
+    }
+
+    /// Field errors of a validation error, empty for other kinds
+    pub fn fields(&self) -> &[FieldError] {
+        match self {
+            ApiError::Validation { fields, .. } => fields,
+            _ => &[],
+        }
+    }
+
+    pub fn status(&self) -> u16 {
+        match self {
+            ApiError::NotFound { .. } => 404,
+            ApiError::Validation { fields, .. } if fields.is_empty() => 400,
+            ApiError::Validation { .. } => 422,
+            ApiError::Unauthorized { .. } => 401,
+            ApiError::Forbidden { .. } => 403,
+            ApiError::Conflict { .. } => 409,
+            ApiError::Internal { .. } => 500,
+        }
+    }
+}
+
+impl Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// Anything unexpected becomes an internal error, its details stay in the log
+impl From<anyhow::Error> for ApiError {
+    fn from(error: anyhow::Error) -> Self {
+        log::error!("{:#}", error);
+        ApiError::internal()
+    }
+}
+
+#[cfg(feature = "actix")]
+impl actix_web::ResponseError for ApiError {
+    fn status_code(&self) -> actix_web::http::StatusCode {
+        actix_web::http::StatusCode::from_u16(self.status()).unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    fn error_response(&self) -> actix_web::HttpResponse {
+        actix_web::HttpResponse::build(self.status_code()).json(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ApiError, FieldError};
+
+    #[test]
+    fn json_body() {
+        let error = ApiError::not_found("Part doesn't exist");
+        assert_eq!(serde_json::to_value(&error).unwrap(), serde_json::json!({ "kind": "NotFound", "message": "Part doesn't exist" }));
+
+        let error = ApiError::validation(vec![FieldError::new("rating", "must be between 0 and 5")]);
+        assert_eq!(error.status(), 422);
+        assert_eq!(error.to_string(), "rating: must be between 0 and 5");
+        let json = serde_json::to_string(&error).unwrap();
+        assert_eq!(serde_json::from_str::<ApiError>(&json).unwrap(), error);
+
+        assert_eq!(ApiError::invalid("Malformed file").status(), 400);
+        assert_eq!(ApiError::from_status(409, "Taken"), ApiError::conflict("Taken"));
+    }
+}
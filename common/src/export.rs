@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+
+use serde::{Serialize, Deserialize};
+use strum::{Display, EnumIter, EnumString, IntoEnumIterator};
+
+use crate::{DBPart, DBPartProps, GetPartProps, PartsCategory, price::{Price, format_amount}, build::BuildSlots, traits::{Field, PartProperties}};
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default, Debug, EnumIter, Display, EnumString)]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+    Markdown,
+}
+
+impl ExportFormat {
+    pub fn all() -> Vec<ExportFormat> {
+        ExportFormat::iter().collect()
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "md",
+        }
+    }
+
+    pub fn mime(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv",
+            ExportFormat::Json => "application/json",
+            ExportFormat::Markdown => "text/markdown",
+        }
+    }
+}
+
+/// All parts matching the query, pagination of the query is ignored
+#[derive(Serialize, Deserialize)]
+pub struct ExportPartsProps {
+    pub query: GetPartProps,
+    /// Labels of properties in order of columns, e.g. `Max frequency`.
+    /// Name and price always come first.
+    pub columns: Vec<String>,
+    pub format: ExportFormat,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ExportBuildProps {
+    pub id: String,
+    pub format: ExportFormat,
+}
+
+/// Text cells under named columns, rendered into any [`ExportFormat`]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Default, Debug)]
+pub struct Table {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn render(&self, format: ExportFormat) -> String {
+        match format {
+            ExportFormat::Csv => self.csv(),
+            ExportFormat::Json => self.json(),
+            ExportFormat::Markdown => self.markdown(),
+        }
+    }
+
+    fn csv(&self) -> String {
+        let line = |cells: &[String]| cells.iter().map(|x| csv_cell(x)).collect::<Vec<String>>().join(",");
+
+        let mut lines = vec![line(&self.columns)];
+        lines.extend(self.rows.iter().map(|row| line(row)));
+
+        lines.join("\n") + "\n"
+    }
+
+    /// Array of objects keyed by columns. Keys are written by hand, so they keep order of the columns.
+    fn json(&self) -> String {
+        let string = |x: &String| serde_json::to_string(x).unwrap_or_default();
+        let objects: Vec<String> = self.rows.iter().map(|row| {
+            let fields: Vec<String> = self.columns.iter().zip(row)
+                .map(|(column, cell)| format!("{}: {}", string(column), string(cell)))
+                .collect();
+            format!("  {{ {} }}", fields.join(", "))
+        }).collect();
+
+        match objects.is_empty() {
+            true => "[]\n".to_string(),
+            false => format!("[\n{}\n]\n", objects.join(",\n")),
+        }
+    }
+
+    fn markdown(&self) -> String {
+        let line = |cells: &[String]| format!("| {} |", cells.iter().map(|x| markdown_cell(x)).collect::<Vec<String>>().join(" | "));
+
+        let mut lines = vec![
+            line(&self.columns),
+            line(&vec!["---".to_string(); self.columns.len()]),
+        ];
+        lines.extend(self.rows.iter().map(|row| line(row)));
+
+        lines.join("\n") + "\n"
+    }
+}
+
+fn csv_cell(cell: &str) -> String {
+    match cell.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", cell.replace('"', "\"\"")),
+        false => cell.to_string(),
+    }
+}
+
+fn markdown_cell(cell: &str) -> String {
+    cell.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+/// Booleans are shown as words
+pub fn format_property(property: String) -> String {
+    match property.as_str() {
+        "true" => "Yes".to_string(),
+        "false" => "No".to_string(),
+        _ => property,
+    }
+}
+
+pub fn format_price(price: Option<&Price>) -> String {
+    match price {
+        Some(price) => price.to_string(),
+        None => "-".to_string(),
+    }
+}
+
+/// Fields shown in the header of a part instead of its list of properties
+const HEADER_KEYS: [&str; 3] = ["name", "image_url", "rating"];
+
+/// Fields listed as properties of parts in the category, in order of their definition
+pub fn property_fields(category: PartsCategory) -> Vec<&'static Field> {
+    let template = DBPartProps {
+        category,
+        ..Default::default()
+    };
+
+    template.fields().into_iter()
+        .filter(|x| !HEADER_KEYS.contains(&x.key))
+        .collect()
+}
+
+/// Properties of a part keyed by their labels, the same way parts list them in the frontend
+pub fn labeled_properties(part: &DBPartProps) -> HashMap<String, String> {
+    property_fields(part.category.clone()).into_iter()
+        .filter_map(|field| Some((field.label.to_string(), part.display(field.key)?)))
+        .collect()
+}
+
+/// Part per row with its name, current lowest price and properties in given order
+pub fn parts_table(parts: &[DBPart], prices: &HashMap<String, Price>, columns: &[String]) -> Table {
+    let rows = parts.iter().map(|part| {
+        let properties = labeled_properties(&DBPartProps::from(part.clone()));
+        let mut row = vec![part.name.clone(), format_price(prices.get(&part.id))];
+        row.extend(columns.iter().map(|column| format_property(properties.get(column).cloned().unwrap_or_default())));
+        row
+    }).collect();
+
+    let mut header = vec!["Name".to_string(), "Price".to_string()];
+    header.extend(columns.iter().cloned());
+
+    Table {
+        columns: header,
+        rows,
+    }
+}
+
+/// Slot per row with its part and price, followed by total price of priced parts
+pub fn build_table(slots: &BuildSlots, parts: &[DBPart], prices: &HashMap<String, Price>) -> Table {
+    let mut rows: Vec<Vec<String>> = slots.slots().into_iter().map(|(slot, id)| {
+        let price = format_price(prices.get(&id));
+        match parts.iter().find(|x| x.id == id) {
+            Some(part) => vec![slot.to_string(), part.name.clone(), part.manufactuer.clone(), part.rating.to_string(), price],
+            None => vec![slot.to_string(), "Part not found".to_string(), String::new(), String::new(), price],
+        }
+    }).collect();
+
+    let priced: Vec<&Price> = slots.ids().iter().filter_map(|id| prices.get(id)).collect();
+    if let Some(first) = priced.first() {
+        let total = priced.iter().map(|x| x.amount).sum();
+        let count = match priced.len() {
+            1 => "1 part".to_string(),
+            count => format!("{} parts", count),
+        };
+        rows.push(vec!["Total".to_string(), count, String::new(), String::new(), format_amount(total, first.currency)]);
+    }
+
+    Table {
+        columns: ["Slot", "Part", "Manufacturer", "Rating", "Price"].map(String::from).to_vec(),
+        rows,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{DBPart, PartsCategory, CPUProperties, build::BuildSlots, price::{Currency, Price}};
+
+    use super::{ExportFormat, Table, build_table, parts_table};
+
+    fn price(part: &str, amount: u64) -> Price {
+        Price {
+            part: part.into(),
+            amount,
+            currency: Currency::EUR,
+            retailer: "Alza".into(),
+            url: String::new(),
+            observed_at: String::new(),
+        }
+    }
+
+    fn table() -> Table {
+        Table {
+            columns: vec!["Name".into(), "Socket".into()],
+            rows: vec![
+                vec!["Ryzen 5, 7600".into(), "AM5".into()],
+                vec!["Core \"i5\"".into(), "LGA|1700".into()],
+            ],
+        }
+    }
+
+    #[test]
+    fn render_formats() {
+        let table = table();
+
+        assert_eq!(table.render(ExportFormat::Csv), "Name,Socket\n\"Ryzen 5, 7600\",AM5\n\"Core \"\"i5\"\"\",LGA|1700\n");
+        assert_eq!(table.render(ExportFormat::Markdown), "| Name | Socket |\n| --- | --- |\n| Ryzen 5, 7600 | AM5 |\n| Core \"i5\" | LGA\\|1700 |\n");
+
+        let json: serde_json::Value = serde_json::from_str(&table.render(ExportFormat::Json)).unwrap();
+        assert_eq!(json, serde_json::json!([
+            { "Name": "Ryzen 5, 7600", "Socket": "AM5" },
+            { "Name": "Core \"i5\"", "Socket": "LGA|1700" },
+        ]));
+
+        // Keys follow the columns, not alphabetical order
+        let reversed = Table {
+            columns: vec!["Socket".into(), "Name".into()],
+            rows: vec![vec!["AM5".into(), "Ryzen".into()]],
+        };
+        assert_eq!(reversed.render(ExportFormat::Json), "[\n  { \"Socket\": \"AM5\", \"Name\": \"Ryzen\" }\n]\n");
+        assert_eq!(Table::default().render(ExportFormat::Json), "[]\n");
+    }
+
+    #[test]
+    fn parts_columns() {
+        let part = DBPart {
+            id: "ryzen".into(),
+            name: "Ryzen 5 7600".into(),
+            manufactuer: "AMD".into(),
+            category: PartsCategory::CPU(CPUProperties {
+                cores: 6,
+                ecc_memory_supported: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let prices: HashMap<String, Price> = [("ryzen".to_string(), price("ryzen", 22990))].into();
+
+        let columns = vec!["Ecc memory supported".to_string(), "Cores".to_string(), "Manufactuer".to_string(), "Unknown".to_string()];
+        let table = parts_table(&[part], &prices, &columns);
+
+        assert_eq!(table.columns, ["Name", "Price", "Ecc memory supported", "Cores", "Manufactuer", "Unknown"]);
+        assert_eq!(table.rows, vec![vec!["Ryzen 5 7600", "229.90 EUR", "Yes", "6", "AMD", ""]]);
+    }
+
+    #[test]
+    fn build_total() {
+        let slots = BuildSlots { cpu: Some("ryzen".into()), gpu: Some("rtx".into()), ..Default::default() };
+        let parts = [
+            DBPart { id: "ryzen".into(), name: "Ryzen 5 7600".into(), ..Default::default() },
+            DBPart { id: "rtx".into(), name: "RTX 4070".into(), ..Default::default() },
+        ];
+
+        // Total counts only parts with a price
+        let mut prices: HashMap<String, Price> = [("ryzen".to_string(), price("ryzen", 22990))].into();
+        let table = build_table(&slots, &parts, &prices);
+        assert_eq!(table.rows.last().unwrap(), &vec!["Total", "1 part", "", "", "229.90 EUR"]);
+
+        prices.insert("rtx".into(), price("rtx", 60000));
+        let table = build_table(&slots, &parts, &prices);
+        assert_eq!(table.rows.last().unwrap(), &vec!["Total", "2 parts", "", "", "829.90 EUR"]);
+    }
+}
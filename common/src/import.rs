@@ -0,0 +1,41 @@
+use serde::{Serialize, Deserialize};
+use strum::{Display, EnumIter, EnumString};
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default, Debug, EnumIter, Display, EnumString)]
+pub enum ImportFormat {
+    /// Array of `DBPartProps`
+    #[default]
+    Json,
+    /// Parts of a single category, header row names part fields and properties of the category
+    Csv,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct ImportPartsProps {
+    pub format: ImportFormat,
+    /// Name of the category variant, e.g. `CPU`. Required for CSV, ignored for JSON.
+    #[serde(default)]
+    pub category: Option<String>,
+    /// Content of the file
+    pub data: String,
+    /// Only validate, nothing is written
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Why a row of an import can't be imported
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct RowError {
+    /// 1-based position of the part in the file, CSV header is not counted
+    pub row: usize,
+    pub message: String,
+}
+
+/// Outcome of an import. Parts are imported all at once, so any error means none were imported.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Default, Debug)]
+pub struct ImportReport {
+    pub rows: usize,
+    pub imported: usize,
+    pub dry_run: bool,
+    pub errors: Vec<RowError>,
+}
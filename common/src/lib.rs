@@ -1,14 +1,28 @@
-use std::{collections::HashMap, str::FromStr};
+use std::str::FromStr;
 
 use serde::{Serialize, Deserialize};
 use strum::{EnumIter, IntoEnumIterator, Display, EnumString};
 #[cfg(feature = "surreal")]
 use surrealdb::sql::{Value, json};
-use traits::PartProperties;
-use types::StringenFloat;
+use traits::{PartProperties, Field};
+use error::FieldError;
+use query::{PropertyFilter, SortBy};
+use types::{StringenFloat, Frequency, Power, DataSize, Temperature, PartId, ReleaseDate};
 
 pub mod types;
 pub mod traits;
+pub mod compatibility;
+pub mod build;
+pub mod query;
+pub mod audit;
+pub mod user;
+pub mod price;
+pub mod optimizer;
+pub mod import;
+pub mod export;
+pub mod error;
+pub mod validation;
+pub mod review;
 
 #[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
 pub struct DBPart {
@@ -17,8 +31,12 @@ pub struct DBPart {
     pub image_url: String,
     pub model: String,
     pub manufactuer: String,
-    pub release_date: String,
+    #[serde(default, deserialize_with = "ReleaseDate::deserialize_optional")]
+    pub release_date: Option<ReleaseDate>,
+    /// Average stars of reviews, see [`review::average_rating`]
     pub rating: StringenFloat,
+    #[serde(default)]
+    pub review_count: u32,
     pub category: PartsCategory,
 }
 
@@ -28,12 +46,39 @@ pub struct DBPartProps {
     pub image_url: String,
     pub model: String,
     pub manufactuer: String,
-    pub release_date: String,
+    #[serde(default, deserialize_with = "ReleaseDate::deserialize_optional")]
+    pub release_date: Option<ReleaseDate>,
     pub rating: StringenFloat,
+    /// Kept with rating, which is computed from reviews
+    #[serde(default)]
+    pub review_count: u32,
     pub category: PartsCategory,
 }
 
-impl PartProperties for DBPartProps {}
+part_properties!(DBPartProps {
+    name: "Name" { required: true },
+    image_url: "Image url",
+    model: "Model",
+    manufactuer: "Manufactuer",
+    release_date: "Release date",
+    rating: "Rating" { computed: true },
+    ..category
+});
+
+impl From<DBPart> for DBPartProps {
+    fn from(value: DBPart) -> Self {
+        Self {
+            name: value.name,
+            image_url: value.image_url,
+            model: value.model,
+            manufactuer: value.manufactuer,
+            release_date: value.release_date,
+            rating: value.rating,
+            review_count: value.review_count,
+            category: value.category,
+        }
+    }
+}
 
 #[cfg(feature = "surreal")]
 impl Into<Value> for DBPartProps {
@@ -48,12 +93,30 @@ pub struct StatusResponse {
     pub functional: bool
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Default)]
 pub struct GetPartProps {
-    /// None: Get all parts
-    /// Some: Get part with specified id
-    pub id: Option<String>,
+    /// None: Get page of parts matching the query
+    /// Some: Get part with specified id, rest of the query is ignored
+    pub id: Option<PartId>,
     pub limit: u32,
+    #[serde(default)]
+    pub offset: u32,
+    /// Name of the category variant, e.g. `CPU`
+    #[serde(default)]
+    pub category: Option<String>,
+    /// Case insensitive text searched for in name, model and manufacturer
+    #[serde(default)]
+    pub query: Option<String>,
+    #[serde(default)]
+    pub filters: Vec<PropertyFilter>,
+    #[serde(default)]
+    pub sort: Option<SortBy>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetCompatibilityProps {
+    /// Ids of parts that should be checked against each other
+    pub ids: Vec<String>,
 }
 
 #[derive(Clone, Serialize, Deserialize, PartialEq, Default, Debug, EnumIter, Display, EnumString)]
@@ -61,13 +124,59 @@ pub enum PartsCategory {
     #[default]
     Basic,
     CPU(CPUProperties),
+    GPU(GPUProperties),
+    RAM(RAMProperties),
+    Motherboard(MotherboardProperties),
+    PSU(PSUProperties),
+    Storage(StorageProperties),
+    Case(CaseProperties),
+    Cooler(CoolerProperties),
 }
 
-impl PartProperties for PartsCategory {
-    fn to_string_vec(&self) -> anyhow::Result<std::collections::HashMap<String, String>> {
+impl PartsCategory {
+    /// Properties of the category, None for basic parts
+    fn properties(&self) -> Option<&dyn PartProperties> {
         match self {
-            PartsCategory::Basic => Ok(HashMap::new()),
-            PartsCategory::CPU(props) => props.to_string_vec(),
+            PartsCategory::Basic => None,
+            PartsCategory::CPU(props) => Some(props),
+            PartsCategory::GPU(props) => Some(props),
+            PartsCategory::RAM(props) => Some(props),
+            PartsCategory::Motherboard(props) => Some(props),
+            PartsCategory::PSU(props) => Some(props),
+            PartsCategory::Storage(props) => Some(props),
+            PartsCategory::Case(props) => Some(props),
+            PartsCategory::Cooler(props) => Some(props),
+        }
+    }
+
+    fn properties_mut(&mut self) -> Option<&mut dyn PartProperties> {
+        match self {
+            PartsCategory::Basic => None,
+            PartsCategory::CPU(props) => Some(props),
+            PartsCategory::GPU(props) => Some(props),
+            PartsCategory::RAM(props) => Some(props),
+            PartsCategory::Motherboard(props) => Some(props),
+            PartsCategory::PSU(props) => Some(props),
+            PartsCategory::Storage(props) => Some(props),
+            PartsCategory::Case(props) => Some(props),
+            PartsCategory::Cooler(props) => Some(props),
+        }
+    }
+}
+
+impl PartProperties for PartsCategory {
+    fn fields(&self) -> Vec<&'static Field> {
+        self.properties().map(|x| x.fields()).unwrap_or_default()
+    }
+
+    fn get(&self, key: &str) -> Option<serde_json::Value> {
+        self.properties()?.get(key)
+    }
+
+    fn set(&mut self, key: &str, value: serde_json::Value) -> Result<(), FieldError> {
+        match self.properties_mut() {
+            Some(props) => props.set(key, value),
+            None => Err(FieldError::new(key, "unknown property")),
         }
     }
 }
@@ -91,21 +200,185 @@ impl PartsCategory {
 pub struct CPUProperties {
     pub cores: u32,
     pub threads: u32,
-    pub max_frequency: String,
-    pub base_frequency: String,
-    pub max_tdp: String,
-    pub base_tdp: String,
-    pub cache: String,
-    pub max_ram_size: String,
+    pub max_frequency: Frequency,
+    pub base_frequency: Frequency,
+    pub max_tdp: Power,
+    pub base_tdp: Power,
+    pub cache: DataSize,
+    pub max_ram_size: DataSize,
     pub max_memory_channels: u32,
     pub ecc_memory_supported: bool,
     pub max_pcie_lanes: u32,
     pub max_supported_pcie_version: String,
     pub socket: String,
-    pub max_temperature: String,
+    pub max_temperature: Temperature,
+}
+
+part_properties!(CPUProperties {
+    cores: "Cores",
+    threads: "Threads",
+    max_frequency: "Max frequency",
+    base_frequency: "Base frequency",
+    max_tdp: "Max tdp",
+    base_tdp: "Base tdp",
+    cache: "Cache",
+    max_ram_size: "Max ram size",
+    max_memory_channels: "Max memory channels",
+    ecc_memory_supported: "Ecc memory supported",
+    max_pcie_lanes: "Max pcie lanes",
+    max_supported_pcie_version: "Max supported pcie version",
+    socket: "Socket",
+    max_temperature: "Max temperature",
+});
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Default, Debug)]
+pub struct GPUProperties {
+    pub chipset: String,
+    pub memory_size: DataSize,
+    pub memory_type: String,
+    pub base_clock: Frequency,
+    pub boost_clock: Frequency,
+    pub tdp: Power,
+    pub recommended_psu: Power,
+    pub pcie_version: String,
+    pub length: String,
+    pub slot_width: u32,
+    pub hdmi_ports: u32,
+    pub display_ports: u32,
 }
 
-impl PartProperties for CPUProperties {}
+part_properties!(GPUProperties {
+    chipset: "Chipset",
+    memory_size: "Memory size",
+    memory_type: "Memory type",
+    base_clock: "Base clock",
+    boost_clock: "Boost clock",
+    tdp: "Tdp",
+    recommended_psu: "Recommended psu",
+    pcie_version: "Pcie version",
+    length: "Length",
+    slot_width: "Slot width",
+    hdmi_ports: "Hdmi ports",
+    display_ports: "Display ports",
+});
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Default, Debug)]
+pub struct RAMProperties {
+    pub memory_type: String,
+    pub capacity: DataSize,
+    pub modules: u32,
+    pub speed: String,
+    pub cas_latency: u32,
+    pub voltage: String,
+    pub ecc: bool,
+}
+
+part_properties!(RAMProperties {
+    memory_type: "Memory type",
+    capacity: "Capacity",
+    modules: "Modules",
+    speed: "Speed",
+    cas_latency: "Cas latency",
+    voltage: "Voltage",
+    ecc: "Ecc",
+});
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Default, Debug)]
+pub struct MotherboardProperties {
+    pub socket: String,
+    pub chipset: String,
+    pub form_factor: String,
+    pub memory_type: String,
+    pub memory_slots: u32,
+    pub max_memory: DataSize,
+    pub pcie_version: String,
+    pub m2_slots: u32,
+    pub sata_ports: u32,
+}
+
+part_properties!(MotherboardProperties {
+    socket: "Socket",
+    chipset: "Chipset",
+    form_factor: "Form factor",
+    memory_type: "Memory type",
+    memory_slots: "Memory slots",
+    max_memory: "Max memory",
+    pcie_version: "Pcie version",
+    m2_slots: "M2 slots",
+    sata_ports: "Sata ports",
+});
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Default, Debug)]
+pub struct PSUProperties {
+    pub wattage: Power,
+    pub efficiency_rating: String,
+    pub modular: String,
+    pub form_factor: String,
+}
+
+part_properties!(PSUProperties {
+    wattage: "Wattage",
+    efficiency_rating: "Efficiency rating",
+    modular: "Modular" { options: &["Full", "Semi", "No"] },
+    form_factor: "Form factor",
+});
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Default, Debug)]
+pub struct StorageProperties {
+    pub storage_type: String,
+    pub capacity: DataSize,
+    pub interface: String,
+    pub form_factor: String,
+    pub read_speed: String,
+    pub write_speed: String,
+}
+
+part_properties!(StorageProperties {
+    storage_type: "Storage type" { options: &["SSD", "HDD"] },
+    capacity: "Capacity",
+    interface: "Interface",
+    form_factor: "Form factor",
+    read_speed: "Read speed",
+    write_speed: "Write speed",
+});
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Default, Debug)]
+pub struct CaseProperties {
+    pub form_factor: String,
+    pub supported_motherboards: String,
+    pub max_gpu_length: String,
+    pub max_cooler_height: String,
+    pub drive_bays: u32,
+    pub included_fans: u32,
+}
+
+part_properties!(CaseProperties {
+    form_factor: "Form factor",
+    supported_motherboards: "Supported motherboards",
+    max_gpu_length: "Max gpu length",
+    max_cooler_height: "Max cooler height",
+    drive_bays: "Drive bays",
+    included_fans: "Included fans",
+});
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Default, Debug)]
+pub struct CoolerProperties {
+    pub cooler_type: String,
+    pub supported_sockets: String,
+    pub max_tdp: Power,
+    pub height: String,
+    pub fan_size: String,
+    pub noise_level: String,
+}
+
+part_properties!(CoolerProperties {
+    cooler_type: "Cooler type" { options: &["Air", "Liquid"] },
+    supported_sockets: "Supported sockets",
+    max_tdp: "Max tdp",
+    height: "Height",
+    fan_size: "Fan size",
+    noise_level: "Noise level",
+});
 
 #[cfg(feature = "surreal")]
 fn convert_to_value<T>(value: &T) -> anyhow::Result<Value>
@@ -120,7 +393,9 @@ where T: Serialize
 mod tests {
     use serde_json::json;
 
-    use crate::{StringenFloat, DBPartProps};
+    use strum::IntoEnumIterator;
+
+    use crate::{StringenFloat, DBPartProps, PartsCategory, CPUProperties, GPUProperties, traits::{FieldType, PartProperties}, types::{Frequency, DataSize, Power, Temperature, ReleaseDate, DatePrecision}};
 
     #[test]
     fn stringen_float() -> anyhow::Result<()> {
@@ -175,8 +450,9 @@ mod tests {
             image_url: "".into(),
             model: "LKFHDS".into(),
             manufactuer: "Chinese".into(),
-            release_date: "22Q2".to_string(),
+            release_date: "2022Q2".parse().ok(),
             rating: 4.5.into(),
+            review_count: 2,
             category: crate::PartsCategory::Basic,
         };
         
@@ -190,4 +466,226 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn category_properties_from_json() -> anyhow::Result<()> {
+        let db_part = DBPartProps {
+            name: "RTX 3060 Ti".into(),
+            category: PartsCategory::GPU(GPUProperties {
+                memory_size: "8 GB".parse()?,
+                slot_width: 2,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let json = serde_json::to_value(db_part.clone())?;
+        let db_parts_from_json: DBPartProps = serde_json::from_value(json)?;
+
+        assert!(db_part == db_parts_from_json);
+
+        let properties = db_part.category.labeled_values();
+        assert_eq!(properties.get("Memory size"), Some(&"8 GB".to_string()));
+        assert_eq!(properties.get("Slot width"), Some(&"2".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn properties_round_trip() -> anyhow::Result<()> {
+        let part = DBPartProps {
+            name: "Ryzen 5 7600".into(),
+            rating: 4.5.into(),
+            category: PartsCategory::CPU(CPUProperties {
+                cores: 6,
+                max_tdp: "88 W".parse()?,
+                ecc_memory_supported: true,
+                socket: "AM5".into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let mut parts: Vec<DBPartProps> = PartsCategory::iter()
+            .map(|category| DBPartProps { category, ..Default::default() })
+            .collect();
+        parts.push(part);
+
+        for part in parts {
+            // Category is chosen separately, the rest is set field by field
+            let empty = DBPartProps {
+                category: PartsCategory::from_string(&part.category.to_string()),
+                ..Default::default()
+            };
+
+            let mut copy = empty.clone();
+            for field in part.fields() {
+                copy.set(field.key, part.get(field.key).unwrap()).map_err(|x| anyhow::anyhow!("{}: {}", x.field, x.message))?;
+            }
+            assert_eq!(copy, part);
+
+            let mut from_input = empty;
+            for field in part.fields() {
+                from_input.set_input(field.key, &part.display(field.key).unwrap()).map_err(|x| anyhow::anyhow!("{}: {}", x.field, x.message))?;
+            }
+            assert_eq!(from_input, part);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn nested_properties() {
+        let mut part = DBPartProps {
+            category: PartsCategory::Storage(Default::default()),
+            ..Default::default()
+        };
+
+        let keys: Vec<&str> = part.fields().iter().map(|x| x.key).collect();
+        assert_eq!(&keys[..7], ["name", "image_url", "model", "manufactuer", "release_date", "rating", "storage_type"]);
+        assert!(!keys.contains(&"category"));
+
+        assert!(part.set_input("capacity", "2 TB").is_ok());
+        assert!(part.set_input("storage_type", "SSD").is_ok());
+        assert_eq!(part.get("capacity"), Some(json!("2 TB")));
+        assert_eq!(part.display("storage_type"), Some("SSD".to_string()));
+
+        assert_eq!(part.set_input("storage_type", "Tape").unwrap_err().field, "storage_type");
+        assert_eq!(part.set_input("cores", "6").unwrap_err().message, "unknown property");
+        assert!(part.set_input("capacity", "a lot").is_err());
+        assert_eq!(part.display("storage_type"), Some("SSD".to_string()));
+
+        let field = part.field("capacity").unwrap();
+        assert_eq!((field.label, field.field_type, field.unit), ("Capacity", FieldType::Quantity, Some("GB")));
+        assert!(part.field("name").unwrap().required);
+
+        let mut basic = DBPartProps::default();
+        assert!(basic.set_input("capacity", "2 TB").is_err());
+        assert_eq!(basic.fields().len(), 6);
+    }
+
+    #[test]
+    fn typed_input() {
+        let mut cpu = CPUProperties::default();
+        assert!(cpu.set_input("max_tdp", "65").is_ok());
+        assert_eq!(cpu.max_tdp, "65 W".parse().unwrap());
+        assert!(cpu.set_input("cores", " 8 ").is_ok());
+        assert_eq!(cpu.cores, 8);
+        assert!(cpu.set_input("cores", "8.5").is_err());
+        assert!(cpu.set_input("ecc_memory_supported", "Yes").is_ok());
+        assert!(cpu.ecc_memory_supported);
+        assert!(cpu.set_input("ecc_memory_supported", "maybe").is_err());
+    }
+
+    #[test]
+    fn all_categories_from_string() {
+        for variant in PartsCategory::get_all_variats() {
+            assert_eq!(PartsCategory::from_string(&variant).to_string(), variant);
+        }
+    }
+
+    #[test]
+    fn quantity_from_str() -> anyhow::Result<()> {
+        let frequency: Frequency = "4.8 GHz".parse()?;
+
+        assert_eq!(frequency, "4800 MHz".parse()?);
+        assert_eq!(frequency, "4.80 GHz".parse()?);
+        assert_eq!(frequency.get(), 4_800_000_000);
+        assert_eq!(frequency.to_string(), "4.8 GHz");
+        assert_eq!("24 MB".parse::<DataSize>()?.as_unit("KB"), Some(24576.0));
+        assert_eq!("100 C".parse::<Temperature>()?.to_string(), "100 °C");
+        assert_eq!("-5.5 °C".parse::<Temperature>()?.get(), -5500);
+        assert!("65".parse::<Power>().is_err());
+        assert!("65 GHz".parse::<Power>().is_err());
+        assert!("W".parse::<Power>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn quantity_order() -> anyhow::Result<()> {
+        let mut sizes: Vec<DataSize> = vec!["1 TB".parse()?, "512 MB".parse()?, "16 GB".parse()?];
+        sizes.sort();
+
+        assert_eq!(sizes, vec!["512 MB".parse()?, "16 GB".parse()?, "1 TB".parse()?]);
+        assert_eq!(sizes.into_iter().sum::<DataSize>().to_string(), "1040.5 GB");
+
+        Ok(())
+    }
+
+    #[test]
+    fn quantity_sum_saturates() -> anyhow::Result<()> {
+        let max = Power::new(i64::MAX);
+        let min = Power::new(i64::MIN);
+
+        assert_eq!(max + "1 W".parse()?, max);
+        assert_eq!(min + "-1 W".parse()?, min);
+        assert_eq!(vec![max, max, "-5 W".parse()?].into_iter().sum::<Power>(), Power::new(i64::MAX - 5000));
+        assert_eq!(Power::new(i64::MAX - 1) + Power::new(1), max);
+
+        Ok(())
+    }
+
+    #[test]
+    fn quantity_json() -> anyhow::Result<()> {
+        let values = ["0 W", "154 W", "1.5 W", "2.345 kW", "7 mW"];
+        for value in values {
+            let power: Power = value.parse()?;
+            let json = serde_json::to_value(power)?;
+            let power_from_json: Power = serde_json::from_value(json.clone())?;
+
+            assert_eq!(json, serde_json::json!(value));
+            assert_eq!(power, power_from_json);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn release_date_from_str() -> anyhow::Result<()> {
+        let quarter: ReleaseDate = "2022Q4".parse()?;
+        assert_eq!(quarter, ReleaseDate::from_quarter(2022, 4).unwrap());
+        assert_eq!(quarter, "22Q4".parse()?);
+        assert_eq!((quarter.year(), quarter.quarter(), quarter.month(), quarter.day()), (2022, 4, None, None));
+        assert_eq!("2022-11".parse::<ReleaseDate>()?.month(), Some(11));
+        assert_eq!("2024-02-29".parse::<ReleaseDate>()?.precision(), DatePrecision::Day);
+        for invalid in ["", "2022", "22Q5", "2022Q0", "2022-13", "2022-1", "2023-02-29", "2022-10-13T12:00", "banana"] {
+            assert!(invalid.parse::<ReleaseDate>().is_err(), "{}", invalid);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn release_date_order() -> anyhow::Result<()> {
+        let mut dates: Vec<ReleaseDate> = vec!["2022-10-13".parse()?, "2023Q1".parse()?, "2022-10".parse()?, "2022Q4".parse()?, "2021-12-31".parse()?];
+        dates.sort();
+
+        let dates: Vec<String> = dates.iter().map(|x| x.to_string()).collect();
+        assert_eq!(dates, ["2021-12-31", "2022Q4", "2022-10", "2022-10-13", "2023Q1"]);
+        assert_eq!("2022Q4".parse::<ReleaseDate>()?.as_number(), 20221001);
+
+        Ok(())
+    }
+
+    #[test]
+    fn release_date_json() -> anyhow::Result<()> {
+        for value in ["2022Q4", "2022-10", "2022-10-13"] {
+            let date: ReleaseDate = value.parse()?;
+            let json = serde_json::to_value(date)?;
+
+            assert_eq!(json, serde_json::json!(value));
+            assert_eq!(date, serde_json::from_value(json)?);
+        }
+
+        let mut part = DBPartProps::default();
+        assert_eq!(part.display("release_date").as_deref(), Some(""));
+        assert!(part.set_input("release_date", "22Q4").is_ok());
+        assert_eq!(part.display("release_date").as_deref(), Some("2022Q4"));
+        assert!(part.set_input("release_date", "soon").is_err());
+        assert!(part.set_input("release_date", "").is_ok());
+        assert_eq!(part.release_date, None);
+
+        Ok(())
+    }
 }
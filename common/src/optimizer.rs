@@ -0,0 +1,445 @@
+use std::collections::BTreeMap;
+
+use serde::{Serialize, Deserialize};
+use strum::{Display, EnumIter, EnumString, IntoEnumIterator};
+
+use crate::{DBPart, DBPartProps, PartsCategory, build::BuildSlots, compatibility::{check_compatibility, CompatibilityIssue, Severity}, price::Currency, query::{PropertyFilter, PropertyKind, property_keys}};
+
+/// Slots in the order parts are picked. Parts that constrain others come first,
+/// so incompatible combinations are cut early. PSU comes after everything that draws power.
+const SLOTS: [&str; 8] = ["CPU", "Motherboard", "RAM", "Cooler", "GPU", "PSU", "Storage", "Case"];
+/// Compatibility checks done before the search gives up and returns what it found,
+/// keeps response time bounded for big catalogs
+const MAX_CHECKS: usize = 200_000;
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default, Debug, EnumIter, Display, EnumString)]
+pub enum UseCase {
+    #[default]
+    Gaming,
+    /// No dedicated GPU or cooler
+    Office,
+    Workstation,
+}
+
+impl UseCase {
+    pub fn all() -> Vec<UseCase> {
+        UseCase::iter().collect()
+    }
+
+    fn slots(&self) -> Vec<&'static str> {
+        SLOTS.into_iter()
+            .filter(|slot| *self != UseCase::Office || !matches!(*slot, "GPU" | "Cooler"))
+            .collect()
+    }
+
+    /// How much performance of parts in the category matters, compared to rating of any part
+    fn performance_weight(&self, category: &str) -> f64 {
+        match (self, category) {
+            (UseCase::Gaming, "GPU") => 3.0,
+            (UseCase::Gaming, "CPU") => 1.5,
+            (UseCase::Gaming, "RAM") => 1.0,
+            (UseCase::Gaming, "Storage") => 0.5,
+            (UseCase::Office, "CPU" | "RAM" | "Storage") => 1.0,
+            (UseCase::Workstation, "CPU") => 3.0,
+            (UseCase::Workstation, "RAM") => 2.0,
+            (UseCase::Workstation, "GPU" | "Storage") => 1.0,
+            _ => 0.0,
+        }
+    }
+}
+
+/// Raw performance of a part, only comparable within its category
+fn performance(category: &PartsCategory) -> f64 {
+    match category {
+        PartsCategory::CPU(props) => props.cores as f64 * props.max_frequency.as_unit("GHz").unwrap_or(0.0),
+        PartsCategory::GPU(props) => props.boost_clock.as_unit("GHz").unwrap_or(0.0) * props.memory_size.as_unit("GB").unwrap_or(0.0),
+        PartsCategory::RAM(props) => props.capacity.as_unit("GB").unwrap_or(0.0),
+        PartsCategory::Storage(props) => props.capacity.as_unit("GB").unwrap_or(0.0),
+        _ => 0.0,
+    }
+}
+
+/// Part with its current lowest price
+#[derive(Clone, PartialEq, Debug)]
+pub struct Candidate {
+    pub part: DBPart,
+    /// In hundredths of the currency
+    pub price: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct SuggestBuildProps {
+    /// Highest total price, in hundredths of the currency
+    pub budget: u64,
+    #[serde(default)]
+    pub currency: Currency,
+    #[serde(default)]
+    pub use_case: UseCase,
+    /// Only parts that have the property are constrained,
+    /// e.g. `socket` applies to CPUs and motherboards, `cores` only to CPUs
+    #[serde(default)]
+    pub constraints: Vec<PropertyFilter>,
+    /// Max number of suggestions
+    #[serde(default = "default_limit")]
+    pub limit: u32,
+}
+
+fn default_limit() -> u32 {
+    3
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct BuildSuggestion {
+    pub slots: BuildSlots,
+    pub parts: Vec<DBPart>,
+    /// In hundredths of the currency
+    pub total: u64,
+    pub currency: Currency,
+    /// Higher is better, only comparable between suggestions of the same request
+    pub score: f64,
+    /// Only warnings, builds with errors are never suggested
+    pub issues: Vec<CompatibilityIssue>,
+    /// Slots left empty as there is no priced part for them
+    pub missing: Vec<String>,
+}
+
+struct Scored {
+    candidate: Candidate,
+    score: f64,
+}
+
+struct Slot {
+    name: &'static str,
+    /// Best first
+    options: Vec<Scored>,
+}
+
+/// Suggested build before it is checked and returned
+struct Found {
+    score: f64,
+    total: u64,
+    parts: Vec<DBPart>,
+}
+
+/// Depth first search over slots with branch and bound
+struct Search<'a> {
+    slots: &'a [Slot],
+    /// Cheapest price of all slots from index on
+    cheapest_rest: Vec<u64>,
+    /// Best score of all slots from index on
+    best_rest: Vec<f64>,
+    budget: u64,
+    limit: usize,
+    checks: usize,
+    chosen: Vec<&'a Scored>,
+    /// Best first
+    found: Vec<Found>,
+}
+
+impl<'a> Search<'a> {
+    /// Score a build has to beat to be among found builds
+    fn worst(&self) -> Option<f64> {
+        match self.found.len() >= self.limit {
+            true => self.found.last().map(|x| x.score),
+            false => None,
+        }
+    }
+
+    fn run(&mut self, index: usize, total: u64, score: f64) {
+        let slots = self.slots;
+        let slot = match slots.get(index) {
+            Some(slot) => slot,
+            None => return self.record(total, score),
+        };
+
+        if slot.options.is_empty() {
+            return self.run(index + 1, total, score);
+        }
+
+        for option in &slot.options {
+            if self.checks >= MAX_CHECKS {
+                return;
+            }
+
+            // Options are sorted by score, so no later one can do better
+            if self.worst().is_some_and(|worst| score + option.score + self.best_rest[index + 1] <= worst) {
+                return;
+            }
+
+            let total = total + option.candidate.price;
+            if total + self.cheapest_rest[index + 1] > self.budget {
+                continue;
+            }
+
+            self.chosen.push(option);
+            self.checks += 1;
+            let parts: Vec<DBPart> = self.chosen.iter().map(|x| x.candidate.part.clone()).collect();
+            // Issues only pile up as parts are added, so an error can't go away later
+            if !check_compatibility(&parts).iter().any(|x| x.severity() == Severity::Error) {
+                self.run(index + 1, total, score + option.score);
+            }
+            self.chosen.pop();
+        }
+    }
+
+    fn record(&mut self, total: u64, score: f64) {
+        let parts = self.chosen.iter().map(|x| x.candidate.part.clone()).collect();
+        self.found.push(Found { score, total, parts });
+        self.found.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.total.cmp(&b.total)));
+        self.found.truncate(self.limit);
+    }
+}
+
+/// Best compatible builds under the budget, best first.
+/// Parts are scored by rating and by performance that matters for the use case.
+pub fn suggest_builds(candidates: &[Candidate], props: &SuggestBuildProps) -> Vec<BuildSuggestion> {
+    let keys = property_keys();
+    let slots: Vec<Slot> = props.use_case.slots().into_iter().map(|name| {
+        let options: Vec<&Candidate> = candidates.iter()
+            .filter(|x| x.part.category.to_string() == name && x.price <= props.budget)
+            .filter(|x| fulfills(&x.part, &props.constraints, &keys))
+            .collect();
+
+        let max_performance = options.iter().map(|x| performance(&x.part.category)).fold(0.0, f64::max);
+        let weight = props.use_case.performance_weight(name);
+        let mut options: Vec<Scored> = options.into_iter().map(|candidate| {
+            let relative = match max_performance > 0.0 {
+                true => performance(&candidate.part.category) / max_performance,
+                false => 0.0,
+            };
+            let score = candidate.part.rating.get() / 5.0 + weight * relative;
+
+            Scored { candidate: candidate.clone(), score }
+        }).collect();
+        options.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.candidate.price.cmp(&b.candidate.price)));
+
+        Slot { name, options }
+    }).collect();
+
+    let missing: Vec<String> = slots.iter()
+        .filter(|x| x.options.is_empty())
+        .map(|x| x.name.to_string())
+        .collect();
+    if missing.len() == slots.len() {
+        return Vec::new();
+    }
+
+    let mut cheapest_rest = vec![0; slots.len() + 1];
+    let mut best_rest = vec![0.0; slots.len() + 1];
+    for (index, slot) in slots.iter().enumerate().rev() {
+        cheapest_rest[index] = cheapest_rest[index + 1] + slot.options.iter().map(|x| x.candidate.price).min().unwrap_or(0);
+        best_rest[index] = best_rest[index + 1] + slot.options.iter().map(|x| x.score).fold(0.0, f64::max);
+    }
+
+    let mut search = Search {
+        slots: &slots,
+        cheapest_rest,
+        best_rest,
+        budget: props.budget,
+        limit: props.limit.max(1) as usize,
+        checks: 0,
+        chosen: Vec::new(),
+        found: Vec::new(),
+    };
+    search.run(0, 0, 0.0);
+
+    search.found.into_iter().map(|found| {
+        let mut slots = BuildSlots::default();
+        for part in &found.parts {
+            slots.insert(part.id.clone(), &part.category);
+        }
+
+        BuildSuggestion {
+            slots,
+            issues: check_compatibility(&found.parts),
+            parts: found.parts,
+            total: found.total,
+            currency: props.currency,
+            score: found.score,
+            missing: missing.clone(),
+        }
+    }).collect()
+}
+
+/// Whether part fulfills all constraints on properties it has
+fn fulfills(part: &DBPart, constraints: &[PropertyFilter], keys: &BTreeMap<String, PropertyKind>) -> bool {
+    let properties = DBPartProps::from(part.clone()).indexed_properties();
+
+    constraints.iter().all(|constraint| match properties.get(&constraint.key) {
+        Some(value) => constraint.condition.matches(value, keys.get(&constraint.key).is_some_and(PropertyKind::is_numeric)),
+        None => true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{DBPart, PartsCategory, CPUProperties, MotherboardProperties, RAMProperties, PSUProperties, GPUProperties, StorageProperties, CaseProperties, compatibility::Severity, query::{PropertyFilter, FilterCondition}};
+
+    use super::{suggest_builds, Candidate, SuggestBuildProps, UseCase};
+
+    fn candidate(id: &str, price: u64, rating: f64, category: PartsCategory) -> Candidate {
+        Candidate {
+            part: DBPart {
+                id: id.into(),
+                name: id.into(),
+                rating: rating.into(),
+                category,
+                ..Default::default()
+            },
+            price,
+        }
+    }
+
+    fn cpu(id: &str, price: u64, cores: u32, socket: &str) -> Candidate {
+        candidate(id, price, 4.0, PartsCategory::CPU(CPUProperties {
+            cores,
+            max_frequency: "5 GHz".parse().unwrap(),
+            max_tdp: "125 W".parse().unwrap(),
+            socket: socket.into(),
+            ..Default::default()
+        }))
+    }
+
+    fn motherboard(id: &str, price: u64, socket: &str, memory_type: &str) -> Candidate {
+        candidate(id, price, 4.0, PartsCategory::Motherboard(MotherboardProperties {
+            socket: socket.into(),
+            memory_type: memory_type.into(),
+            ..Default::default()
+        }))
+    }
+
+    fn catalog() -> Vec<Candidate> {
+        vec![
+            cpu("i5", 25000, 14, "FCLGA1700"),
+            cpu("i9", 60000, 24, "FCLGA1700"),
+            cpu("r5", 22000, 6, "AM5"),
+            motherboard("z790", 20000, "FCLGA1700", "DDR5"),
+            motherboard("b650", 15000, "AM5", "DDR5"),
+            candidate("ddr5", 10000, 4.5, PartsCategory::RAM(RAMProperties {
+                memory_type: "DDR5".into(),
+                capacity: "32 GB".parse().unwrap(),
+                modules: 2,
+                ..Default::default()
+            })),
+            candidate("ddr4", 5000, 5.0, PartsCategory::RAM(RAMProperties {
+                memory_type: "DDR4".into(),
+                capacity: "64 GB".parse().unwrap(),
+                modules: 2,
+                ..Default::default()
+            })),
+            candidate("rtx", 60000, 4.5, PartsCategory::GPU(GPUProperties {
+                boost_clock: "2.5 GHz".parse().unwrap(),
+                memory_size: "12 GB".parse().unwrap(),
+                tdp: "285 W".parse().unwrap(),
+                recommended_psu: "700 W".parse().unwrap(),
+                ..Default::default()
+            })),
+            candidate("psu550", 6000, 4.0, PartsCategory::PSU(PSUProperties {
+                wattage: "550 W".parse().unwrap(),
+                ..Default::default()
+            })),
+            candidate("psu850", 12000, 4.0, PartsCategory::PSU(PSUProperties {
+                wattage: "850 W".parse().unwrap(),
+                ..Default::default()
+            })),
+            candidate("ssd", 8000, 4.0, PartsCategory::Storage(StorageProperties {
+                capacity: "1 TB".parse().unwrap(),
+                ..Default::default()
+            })),
+            candidate("case", 7000, 4.0, PartsCategory::Case(CaseProperties::default())),
+        ]
+    }
+
+    fn props(budget: u64, use_case: UseCase) -> SuggestBuildProps {
+        SuggestBuildProps {
+            budget,
+            currency: Default::default(),
+            use_case,
+            constraints: Vec::new(),
+            limit: 3,
+        }
+    }
+
+    #[test]
+    fn suggested_builds_are_compatible_and_affordable() {
+        let suggestions = suggest_builds(&catalog(), &props(200000, UseCase::Gaming));
+
+        assert!(!suggestions.is_empty());
+        assert!(suggestions.windows(2).all(|x| x[0].score >= x[1].score));
+        for suggestion in &suggestions {
+            assert!(suggestion.total <= 200000);
+            assert!(suggestion.issues.iter().all(|x| x.severity() != Severity::Error));
+            assert_eq!(suggestion.missing, vec!["Cooler"]);
+            // DDR4 is rated better but doesn't fit any board, GPU needs the bigger PSU
+            assert_eq!(suggestion.slots.ram, vec!["ddr5"]);
+            assert_eq!(suggestion.slots.psu.as_deref(), Some("psu850"));
+        }
+        assert_eq!(suggestions[0].slots.cpu.as_deref(), Some("i9"));
+    }
+
+    #[test]
+    fn budget_and_use_case() {
+        let suggestions = suggest_builds(&catalog(), &props(150000, UseCase::Gaming));
+        assert_eq!(suggestions[0].slots.cpu.as_deref(), Some("i5"));
+
+        let suggestions = suggest_builds(&catalog(), &props(70000, UseCase::Office));
+        assert!(suggestions[0].slots.gpu.is_none());
+        assert!(suggestions[0].total <= 70000);
+
+        assert!(suggest_builds(&catalog(), &props(50000, UseCase::Office)).is_empty());
+    }
+
+    #[test]
+    fn constraints() {
+        let mut props = props(200000, UseCase::Gaming);
+        props.constraints = vec![PropertyFilter {
+            key: "socket".into(),
+            condition: FilterCondition::Equals(json!("AM5")),
+        }];
+        let suggestions = suggest_builds(&catalog(), &props);
+        assert_eq!(suggestions[0].slots.cpu.as_deref(), Some("r5"));
+        assert_eq!(suggestions[0].slots.motherboard.as_deref(), Some("b650"));
+
+        props.constraints = vec![PropertyFilter {
+            key: "cores".into(),
+            condition: FilterCondition::Range { min: Some(8.0), max: Some(16.0) },
+        }];
+        let suggestions = suggest_builds(&catalog(), &props);
+        assert!(suggestions.iter().all(|x| x.slots.cpu.as_deref() == Some("i5")));
+    }
+
+    #[test]
+    fn constraints_with_units() {
+        let mut props = props(200000, UseCase::Gaming);
+        props.constraints = vec![PropertyFilter {
+            key: "max_tdp".into(),
+            condition: FilterCondition::Equals(json!("125 W")),
+        }];
+        let suggestions = suggest_builds(&catalog(), &props);
+        assert_eq!(suggestions[0].slots.cpu.as_deref(), Some("i9"));
+
+        props.constraints = vec![PropertyFilter {
+            key: "wattage".into(),
+            condition: FilterCondition::OneOf(vec![json!("0.85 kW"), json!("1000 W")]),
+        }];
+        let suggestions = suggest_builds(&catalog(), &props);
+        assert!(!suggestions.is_empty());
+        assert!(suggestions.iter().all(|x| x.slots.psu.as_deref() == Some("psu850")));
+
+        props.constraints = vec![PropertyFilter {
+            key: "cores".into(),
+            condition: FilterCondition::Equals(json!(14.0)),
+        }];
+        let suggestions = suggest_builds(&catalog(), &props);
+        assert!(suggestions.iter().all(|x| x.slots.cpu.as_deref() == Some("i5")));
+
+        props.constraints = vec![PropertyFilter {
+            key: "max_tdp".into(),
+            condition: FilterCondition::Equals(json!("65 W")),
+        }];
+        let suggestions = suggest_builds(&catalog(), &props);
+        assert!(suggestions.iter().all(|x| x.slots.cpu.is_none()));
+    }
+}
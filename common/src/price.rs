@@ -0,0 +1,124 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use serde::{Serialize, Deserialize};
+use strum::{Display, EnumIter, EnumString};
+
+use crate::types::PartId;
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default, Debug, EnumIter, Display, EnumString)]
+pub enum Currency {
+    #[default]
+    EUR,
+    USD,
+    GBP,
+    CZK,
+}
+
+/// Price of a part observed at a retailer
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct Price {
+    pub part: String,
+    /// In hundredths of the currency, e.g. cents, so sums are exact
+    pub amount: u64,
+    pub currency: Currency,
+    pub retailer: String,
+    pub url: String,
+    /// RFC 3339 timestamp
+    pub observed_at: String,
+}
+
+impl Display for Price {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format_amount(self.amount, self.currency))
+    }
+}
+
+/// Amount in hundredths with its currency, e.g. `249.05 EUR`
+pub fn format_amount(amount: u64, currency: Currency) -> String {
+    format!("{}.{:02} {}", amount / 100, amount % 100, currency)
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct AddPriceProps {
+    pub part: PartId,
+    pub amount: u64,
+    pub currency: Currency,
+    pub retailer: String,
+    pub url: String,
+    /// RFC 3339 timestamp, None for now
+    #[serde(default)]
+    pub observed_at: Option<String>,
+}
+
+/// Prices of a part, oldest first
+#[derive(Serialize, Deserialize)]
+pub struct GetPriceHistoryProps {
+    pub part: PartId,
+    /// None for prices in all currencies
+    #[serde(default)]
+    pub currency: Option<Currency>,
+}
+
+/// Current lowest price of each part, parts without price in the currency are left out
+#[derive(Serialize, Deserialize)]
+pub struct GetLowestPricesProps {
+    pub parts: Vec<String>,
+    #[serde(default)]
+    pub currency: Currency,
+}
+
+/// Current lowest price of each part. Only the latest observation at every retailer is current,
+/// so a sale that ended doesn't count. Expects prices ordered oldest first.
+pub fn lowest_prices(prices: &[Price]) -> BTreeMap<String, Price> {
+    let mut current: BTreeMap<(&str, &str), &Price> = BTreeMap::new();
+    for price in prices {
+        current.insert((&price.part, &price.retailer), price);
+    }
+
+    let mut lowest: BTreeMap<String, Price> = BTreeMap::new();
+    for price in current.into_values() {
+        match lowest.get(&price.part) {
+            Some(cheaper) if cheaper.amount <= price.amount => {},
+            _ => { lowest.insert(price.part.clone(), price.clone()); },
+        }
+    }
+
+    lowest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lowest_prices, Price, Currency};
+
+    fn price(part: &str, retailer: &str, amount: u64) -> Price {
+        Price {
+            part: part.into(),
+            amount,
+            currency: Currency::EUR,
+            retailer: retailer.into(),
+            url: "".into(),
+            observed_at: "".into(),
+        }
+    }
+
+    #[test]
+    fn price_display() {
+        assert_eq!(price("cpu", "Alza", 24905).to_string(), "249.05 EUR");
+    }
+
+    #[test]
+    fn current_lowest_prices() {
+        let prices = vec![
+            price("cpu", "Alza", 20000),
+            price("cpu", "Czc", 25000),
+            price("gpu", "Alza", 50000),
+            // Sale at Alza ended
+            price("cpu", "Alza", 30000),
+        ];
+
+        let lowest = lowest_prices(&prices);
+        assert_eq!(lowest.len(), 2);
+        assert_eq!(lowest["cpu"], prices[1]);
+        assert_eq!(lowest["gpu"], prices[2]);
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::BTreeMap;
+
+use serde::{Serialize, Deserialize};
+use serde_json::{Map, Value};
+use strum::IntoEnumIterator;
+
+use crate::{DBPart, DBPartProps, PartsCategory, traits::{Field, FieldType, PartProperties}, types::{Frequency, Power, DataSize, Temperature, ReleaseDate}};
+
+/// Condition that a single property of a part has to fulfill
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct PropertyFilter {
+    /// Name of the field, e.g. `cores` or `max_frequency`
+    pub key: String,
+    pub condition: FilterCondition,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub enum FilterCondition {
+    /// Inclusive bounds, only for numeric properties.
+    /// Release dates are compared by their first day as number `yyyymmdd`, e.g. `20230101` for parts released since 2023.
+    Range {
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+    Equals(Value),
+    OneOf(Vec<Value>),
+}
+
+impl FilterCondition {
+    /// Whether an indexed property value fulfills the condition, the same way the database query decides.
+    /// Values of numeric properties are compared as numbers, so `8` equals `8.0` and `"65 W"` equals indexed `65000`.
+    pub fn matches(&self, value: &Value, numeric: bool) -> bool {
+        let equals = |expected: &Value| match numeric {
+            true => numeric_value(value).is_some() && numeric_value(value) == numeric_value(expected),
+            false => value == expected,
+        };
+
+        match self {
+            FilterCondition::Range { min, max } => match numeric_value(value) {
+                Some(number) => min.is_none_or(|min| number >= min) && max.is_none_or(|max| number <= max),
+                None => false,
+            },
+            FilterCondition::Equals(expected) => equals(expected),
+            FilterCondition::OneOf(values) => values.iter().any(equals),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default, Debug)]
+pub enum SortDirection {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct SortBy {
+    pub key: String,
+    #[serde(default)]
+    pub direction: SortDirection,
+}
+
+/// One page of parts, returned when parts are not requested by id
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct PartsPage {
+    pub parts: Vec<DBPart>,
+    /// Number of parts matching the query, ignoring pagination
+    pub total: u32,
+    pub offset: u32,
+    pub limit: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetPropertyValuesProps {
+    /// Name of the category variant, None: values across all parts
+    pub category: Option<String>,
+    pub key: String,
+}
+
+/// Number used to compare property values.
+/// Quantities are compared in their smallest unit, so "1 GHz" is greater than "900 MHz".
+/// Release dates are compared as number `yyyymmdd` of their first day, see [`ReleaseDate::as_number`].
+pub fn numeric_value(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(string) => {
+            if let Ok(quantity) = string.parse::<Frequency>() {
+                return Some(quantity.get() as f64);
+            }
+            if let Ok(quantity) = string.parse::<Power>() {
+                return Some(quantity.get() as f64);
+            }
+            if let Ok(quantity) = string.parse::<DataSize>() {
+                return Some(quantity.get() as f64);
+            }
+            if let Ok(quantity) = string.parse::<Temperature>() {
+                return Some(quantity.get() as f64);
+            }
+            if let Ok(date) = string.parse::<ReleaseDate>() {
+                return Some(date.as_number() as f64);
+            }
+
+            string.trim().parse().ok()
+        },
+        _ => None,
+    }
+}
+
+/// Type of a property, decides how it can be filtered
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub enum PropertyKind {
+    Number,
+    /// Number with unit, holds the unit used when none is given
+    Quantity(String),
+    Boolean,
+    Text,
+    /// Release date, compared as a number
+    Date,
+}
+
+impl From<&Field> for PropertyKind {
+    fn from(field: &Field) -> Self {
+        match (field.field_type, field.unit) {
+            (FieldType::Integer | FieldType::Decimal, _) => PropertyKind::Number,
+            (FieldType::Quantity, Some(unit)) => PropertyKind::Quantity(unit.to_string()),
+            (FieldType::Quantity, None) => PropertyKind::Number,
+            (FieldType::Boolean, _) => PropertyKind::Boolean,
+            (FieldType::Text, _) => PropertyKind::Text,
+            (FieldType::Date, _) => PropertyKind::Date,
+        }
+    }
+}
+
+impl PropertyKind {
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, PropertyKind::Number | PropertyKind::Quantity(_) | PropertyKind::Date)
+    }
+
+    /// Parses user input into value compared against indexed properties.
+    /// Plain numbers entered for a quantity are read in its default unit, so "65" for TDP means 65 W.
+    /// Dates are entered as release dates, e.g. "2023Q1".
+    pub fn parse_number(&self, input: &str) -> Option<f64> {
+        let input = input.trim();
+        match self {
+            PropertyKind::Quantity(unit) if input.parse::<f64>().is_ok() => {
+                numeric_value(&Value::String(format!("{} {}", input, unit)))
+            },
+            PropertyKind::Number | PropertyKind::Quantity(_) => numeric_value(&Value::String(input.to_string())),
+            PropertyKind::Date => input.parse::<ReleaseDate>().ok().map(|date| date.as_number() as f64),
+            _ => None,
+        }
+    }
+}
+
+/// Keys of part fields and properties of given category, with their kinds
+pub fn category_property_keys(category: PartsCategory) -> BTreeMap<String, PropertyKind> {
+    let props = DBPartProps {
+        category,
+        ..Default::default()
+    };
+
+    props.fields()
+        .into_iter()
+        .map(|field| (field.key.to_string(), PropertyKind::from(field)))
+        .collect()
+}
+
+/// All keys that can be filtered and sorted on, with their kinds
+pub fn property_keys() -> BTreeMap<String, PropertyKind> {
+    let mut keys: BTreeMap<String, PropertyKind> = BTreeMap::new();
+    for category in PartsCategory::iter() {
+        keys.extend(category_property_keys(category));
+    }
+
+    keys
+}
+
+impl DBPartProps {
+    /// Flat map of part fields and properties of its category.
+    /// Numeric properties are stored as numbers, so the database can compare and order them.
+    /// Unknown values, like a missing release date, are left out.
+    pub fn indexed_properties(&self) -> Map<String, Value> {
+        let keys = property_keys();
+        let mut properties = Map::new();
+        for (key, value) in self.flat_properties() {
+            let numeric = keys.get(&key).map(PropertyKind::is_numeric).unwrap_or(false);
+            let value = match (numeric, &value) {
+                (_, Value::Null) => continue,
+                (true, Value::String(_)) => numeric_value(&value).map(Value::from).unwrap_or(value),
+                _ => value,
+            };
+            properties.insert(key, value);
+        }
+
+        properties
+    }
+
+    fn flat_properties(&self) -> Vec<(String, Value)> {
+        self.fields()
+            .into_iter()
+            .filter_map(|field| Some((field.key.to_string(), self.get(field.key)?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{DBPartProps, PartsCategory, CPUProperties};
+
+    use super::{numeric_value, property_keys, FilterCondition, PropertyKind};
+
+    #[test]
+    fn numeric_values() {
+        assert_eq!(numeric_value(&json!("1 GHz")), Some(1_000_000_000.0));
+        assert_eq!(numeric_value(&json!("900 MHz")), Some(900_000_000.0));
+        assert_eq!(numeric_value(&json!("4.5")), Some(4.5));
+        assert_eq!(numeric_value(&json!(14)), Some(14.0));
+        assert_eq!(numeric_value(&json!("FCLGA1700")), None);
+        assert_eq!(numeric_value(&json!(true)), None);
+    }
+
+    #[test]
+    fn conditions() {
+        let equals = FilterCondition::Equals(json!("65 W"));
+        assert!(equals.matches(&json!(65_000.0), true));
+        assert!(!equals.matches(&json!(65_000.0), false));
+        assert!(FilterCondition::Equals(json!(8)).matches(&json!(8.0), true));
+        assert!(FilterCondition::Equals(json!("AM5")).matches(&json!("AM5"), false));
+        assert!(!FilterCondition::Equals(json!("AM5")).matches(&json!("AM4"), false));
+
+        let one_of = FilterCondition::OneOf(vec![json!("0.5 kW"), json!("65 W")]);
+        assert!(one_of.matches(&json!(500_000), true));
+        assert!(!one_of.matches(&json!(125_000), true));
+        assert!(FilterCondition::Range { min: Some(8.0), max: None }.matches(&json!(8), true));
+    }
+
+    #[test]
+    fn parse_numbers() {
+        let power = PropertyKind::Quantity("W".into());
+        assert_eq!(power.parse_number("65"), Some(65_000.0));
+        assert_eq!(power.parse_number("0.5 kW"), Some(500_000.0));
+        assert_eq!(power.parse_number("a lot"), None);
+        assert_eq!(PropertyKind::Number.parse_number(" 8 "), Some(8.0));
+        assert_eq!(PropertyKind::Text.parse_number("8"), None);
+        assert_eq!(PropertyKind::Date.parse_number("2023Q1"), Some(20230101.0));
+        assert_eq!(PropertyKind::Date.parse_number("2023"), None);
+    }
+
+    #[test]
+    fn indexed_properties() -> anyhow::Result<()> {
+        let props = DBPartProps {
+            name: "Intel Core i5-13500".into(),
+            release_date: "22Q4".parse().ok(),
+            rating: 3.5.into(),
+            category: PartsCategory::CPU(CPUProperties {
+                cores: 14,
+                max_frequency: "4.8 GHz".parse()?,
+                socket: "FCLGA1700".into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let properties = props.indexed_properties();
+        assert_eq!(properties["name"], json!("Intel Core i5-13500"));
+        assert_eq!(properties["rating"], json!(3.5));
+        assert_eq!(properties["release_date"], json!(20221001.0));
+        assert_eq!(properties["cores"], json!(14));
+        assert_eq!(properties["max_frequency"], json!(4_800_000_000.0));
+        assert_eq!(properties["socket"], json!("FCLGA1700"));
+        assert!(!properties.contains_key("category"));
+        assert!(!DBPartProps::default().indexed_properties().contains_key("release_date"));
+
+        let keys = property_keys();
+        assert_eq!(keys.get("cores"), Some(&PropertyKind::Number));
+        assert_eq!(keys.get("rating"), Some(&PropertyKind::Number));
+        assert_eq!(keys.get("wattage"), Some(&PropertyKind::Quantity("W".into())));
+        assert_eq!(keys.get("socket"), Some(&PropertyKind::Text));
+        assert_eq!(keys.get("name"), Some(&PropertyKind::Text));
+        assert_eq!(keys.get("pcie_version"), Some(&PropertyKind::Text));
+        assert_eq!(keys.get("ecc"), Some(&PropertyKind::Boolean));
+        assert_eq!(keys.get("release_date"), Some(&PropertyKind::Date));
+        assert_eq!(keys.get("category"), None);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use serde::{Serialize, Deserialize};
+
+use crate::types::StringenFloat;
+
+/// Fewest stars of a review
+pub const MIN_STARS: u8 = 1;
+/// Most stars of a review
+pub const MAX_STARS: u8 = 5;
+/// Longest text of a review, in characters
+pub const MAX_REVIEW_LENGTH: usize = 2000;
+
+/// Review of a part, each user has at most one review of a part
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct Review {
+    pub part: String,
+    /// Username of the author
+    pub username: String,
+    /// From 1 to 5
+    pub stars: u8,
+    pub text: String,
+    /// RFC 3339 timestamp of the last change
+    pub updated_at: String,
+}
+
+/// Review of the logged in user, replaces the previous one of the part
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct SubmitReviewProps {
+    pub stars: u8,
+    #[serde(default)]
+    pub text: String,
+}
+
+/// Reviews of a part, newest first, with rating of the part they add up to
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct PartReviews {
+    pub rating: StringenFloat,
+    pub review_count: u32,
+    pub reviews: Vec<Review>,
+}
+
+/// Rating of a part computed from stars of its reviews, 0 without reviews.
+/// The average is rounded to hundredths, so it's shown the same everywhere.
+pub fn average_rating(stars: &[u8]) -> StringenFloat {
+    if stars.is_empty() {
+        return StringenFloat::new(0.0);
+    }
+
+    let sum: u32 = stars.iter().map(|x| *x as u32).sum();
+    let average = sum as f64 / stars.len() as f64;
+    StringenFloat::new((average * 100.0).round() / 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::average_rating;
+
+    #[test]
+    fn average_ratings() {
+        assert_eq!(average_rating(&[]).get(), 0.0);
+        assert_eq!(average_rating(&[4]).get(), 4.0);
+        assert_eq!(average_rating(&[5, 4]).get(), 4.5);
+        assert_eq!(average_rating(&[5, 4, 4]).get(), 4.33);
+        assert_eq!(average_rating(&[1, 2, 2]).to_string(), "1.67");
+    }
+}
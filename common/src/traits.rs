@@ -1,35 +1,229 @@
 use std::collections::HashMap;
-use serde::Serialize;
-use serde_json::Value;
-
-pub trait PartProperties: Serialize {
-    fn to_string_vec(&self) -> anyhow::Result<HashMap<String, String>> {
-        let field_values_array = serde_json::to_string(&self.clone())?;
-        let mut chars = field_values_array.chars();
-        chars.next();
-        chars.next_back();
-        let field_values_array = format!("[{}]", chars.as_str());
-        let field_values_array = field_values_array.replace("\":", "\",");
-        let array: Vec<Value> = serde_json::from_str(&field_values_array)?;
-        let array: Vec<String> = array.iter().map(|x| x.to_string()).collect();
-        let mut map: HashMap<String, String> = HashMap::new();
-        for i in 0..array.len() / 2 {
-            let key = array.get(i * 2);
-            let value = array.get(i * 2 + 1);
-            if let (Some(key), Some(value)) = (key, value) {
-                let mut key = key.replace("\"", "");
-                let mut chars: Vec<char> = key.chars().collect();
-                let uppercase_char = chars[0].to_uppercase().nth(0);
-                if let Some(uppercase_char) = uppercase_char {
-                    chars[0] = uppercase_char;
-                    key = chars.into_iter().collect();
-                }
-                key = key.replace("_", " ");
-                let value = value.replace("\"", "");
-                map.insert(key.to_string(), value.to_string());
-            }
+use serde::{Serialize, de::DeserializeOwned};
+pub use serde_json::Value;
+
+use crate::{error::FieldError, types::{Quantity, ReleaseDate, StringenFloat, Unit}};
+
+/// How a property is stored, decides how its value is entered and compared
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FieldType {
+    Text,
+    Integer,
+    Decimal,
+    Boolean,
+    /// Number with unit, e.g. "4.8 GHz"
+    Quantity,
+    /// Release date, empty if unknown
+    Date,
+}
+
+/// Description of a single property of a part
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Field {
+    /// Name of the field as it's serialized, e.g. `max_tdp`
+    pub key: &'static str,
+    /// Name shown to users, e.g. "Max tdp"
+    pub label: &'static str,
+    pub field_type: FieldType,
+    /// Unit of quantities entered as plain numbers
+    pub unit: Option<&'static str>,
+    /// Accepted values of text properties, empty if any text is accepted
+    pub options: &'static [&'static str],
+    pub required: bool,
+    /// Kept up to date by the server, not entered by users
+    pub computed: bool,
+}
+
+impl Field {
+    /// Defaults of metadata not read from the field itself
+    #[doc(hidden)]
+    pub const DEFAULT: Field = Field {
+        key: "",
+        label: "",
+        field_type: FieldType::Text,
+        unit: None,
+        options: &[],
+        required: false,
+        computed: false,
+    };
+
+    /// Whether text is one of the options, empty text is accepted as unset value
+    pub fn accepts(&self, text: &str) -> bool {
+        self.options.is_empty() || text.is_empty() || self.options.contains(&text)
+    }
+}
+
+/// Type of a property field, with the way user input is read into it
+pub trait PropertyValue: Serialize + DeserializeOwned {
+    const TYPE: FieldType;
+    const UNIT: Option<&'static str> = None;
+}
+
+impl PropertyValue for String {
+    const TYPE: FieldType = FieldType::Text;
+}
+
+impl PropertyValue for u32 {
+    const TYPE: FieldType = FieldType::Integer;
+}
+
+impl PropertyValue for bool {
+    const TYPE: FieldType = FieldType::Boolean;
+}
+
+impl PropertyValue for StringenFloat {
+    const TYPE: FieldType = FieldType::Decimal;
+}
+
+impl PropertyValue for Option<ReleaseDate> {
+    const TYPE: FieldType = FieldType::Date;
+}
+
+impl<U: Unit> PropertyValue for Quantity<U> {
+    const TYPE: FieldType = FieldType::Quantity;
+    const UNIT: Option<&'static str> = Some(U::ZERO_UNIT);
+}
+
+/// Type and unit of a field, used by [`part_properties!`] to read them from the field itself
+#[doc(hidden)]
+pub const fn field_type<S, T: PropertyValue>(_: fn(&S) -> &T) -> (FieldType, Option<&'static str>) {
+    (T::TYPE, T::UNIT)
+}
+
+#[doc(hidden)]
+pub fn get_field<T: PropertyValue>(value: &T) -> Option<Value> {
+    serde_json::to_value(value).ok()
+}
+
+/// Reads a value into a field, checking options of the field
+#[doc(hidden)]
+pub fn set_field<T: PropertyValue>(target: &mut T, field: &Field, value: Value) -> Result<(), FieldError> {
+    if let Value::String(text) = &value {
+        if !field.accepts(text) {
+            return Err(FieldError::new(field.key, format!("must be one of {}", field.options.join(", "))));
+        }
+    }
+
+    *target = serde_json::from_value(value).map_err(|x| FieldError::new(field.key, x.to_string()))?;
+    Ok(())
+}
+
+/// Properties with metadata of their fields, so they can be listed, shown and edited by key.
+/// Implemented with [`part_properties!`], keys of nested categories are reached through their parts.
+pub trait PartProperties {
+    fn fields(&self) -> Vec<&'static Field>;
+
+    /// Value of the field as it's serialized, None for unknown keys
+    fn get(&self, key: &str) -> Option<Value>;
+
+    /// Replaces value of the field, the value has the same form as [`PartProperties::get`] returns
+    fn set(&mut self, key: &str, value: Value) -> Result<(), FieldError>;
+
+    fn field(&self, key: &str) -> Option<&'static Field> {
+        self.fields().into_iter().find(|x| x.key == key)
+    }
+
+    /// Replaces value of the field with text entered by user.
+    /// Plain numbers entered for a quantity are read in its unit, so "65" for TDP means 65 W.
+    fn set_input(&mut self, key: &str, input: &str) -> Result<(), FieldError> {
+        let field = self.field(key).ok_or_else(|| FieldError::new(key, "unknown property"))?;
+        let input = input.trim();
+        let value = match field.field_type {
+            FieldType::Text | FieldType::Decimal => Value::from(input),
+            FieldType::Date if input.is_empty() => Value::Null,
+            FieldType::Date => Value::from(input),
+            FieldType::Quantity => match (input.parse::<f64>(), field.unit) {
+                (Ok(_), Some(unit)) => Value::from(format!("{} {}", input, unit)),
+                _ => Value::from(input),
+            },
+            FieldType::Integer => match input.parse::<u32>() {
+                Ok(number) => Value::from(number),
+                Err(_) => return Err(FieldError::new(key, format!("{} is not a number", input))),
+            },
+            FieldType::Boolean => match input.to_lowercase().as_str() {
+                "true" | "yes" => Value::Bool(true),
+                "false" | "no" => Value::Bool(false),
+                _ => return Err(FieldError::new(key, format!("{} is not true or false", input))),
+            },
+        };
+
+        self.set(key, value)
+    }
+
+    /// Value of the field as text, e.g. "4.8 GHz" or "true", empty if the value is unknown
+    fn display(&self, key: &str) -> Option<String> {
+        match self.get(key)? {
+            Value::String(text) => Some(text),
+            Value::Null => Some(String::new()),
+            value => Some(value.to_string()),
         }
+    }
 
-        return Ok(map);
+    /// Values of all fields as text, keyed by their labels
+    fn labeled_values(&self) -> HashMap<String, String> {
+        self.fields().into_iter()
+            .filter_map(|field| Some((field.label.to_string(), self.display(field.key)?)))
+            .collect()
     }
 }
+
+/// Implements [`PartProperties`] for a struct from labels of its fields.
+/// Other metadata, like options or whether the field is required, can follow the label.
+/// A trailing `..field` delegates unknown keys to nested properties, like category of a part.
+///
+/// ```ignore
+/// part_properties!(StorageProperties {
+///     storage_type: "Storage type" { options: &["SSD", "HDD"] },
+///     capacity: "Capacity",
+/// });
+/// ```
+#[macro_export]
+macro_rules! part_properties {
+    ($name:ident {
+        $($field:ident: $label:literal $({ $($meta:ident: $value:expr),* $(,)? })?),* $(,)?
+        $(..$nested:ident)?
+    }) => {
+        impl $name {
+            pub const FIELDS: &'static [$crate::traits::Field] = &[
+                $({
+                    let (field_type, unit) = $crate::traits::field_type(|x: &$name| &x.$field);
+                    $crate::traits::Field {
+                        key: stringify!($field),
+                        label: $label,
+                        field_type,
+                        unit,
+                        $($($meta: $value,)*)?
+                        ..$crate::traits::Field::DEFAULT
+                    }
+                }),*
+            ];
+        }
+
+        impl $crate::traits::PartProperties for $name {
+            fn fields(&self) -> Vec<&'static $crate::traits::Field> {
+                #[allow(unused_mut)]
+                let mut fields: Vec<&'static $crate::traits::Field> = Self::FIELDS.iter().collect();
+                $(fields.extend(self.$nested.fields());)?
+                fields
+            }
+
+            fn get(&self, key: &str) -> Option<$crate::traits::Value> {
+                match key {
+                    $(stringify!($field) => $crate::traits::get_field(&self.$field),)*
+                    _ => $crate::part_properties!(@nested self, get(key) $(, $nested)?),
+                }
+            }
+
+            fn set(&mut self, key: &str, value: $crate::traits::Value) -> Result<(), $crate::error::FieldError> {
+                let field = Self::FIELDS.iter().find(|x| x.key == key);
+                match (key, field) {
+                    $((stringify!($field), Some(field)) => $crate::traits::set_field(&mut self.$field, field, value),)*
+                    _ => $crate::part_properties!(@nested self, set(key, value) $(, $nested)?),
+                }
+            }
+        }
+    };
+    (@nested $self:ident, get($key:ident)) => { None };
+    (@nested $self:ident, set($key:ident, $value:ident)) => { Err($crate::error::FieldError::new($key, "unknown property")) };
+    (@nested $self:ident, $method:ident($($arg:ident),*), $nested:ident) => { $self.$nested.$method($($arg),*) };
+}
@@ -1,4 +1,4 @@
-use std::{num::ParseFloatError, fmt::Display};
+use std::{num::ParseFloatError, fmt::Display, marker::PhantomData, str::FromStr, ops::Add, iter::Sum};
 
 use serde::{de::Visitor, Serialize, Deserialize};
 
@@ -108,3 +108,489 @@ impl<'de> Deserialize<'de> for StringenFloat {
         deserializer.deserialize_str(StringenFloatVisitor)
     }
 }
+
+/// Unit family of a [`Quantity`]. Values are stored as integer count of the smallest unit.
+pub trait Unit {
+    /// Symbols with their size in the smallest unit, ordered from the smallest.
+    /// First symbol of each entry is used for display, the rest are accepted aliases.
+    const UNITS: &'static [(&'static [&'static str], i64)];
+    /// Symbol used to display zero
+    const ZERO_UNIT: &'static str;
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct Hertz;
+
+impl Unit for Hertz {
+    const UNITS: &'static [(&'static [&'static str], i64)] = &[
+        (&["Hz"], 1),
+        (&["kHz"], 1_000),
+        (&["MHz"], 1_000_000),
+        (&["GHz"], 1_000_000_000),
+    ];
+    const ZERO_UNIT: &'static str = "GHz";
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct Milliwatt;
+
+impl Unit for Milliwatt {
+    const UNITS: &'static [(&'static [&'static str], i64)] = &[
+        (&["mW"], 1),
+        (&["W"], 1_000),
+        (&["kW"], 1_000_000),
+    ];
+    const ZERO_UNIT: &'static str = "W";
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct Byte;
+
+// Binary multiples, as used by memory manufacturers
+impl Unit for Byte {
+    const UNITS: &'static [(&'static [&'static str], i64)] = &[
+        (&["B"], 1),
+        (&["KB", "KiB"], 1 << 10),
+        (&["MB", "MiB"], 1 << 20),
+        (&["GB", "GiB"], 1 << 30),
+        (&["TB", "TiB"], 1 << 40),
+        (&["PB", "PiB"], 1 << 50),
+    ];
+    const ZERO_UNIT: &'static str = "GB";
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct MilliCelsius;
+
+impl Unit for MilliCelsius {
+    const UNITS: &'static [(&'static [&'static str], i64)] = &[
+        (&["m°C"], 1),
+        (&["°C", "C", "℃"], 1_000),
+    ];
+    const ZERO_UNIT: &'static str = "°C";
+}
+
+pub type Frequency = Quantity<Hertz>;
+pub type Power = Quantity<Milliwatt>;
+pub type DataSize = Quantity<Byte>;
+pub type Temperature = Quantity<MilliCelsius>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantityParseError(String);
+
+impl Display for QuantityParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for QuantityParseError {}
+
+/// Value with a unit, like "4.8 GHz" or "154 W".
+/// Stored as integer count of the smallest unit, so it can be compared, summed and sorted.
+/// Serialized as human readable text, which round-trips without loss.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct Quantity<U: Unit> {
+    value: i64,
+    unit: PhantomData<U>,
+}
+
+impl<U: Unit> Quantity<U> {
+    /// Creates quantity from count of the smallest unit
+    pub const fn new(value: i64) -> Self {
+        Self {
+            value,
+            unit: PhantomData,
+        }
+    }
+
+    /// Count of the smallest unit
+    pub fn get(&self) -> i64 {
+        self.value
+    }
+
+    /// Value converted to the given unit symbol, e.g. `as_unit("GHz")`
+    pub fn as_unit(&self, symbol: &str) -> Option<f64> {
+        let factor = find_unit::<U>(symbol)?;
+        Some(self.value as f64 / factor as f64)
+    }
+}
+
+fn find_unit<U: Unit>(symbol: &str) -> Option<i64> {
+    let exact = U::UNITS.iter().find(|(symbols, _)| symbols.contains(&symbol));
+    let exact = exact.or_else(|| U::UNITS.iter().find(|(symbols, _)| symbols.iter().any(|x| x.eq_ignore_ascii_case(symbol))));
+
+    exact.map(|(_, factor)| *factor)
+}
+
+impl<U: Unit> Display for Quantity<U> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.value == 0 {
+            return write!(f, "0 {}", U::ZERO_UNIT);
+        }
+
+        let value = self.value as i128;
+        let absolute = value.abs();
+        // Biggest unit which shows the value exactly with at most 3 decimal places
+        let unit = U::UNITS.iter().rev().find(|(_, factor)| {
+            let factor = *factor as i128;
+            absolute >= factor && (absolute * 1000) % factor == 0
+        });
+
+        let (symbols, factor) = unit.unwrap_or(&U::UNITS[0]);
+        let factor = *factor as i128;
+        let sign = if value < 0 { "-" } else { "" };
+        let whole = absolute / factor;
+        let thousandths = (absolute % factor) * 1000 / factor;
+
+        if thousandths == 0 {
+            write!(f, "{}{} {}", sign, whole, symbols[0])
+        } else {
+            let fraction = format!("{:03}", thousandths);
+            write!(f, "{}{}.{} {}", sign, whole, fraction.trim_end_matches('0'), symbols[0])
+        }
+    }
+}
+
+impl<U: Unit> FromStr for Quantity<U> {
+    type Err = QuantityParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || QuantityParseError(format!("Invalid quantity: {}", s));
+        let s = s.trim();
+        let split = s
+            .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-'))
+            .unwrap_or(s.len());
+        let (number, symbol) = s.split_at(split);
+        let factor = find_unit::<U>(symbol.trim()).ok_or_else(error)? as i128;
+
+        let negative = number.starts_with('-');
+        let number = number.trim_start_matches('-');
+        let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+        if whole.is_empty() && fraction.is_empty() {
+            return Err(error());
+        }
+
+        let digits = format!("{}{}", whole, fraction);
+        let mantissa: i128 = digits.parse().map_err(|_| error())?;
+        let scale = 10_i128.checked_pow(fraction.len() as u32).ok_or_else(error)?;
+        let scaled = mantissa.checked_mul(factor).ok_or_else(error)?;
+        // Round to the nearest smallest unit
+        let value = (scaled + scale / 2) / scale;
+        let value = if negative { -value } else { value };
+
+        Ok(Self::new(i64::try_from(value).map_err(|_| error())?))
+    }
+}
+
+/// Saturates at the bounds, values summed may come from user input
+impl<U: Unit> Add for Quantity<U> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.value.saturating_add(rhs.value))
+    }
+}
+
+impl<U: Unit> Sum for Quantity<U> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::new(0), |x, y| x + y)
+    }
+}
+
+impl<U: Unit> Serialize for Quantity<U> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: serde::Serializer
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+struct QuantityVisitor<U: Unit>(PhantomData<U>);
+
+impl<U: Unit> Visitor<'_> for QuantityVisitor<U> {
+    type Value = Quantity<U>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a number with unit represented by a string")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where E: serde::de::Error,
+    {
+        Quantity::from_str(v).map_err(E::custom)
+    }
+}
+
+impl<'de, U: Unit> Deserialize<'de> for Quantity<U> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: serde::Deserializer<'de>
+    {
+        deserializer.deserialize_str(QuantityVisitor(PhantomData))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReleaseDateParseError(String);
+
+impl Display for ReleaseDateParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ReleaseDateParseError {}
+
+/// How exactly a [`ReleaseDate`] is known, ordered from the least exact
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum DatePrecision {
+    Quarter,
+    Month,
+    Day,
+}
+
+/// Date a part was released, known to a quarter, month or day.
+/// Serialized as `2022Q4`, `2022-10` or `2022-10-13`, the older `22Q4` is read as a quarter of 2000s.
+/// Dates are ordered by their first day, a less exact date comes before a more exact one starting the same day.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ReleaseDate {
+    year: u16,
+    /// First month of the quarter for quarters
+    month: u8,
+    /// First day of the month for quarters and months
+    day: u8,
+    precision: DatePrecision,
+}
+
+impl ReleaseDate {
+    /// None unless the quarter is 1 to 4
+    pub fn from_quarter(year: u16, quarter: u8) -> Option<Self> {
+        match quarter {
+            1..=4 => Self::new(year, quarter * 3 - 2, 1, DatePrecision::Quarter),
+            _ => None,
+        }
+    }
+
+    pub fn from_month(year: u16, month: u8) -> Option<Self> {
+        Self::new(year, month, 1, DatePrecision::Month)
+    }
+
+    /// None if the day is not in the month, e.g. 2023-02-29
+    pub fn from_day(year: u16, month: u8, day: u8) -> Option<Self> {
+        Self::new(year, month, day, DatePrecision::Day)
+    }
+
+    fn new(year: u16, month: u8, day: u8, precision: DatePrecision) -> Option<Self> {
+        let leap = year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+        let days = match month {
+            2 if leap => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            1..=12 => 31,
+            _ => return None,
+        };
+        if !(1000..=9999).contains(&year) || !(1..=days).contains(&day) {
+            return None;
+        }
+
+        Some(Self { year, month, day, precision })
+    }
+
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    /// Quarter the date is in, 1 to 4
+    pub fn quarter(&self) -> u8 {
+        self.month.div_ceil(3)
+    }
+
+    /// None if only the quarter is known
+    pub fn month(&self) -> Option<u8> {
+        match self.precision {
+            DatePrecision::Quarter => None,
+            _ => Some(self.month),
+        }
+    }
+
+    /// None unless the exact day is known
+    pub fn day(&self) -> Option<u8> {
+        match self.precision {
+            DatePrecision::Day => Some(self.day),
+            _ => None,
+        }
+    }
+
+    pub fn precision(&self) -> DatePrecision {
+        self.precision
+    }
+
+    /// First day of the date as number `yyyymmdd`, e.g. `20221001` for `2022Q4`.
+    /// Used to index, sort and filter release dates as numbers.
+    pub fn as_number(&self) -> u32 {
+        self.year as u32 * 10_000 + self.month as u32 * 100 + self.day as u32
+    }
+}
+
+impl Display for ReleaseDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.precision {
+            DatePrecision::Quarter => write!(f, "{}Q{}", self.year, self.quarter()),
+            DatePrecision::Month => write!(f, "{}-{:02}", self.year, self.month),
+            DatePrecision::Day => write!(f, "{}-{:02}-{:02}", self.year, self.month, self.day),
+        }
+    }
+}
+
+impl FromStr for ReleaseDate {
+    type Err = ReleaseDateParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ReleaseDateParseError(format!("Invalid release date: {}", s));
+        let number = |text: &str| match text.chars().all(|c| c.is_ascii_digit()) {
+            true => text.parse::<u16>().ok(),
+            false => None,
+        };
+        let s = s.trim();
+
+        let date = match s.split_once(['Q', 'q']) {
+            Some((year, quarter)) if quarter.len() == 1 => {
+                let year = match year.len() {
+                    2 => number(year).map(|year| 2000 + year),
+                    4 => number(year),
+                    _ => None,
+                };
+                ReleaseDate::from_quarter(year.ok_or_else(error)?, number(quarter).ok_or_else(error)? as u8)
+            },
+            Some(_) => None,
+            None => {
+                let parts: Vec<&str> = s.split('-').collect();
+                if parts[0].len() != 4 || parts[1..].iter().any(|x| x.len() != 2) {
+                    return Err(error());
+                }
+                let parts: Vec<u16> = parts.into_iter().map(number).collect::<Option<_>>().ok_or_else(error)?;
+
+                match parts[..] {
+                    [year, month] => ReleaseDate::from_month(year, month as u8),
+                    [year, month, day] => ReleaseDate::from_day(year, month as u8, day as u8),
+                    _ => None,
+                }
+            },
+        };
+
+        date.ok_or_else(error)
+    }
+}
+
+impl Serialize for ReleaseDate {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: serde::Serializer
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+struct ReleaseDateVisitor;
+
+impl Visitor<'_> for ReleaseDateVisitor {
+    type Value = ReleaseDate;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a quarter, month or day represented by a string")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where E: serde::de::Error,
+    {
+        ReleaseDate::from_str(v).map_err(E::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for ReleaseDate {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: serde::Deserializer<'de>
+    {
+        deserializer.deserialize_str(ReleaseDateVisitor)
+    }
+}
+
+impl ReleaseDate {
+    /// Deserializes an optional release date, an empty string is unknown like `null`.
+    /// Use with `#[serde(default, deserialize_with = "ReleaseDate::deserialize_optional")]`.
+    pub fn deserialize_optional<'de, D>(deserializer: D) -> Result<Option<Self>, D::Error>
+    where D: serde::Deserializer<'de>
+    {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(date) if !date.is_empty() => ReleaseDate::from_str(&date).map(Some).map_err(serde::de::Error::custom),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartIdError(String);
+
+impl Display for PartIdError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for PartIdError {}
+
+/// Id of a part record without its table, e.g. `x4vbb2x0ahvn3u5eb6he`.
+/// Only ASCII letters, digits and underscores are accepted, so the id can't change a query it's used in.
+/// The `part:` prefix returned by the database is accepted and dropped.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PartId(String);
+
+impl PartId {
+    /// Longest accepted id, ids generated by the database have 20 characters
+    pub const MAX_LEN: usize = 64;
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for PartId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for PartId {
+    type Err = PartIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let id = s.strip_prefix("part:").unwrap_or(s);
+        if id.is_empty() || id.len() > Self::MAX_LEN || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(PartIdError(format!("Invalid part id: {}", s)));
+        }
+
+        Ok(Self(id.to_string()))
+    }
+}
+
+impl TryFrom<String> for PartId {
+    type Error = PartIdError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::from_str(&value)
+    }
+}
+
+impl From<PartId> for String {
+    fn from(value: PartId) -> Self {
+        value.0
+    }
+}
+
+/// Record of the part, to be bound as a variable of a query
+#[cfg(feature = "surreal")]
+impl From<&PartId> for surrealdb::sql::Value {
+    fn from(value: &PartId) -> Self {
+        surrealdb::sql::Thing::from(("part", value.as_str())).into()
+    }
+}
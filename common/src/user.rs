@@ -0,0 +1,125 @@
+use serde::{Serialize, Deserialize};
+use strum::{Display, EnumIter, EnumString};
+
+use crate::types::PartId;
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default, Debug, EnumIter, Display, EnumString)]
+pub enum Role {
+    /// Manages users and parts
+    Admin,
+    /// Manages parts
+    Editor,
+    #[default]
+    Viewer,
+}
+
+/// Actions only some roles are allowed to do
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug, EnumIter, Display)]
+pub enum Permission {
+    CreateParts,
+    /// Includes reading history of parts and recording their prices
+    UpdateParts,
+    DeleteParts,
+    ManageUsers,
+    SaveBuilds,
+    SaveFavorites,
+    WriteReviews,
+}
+
+impl Role {
+    /// Whether this role has rights of required role, e.g. admins can do everything editors can
+    pub fn has(&self, required: Role) -> bool {
+        self.rank() >= required.rank()
+    }
+
+    pub fn can(&self, permission: Permission) -> bool {
+        let required = match permission {
+            Permission::SaveBuilds | Permission::SaveFavorites | Permission::WriteReviews => Role::Viewer,
+            Permission::CreateParts | Permission::UpdateParts => Role::Editor,
+            Permission::DeleteParts | Permission::ManageUsers => Role::Admin,
+        };
+
+        self.has(required)
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            Role::Admin => 2,
+            Role::Editor => 1,
+            Role::Viewer => 0,
+        }
+    }
+}
+
+/// User without its password hash, which never leaves the backend
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct User {
+    pub id: String,
+    pub username: String,
+    pub role: Role,
+    /// Disabled users can't log in
+    #[serde(default)]
+    pub disabled: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct CreateUserProps {
+    pub username: String,
+    pub password: String,
+    pub role: Role,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DisableUserProps {
+    pub id: String,
+    /// False enables user again
+    pub disabled: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResetPasswordProps {
+    pub id: String,
+    pub password: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LoginProps {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct LoginResponse {
+    /// Sent back as `Authorization: Bearer <token>`
+    pub token: String,
+    pub user: User,
+    /// Unix timestamp in seconds
+    pub expires: u64,
+}
+
+/// Adds or removes a part from favorites of logged in user
+#[derive(Serialize, Deserialize)]
+pub struct FavoriteProps {
+    pub part: PartId,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Role, Permission};
+
+    #[test]
+    fn role_rights() {
+        assert!(Role::Admin.has(Role::Editor));
+        assert!(Role::Editor.has(Role::Editor));
+        assert!(!Role::Viewer.has(Role::Editor));
+    }
+
+    #[test]
+    fn role_permissions() {
+        assert!(Role::Viewer.can(Permission::SaveBuilds));
+        assert!(!Role::Viewer.can(Permission::CreateParts));
+        assert!(Role::Editor.can(Permission::UpdateParts));
+        assert!(!Role::Editor.can(Permission::DeleteParts));
+        assert!(Role::Admin.can(Permission::ManageUsers));
+    }
+}
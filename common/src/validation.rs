@@ -0,0 +1,201 @@
+use serde_json::Value;
+
+use crate::{DBPartProps, PartsCategory, error::FieldError, review::{MAX_REVIEW_LENGTH, MAX_STARS, MIN_STARS, SubmitReviewProps}, traits::PartProperties, user::CreateUserProps};
+
+/// Highest rating of a part, the lowest is 0
+pub const MAX_RATING: f64 = 5.0;
+
+/// Rules a part has to follow before it's stored, the same ones are checked by forms before sending.
+/// All invalid fields are returned, keyed the same way as [`PartProperties`].
+pub fn validate_part(part: &DBPartProps) -> Result<(), Vec<FieldError>> {
+    let mut errors = Vec::new();
+
+    for field in part.fields() {
+        let text = match part.get(field.key) {
+            Some(Value::String(text)) => text,
+            _ => continue,
+        };
+
+        if field.required && text.trim().is_empty() {
+            errors.push(FieldError::new(field.key, "is required"));
+        } else if !field.accepts(&text) {
+            errors.push(FieldError::new(field.key, format!("must be one of {}", field.options.join(", "))));
+        }
+    }
+
+    if !(0.0..=MAX_RATING).contains(&part.rating.get()) {
+        errors.push(FieldError::new("rating", format!("must be between 0 and {}", MAX_RATING)));
+    }
+
+    // Values left at zero are unknown
+    match &part.category {
+        PartsCategory::CPU(props) => {
+            if props.threads > 0 && props.threads < props.cores {
+                errors.push(FieldError::new("threads", "must be at least the number of cores"));
+            }
+            if props.max_frequency.get() > 0 && props.base_frequency > props.max_frequency {
+                errors.push(FieldError::new("base_frequency", "must not be above max frequency"));
+            }
+            if props.max_tdp.get() > 0 && props.base_tdp > props.max_tdp {
+                errors.push(FieldError::new("base_tdp", "must not be above max TDP"));
+            }
+        },
+        PartsCategory::GPU(props) if props.boost_clock.get() > 0 && props.base_clock > props.boost_clock => {
+            errors.push(FieldError::new("base_clock", "must not be above boost clock"));
+        },
+        _ => {},
+    }
+
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors),
+    }
+}
+
+/// Rules a review has to follow before it's stored
+pub fn validate_review(review: &SubmitReviewProps) -> Result<(), Vec<FieldError>> {
+    let mut errors = Vec::new();
+
+    if !(MIN_STARS..=MAX_STARS).contains(&review.stars) {
+        errors.push(FieldError::new("stars", format!("must be between {} and {}", MIN_STARS, MAX_STARS)));
+    }
+    if review.text.chars().count() > MAX_REVIEW_LENGTH {
+        errors.push(FieldError::new("text", format!("must have at most {} characters", MAX_REVIEW_LENGTH)));
+    }
+
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors),
+    }
+}
+
+/// Rules a new user has to follow, whether it's added through the API or the CLI
+pub fn validate_user(props: &CreateUserProps) -> Result<(), Vec<FieldError>> {
+    let mut errors = Vec::new();
+
+    if props.username.trim().is_empty() {
+        errors.push(FieldError::new("username", "is required"));
+    }
+    if let Err(mut password_errors) = validate_password(&props.password) {
+        errors.append(&mut password_errors);
+    }
+
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors),
+    }
+}
+
+/// Rules a password has to follow when a user is added or their password is reset
+pub fn validate_password(password: &str) -> Result<(), Vec<FieldError>> {
+    match password.is_empty() {
+        true => Err(vec![FieldError::new("password", "is required")]),
+        false => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DBPartProps, PartsCategory, CPUProperties, GPUProperties, StorageProperties};
+
+    use crate::{error::FieldError, review::SubmitReviewProps, user::{CreateUserProps, Role}};
+
+    use super::{validate_part, validate_password, validate_review, validate_user};
+
+    fn cpu() -> DBPartProps {
+        DBPartProps {
+            name: "Intel Core i5-13500".into(),
+            release_date: "2022Q4".parse().ok(),
+            rating: 3.5.into(),
+            category: PartsCategory::CPU(CPUProperties {
+                cores: 14,
+                threads: 20,
+                base_frequency: "2.5 GHz".parse().unwrap(),
+                max_frequency: "4.8 GHz".parse().unwrap(),
+                base_tdp: "65 W".parse().unwrap(),
+                max_tdp: "154 W".parse().unwrap(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn invalid_fields(part: &DBPartProps) -> Vec<String> {
+        match validate_part(part) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors.into_iter().map(|x| x.field).collect(),
+        }
+    }
+
+    #[test]
+    fn valid_parts() {
+        assert_eq!(validate_part(&cpu()), Ok(()));
+
+        // Only name is required, unknown values stay empty
+        let monitor = DBPartProps {
+            name: "Monitor".into(),
+            ..Default::default()
+        };
+        assert_eq!(validate_part(&monitor), Ok(()));
+    }
+
+    #[test]
+    fn invalid_parts() {
+        let mut part = cpu();
+        part.name = "  ".into();
+        part.rating = 57.0.into();
+        if let PartsCategory::CPU(props) = &mut part.category {
+            props.threads = 8;
+            props.base_tdp = "200 W".parse().unwrap();
+        }
+        assert_eq!(invalid_fields(&part), ["name", "rating", "threads", "base_tdp"]);
+
+        part = cpu();
+        part.rating = (-0.5).into();
+        assert_eq!(invalid_fields(&part), ["rating"]);
+
+        let gpu = DBPartProps {
+            name: "RTX 3060 Ti".into(),
+            category: PartsCategory::GPU(GPUProperties {
+                base_clock: "1.67 GHz".parse().unwrap(),
+                boost_clock: "1.41 GHz".parse().unwrap(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(invalid_fields(&gpu), ["base_clock"]);
+
+        let storage = DBPartProps {
+            name: "Tape".into(),
+            category: PartsCategory::Storage(StorageProperties {
+                storage_type: "Tape".into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(invalid_fields(&storage), ["storage_type"]);
+    }
+
+    #[test]
+    fn reviews() {
+        let review = |stars, text: &str| validate_review(&SubmitReviewProps { stars, text: text.into() });
+
+        assert_eq!(review(1, ""), Ok(()));
+        assert_eq!(review(5, "Quiet and cool"), Ok(()));
+        assert!(review(0, "").is_err());
+        assert!(review(6, "").is_err());
+        assert_eq!(review(4, &"a".repeat(2001)).unwrap_err()[0].field, "text");
+    }
+
+    #[test]
+    fn users() {
+        let user = |username: &str, password: &str| validate_user(&CreateUserProps { username: username.into(), password: password.into(), role: Role::Viewer });
+        let fields = |result: Result<(), Vec<FieldError>>| result.unwrap_err().into_iter().map(|x| x.field).collect::<Vec<_>>();
+
+        assert_eq!(user("Viewer", "viewer"), Ok(()));
+        assert_eq!(fields(user("", "viewer")), ["username"]);
+        assert_eq!(fields(user("  ", "")), ["username", "password"]);
+        assert_eq!(validate_password(" "), Ok(()));
+        assert_eq!(fields(validate_password("")), ["password"]);
+    }
+}
@@ -1,12 +1,13 @@
 use std::{rc::Rc, collections::HashMap};
 
-use common::{GetPartProps, DBPart, PartsCategory};
+use common::{error::ApiError, GetPartProps, DBPart, PartsCategory, query::{PartsPage, PropertyFilter}, user::{LoginResponse, User, FavoriteProps}, price::{Currency, GetLowestPricesProps, Price}, export::property_fields};
 use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::spawn_local;
 use web_sys::HtmlDivElement;
 use yew::prelude::*;
 use yew_router::prelude::*;
 
-use crate::{content::{ContentPage, Content}, header::Header, parts::Part, connection::post_from_db, filter::ordering};
+use crate::{content::{ContentPage, Content}, header::Header, parts::Part, connection::{post_from_db, set_error_callback, stored_session, store_session, stored_favorites, store_favorites}, filter::ordering};
 
 pub const PCPC_IP: &'static str = "http://127.0.0.1:8088";
 
@@ -22,10 +23,18 @@ pub struct AppContext {
     pub selected_category_callback: Callback<String>,
     pub filter_visibility: bool,
     pub filter_visibility_callback: Callback<bool>,
+    /// Ids of favorite parts, stored on the server for logged in users and in the browser otherwise
     pub favorites: Vec<String>,
     pub favorites_callback: Callback<(String, bool)>,
     pub search_term: String,
     pub search_term_callback: Callback<String>,
+    /// Filters of properties in selected category
+    pub property_filters: Vec<PropertyFilter>,
+    pub property_filters_callback: Callback<Vec<PropertyFilter>>,
+    /// Logged in user, None for anonymous visitors
+    pub user: Option<User>,
+    /// Stores new session after login, None logs out
+    pub session_callback: Callback<Option<LoginResponse>>,
 }
 
 pub async fn get_part_with_callback(context: Rc<AppContext>, id: String, callback: Callback<Part>) {
@@ -35,51 +44,116 @@ pub async fn get_part_with_callback(context: Rc<AppContext>, id: String, callbac
     }
 }
 
-pub async fn get_parts_with_callback(context: Rc<AppContext>, limit: u32, callback: Callback<Vec<Part>>) {
-    let parts = context.get_parts(limit).await;
-    if parts.len() > 0 {
-        callback.emit(parts);
+pub async fn get_parts_with_callback(context: Rc<AppContext>, offset: u32, limit: u32, callback: Callback<(u32, Vec<Part>, u32)>) {
+    let page = context.get_parts(offset, limit).await;
+    if let Some((parts, total)) = page {
+        callback.emit((offset, parts, total));
     }
 }
 
+pub async fn get_favorites_with_callback(callback: Callback<Vec<String>>) {
+    let favorites: Option<Vec<String>> = post_from_db(&format!("{}/api/favorites", PCPC_IP), ()).await.ok();
+    if let Some(favorites) = favorites {
+        callback.emit(favorites);
+    }
+}
+
+/// Emits favorites as stored on the server after the change
+pub async fn update_favorite_with_callback(part: String, favorite: bool, callback: Callback<Vec<String>>) {
+    let url = match favorite {
+        true => format!("{}/api/favorites/add", PCPC_IP),
+        false => format!("{}/api/favorites/remove", PCPC_IP),
+    };
+    let part = match part.parse() {
+        Ok(part) => part,
+        Err(_) => return,
+    };
+    let favorites: Option<Vec<String>> = post_from_db(&url, FavoriteProps { part }).await.ok();
+    if let Some(favorites) = favorites {
+        callback.emit(favorites);
+    }
+}
 
 impl AppContext {
     pub async fn get_part(&self, id: String) -> Option<Part> {
+        // Id may come from the address bar
         let json = GetPartProps {
-            id: Some(id),
+            id: Some(id.parse().ok()?),
             limit: 1,
+            ..Default::default()
         };
         
-        let db_part: Option<DBPart> = post_from_db(&format!("{}/api/part", PCPC_IP), json).await;
+        let db_part: Option<DBPart> = post_from_db(&format!("{}/api/part", PCPC_IP), json).await.ok();
 
         if let Some(db_part) = db_part {
-            let part: Part = db_part.into();
-            return Some(part);
+            let mut parts: Vec<Part> = vec![db_part.into()];
+            self.set_lowest_prices(&mut parts).await;
+            return parts.pop();
         }
 
         None
     }
 
-    pub async fn get_parts(&self, limit: u32) -> Vec<Part> {
-        let json = GetPartProps {
-            id: None,
-            limit,
+    /// Fills in current lowest price of parts, in one request for all of them
+    pub async fn set_lowest_prices(&self, parts: &mut [Part]) {
+        let json = GetLowestPricesProps {
+            parts: parts.iter().map(|x| x.id.clone()).collect(),
+            currency: Currency::default(),
         };
-        
-        let db_parts: Option<Vec<DBPart>> = post_from_db(&format!("{}/api/part", PCPC_IP), json).await;
 
-        if let Some(db_parts) = db_parts {
-            let parts: Vec<Part> = db_parts.iter().map(|x| Part::from(x.clone())).collect();
-            return parts;
+        let prices: Option<HashMap<String, Price>> = post_from_db(&format!("{}/api/price/lowest", PCPC_IP), json).await.ok();
+        if let Some(mut prices) = prices {
+            for part in parts.iter_mut() {
+                part.price = prices.remove(&part.id);
+            }
         }
+    }
+
+    /// Query of parts in selected category matching search term and filters
+    pub fn part_query(&self, offset: u32, limit: u32) -> GetPartProps {
+        let query = match self.search_term.is_empty() {
+            true => None,
+            false => Some(self.search_term.clone()),
+        };
 
-        Vec::new()
+        GetPartProps {
+            id: None,
+            limit,
+            offset,
+            category: Some(self.selected_category.clone()),
+            query,
+            filters: self.property_filters.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Labels of shown properties of selected category, in order of their definition
+    pub fn visible_properties(&self) -> Vec<String> {
+        property_fields(PartsCategory::from_string(&self.selected_category)).into_iter()
+            .map(|field| field.label.to_string())
+            .filter(|label| self.properties_order.get(label).copied().unwrap_or(false))
+            .collect()
+    }
+
+    /// Page of parts in selected category matching search term, with total number of matching parts
+    pub async fn get_parts(&self, offset: u32, limit: u32) -> Option<(Vec<Part>, u32)> {
+        let json = self.part_query(offset, limit);
+
+        let page: Option<PartsPage> = post_from_db(&format!("{}/api/part", PCPC_IP), json).await.ok();
+
+        let page = page?;
+        let mut parts: Vec<Part> = page.parts.into_iter().map(Part::from).collect();
+        self.set_lowest_prices(&mut parts).await;
+
+        Some((parts, page.total))
     }
 }
 
 pub struct App {
     app_context: Rc<AppContext>,
     mouse_event_selected: Option<HtmlDivElement>,
+    /// Last failed request, shown until it's dismissed
+    error: Option<ApiError>,
 }
 
 pub enum AppMessage {
@@ -92,6 +166,11 @@ pub enum AppMessage {
     SetFilterVisibility(bool),
     UpdateFavorite((String, bool)),
     UpdateSearchTerm(String),
+    SetPropertyFilters(Vec<PropertyFilter>),
+    SetSession(Option<LoginResponse>),
+    SetFavorites(Vec<String>),
+    ShowError(ApiError),
+    DismissError,
 }
 
 impl Component for App {
@@ -106,6 +185,9 @@ impl Component for App {
         let selected_category_callback = ctx.link().callback(move |selected_category| AppMessage::SetSelectedCategory(selected_category));
         let favorites_callback = ctx.link().callback(move |(id, favorite)| AppMessage::UpdateFavorite((id, favorite)));
         let search_term_callback = ctx.link().callback(move |search_term| AppMessage::UpdateSearchTerm(search_term));
+        let property_filters_callback = ctx.link().callback(AppMessage::SetPropertyFilters);
+        let session_callback = ctx.link().callback(AppMessage::SetSession);
+        set_error_callback(ctx.link().callback(AppMessage::ShowError));
 
         let mut properties_order: HashMap<String, bool> = HashMap::new();
         for category in PartsCategory::get_all_variats() {
@@ -113,6 +195,15 @@ impl Component for App {
             properties_order.extend(map);
         }
 
+        let user = stored_session().map(|x| x.user);
+        let favorites = match user {
+            Some(_) => {
+                spawn_local(get_favorites_with_callback(ctx.link().callback(AppMessage::SetFavorites)));
+                Vec::new()
+            },
+            None => stored_favorites(),
+        };
+
         let context = Rc::new(AppContext {
             content_page: ContentPage::Parts,
             content_page_callback,
@@ -124,19 +215,24 @@ impl Component for App {
             selected_category_callback,
             filter_visibility: true,
             filter_visibility_callback,
-            favorites: Vec::new(),
+            favorites,
             favorites_callback,
             search_term: "".to_string(),
             search_term_callback,
+            property_filters: Vec::new(),
+            property_filters_callback,
+            user,
+            session_callback,
         });
 
         Self { 
             app_context: context,
             mouse_event_selected: None,
+            error: None,
         }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         let mut app_context = Rc::make_mut(&mut self.app_context);
         match msg {
             AppMessage::ChangeContentPage(page) => app_context.content_page = page,
@@ -177,15 +273,46 @@ impl Component for App {
             },
             AppMessage::OrderPropertiesChange(properties_order) => app_context.properties_order = properties_order,
             AppMessage::SetFilterVisibility(filter_visibility) => app_context.filter_visibility = filter_visibility,
-            AppMessage::SetSelectedCategory(selected_category) => app_context.selected_category = selected_category,
+            AppMessage::SetSelectedCategory(selected_category) => {
+                // Properties differ between categories
+                app_context.property_filters.clear();
+                app_context.selected_category = selected_category;
+            },
             AppMessage::UpdateFavorite((id, favorite)) => {
+                app_context.favorites.retain(|x| *x != id);
                 if favorite {
-                    app_context.favorites.push(id);
-                } else {
-                    app_context.favorites.retain(|x| *x != id);
+                    app_context.favorites.push(id.clone());
+                }
+
+                match app_context.user {
+                    Some(_) => {
+                        let callback = ctx.link().callback(AppMessage::SetFavorites);
+                        spawn_local(update_favorite_with_callback(id, favorite, callback));
+                    },
+                    None => store_favorites(&app_context.favorites),
                 }
             },
+            AppMessage::SetFavorites(favorites) => app_context.favorites = favorites,
             AppMessage::UpdateSearchTerm(search_term) => app_context.search_term = search_term,
+            AppMessage::SetPropertyFilters(filters) => app_context.property_filters = filters,
+            AppMessage::SetSession(session) => {
+                store_session(session.as_ref());
+                app_context.user = session.map(|x| x.user);
+
+                // Favorites of the user replace those of the anonymous visitor
+                match app_context.user {
+                    Some(_) => spawn_local(get_favorites_with_callback(ctx.link().callback(AppMessage::SetFavorites))),
+                    None => app_context.favorites = stored_favorites(),
+                }
+            },
+            AppMessage::ShowError(error) => {
+                // Stored session was already removed, the user has to log in again
+                if let ApiError::Unauthorized { .. } = error {
+                    app_context.user = None;
+                }
+                self.error = Some(error);
+            },
+            AppMessage::DismissError => self.error = None,
         }
 
         true
@@ -208,6 +335,19 @@ impl Component for App {
                         onmousemove={callback}
                     >
                         <Header />
+                        if let Some(error) = &self.error {
+                            <div class={classes!("error-banner")}>
+                                <div>
+                                    <p>{error.message().to_string()}</p>
+                                    if error.fields().len() > 1 {
+                                        <ul>
+                                            { for error.fields().iter().map(|x| html! { <li>{format!("{}: {}", x.field, x.message)}</li> }) }
+                                        </ul>
+                                    }
+                                </div>
+                                <button onclick={ctx.link().callback(|_| AppMessage::DismissError)}>{"✕"}</button>
+                            </div>
+                        }
                         <Content />
                     </div>
                 </BrowserRouter>
@@ -222,12 +362,20 @@ pub enum AppRoute {
     Home,
     #[at("/parts")]
     Parts,
+    #[at("/parts/:id")]
+    Part { id: String },
     #[at("/comparison")]
     Comparison,
     #[at("/create")]
     Create,
     #[at("/favorites")]
     Favorites,
+    #[at("/login")]
+    Login,
+    #[at("/builds/:id")]
+    Build { id: String },
+    #[at("/suggest")]
+    Suggest,
     #[not_found]
     #[at("/404")]
     NotFound,
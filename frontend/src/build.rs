@@ -0,0 +1,232 @@
+use common::{build::{DBBuild, GetBuildProps, UpdateBuildProps, DBBuildProps}, DBPart, GetPartProps, compatibility::estimated_power, export::{ExportBuildProps, ExportFormat}};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::spawn_local;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::{app::PCPC_IP, connection::post_from_db, compatibility::Compatibility, export::{ExportButtons, download_export}, rating::Rating};
+
+pub struct BuildPage {
+    build: Option<DBBuild>,
+    /// False until the build was fetched, so "not found" isn't shown while loading
+    loaded: bool,
+    parts: Vec<DBPart>,
+    name: String,
+}
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct BuildPageProps {
+    pub id: String,
+}
+
+pub enum BuildPageMessage {
+    SetBuild(Option<Box<DBBuild>>),
+    SetParts(Vec<DBPart>),
+    UpdateName(String),
+    RemovePart(String),
+    Save,
+    Export(ExportFormat),
+}
+
+impl Component for BuildPage {
+    type Message = BuildPageMessage;
+    type Properties = BuildPageProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        Self::fetch_build(ctx);
+
+        Self {
+            build: None,
+            loaded: false,
+            parts: Vec::new(),
+            name: "".to_string(),
+        }
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
+        if ctx.props() != old_props {
+            self.loaded = false;
+            Self::fetch_build(ctx);
+        }
+
+        true
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            BuildPageMessage::SetBuild(build) => {
+                if let Some(build) = &build {
+                    self.name = build.name.clone();
+                    let callback = ctx.link().callback(BuildPageMessage::SetParts);
+                    spawn_local(get_parts(build.slots.ids(), callback));
+                }
+
+                self.build = build.map(|build| *build);
+                self.loaded = true;
+            },
+            BuildPageMessage::SetParts(parts) => self.parts = parts,
+            BuildPageMessage::UpdateName(name) => self.name = name,
+            BuildPageMessage::RemovePart(id) => {
+                if let Some(build) = &mut self.build {
+                    build.slots.remove(&id);
+                    self.parts.retain(|x| x.id != id);
+                    ctx.link().send_message(BuildPageMessage::Save);
+                }
+            },
+            BuildPageMessage::Save => {
+                if let Some(build) = &mut self.build {
+                    build.name = self.name.clone();
+                    let json = UpdateBuildProps {
+                        id: build.id.clone(),
+                        props: DBBuildProps {
+                            name: build.name.clone(),
+                            slots: build.slots.clone(),
+                        },
+                    };
+
+                    spawn_local(async move {
+                        let _: Option<DBBuild> = post_from_db(&format!("{}/api/build/update", PCPC_IP), json).await.ok();
+                    });
+                }
+            },
+            BuildPageMessage::Export(format) => {
+                let json = ExportBuildProps {
+                    id: ctx.props().id.clone(),
+                    format,
+                };
+                spawn_local(download_export(format!("{}/api/build/export", PCPC_IP), json, self.name.clone(), format));
+
+                return false;
+            },
+        }
+
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let build = match &self.build {
+            Some(build) => build,
+            None => {
+                return html! {
+                    <div class={classes!("comparison-empty")}>
+                        <h2>{ if self.loaded { "Build not found" } else { "Loading" } }</h2>
+                    </div>
+                };
+            },
+        };
+
+        let rows: Html = build.slots.slots().into_iter().map(|(slot, id)| {
+            let part = self.parts.iter().find(|x| x.id == id);
+            let on_remove = {
+                let id = id.clone();
+                ctx.link().callback(move |_| BuildPageMessage::RemovePart(id.clone()))
+            };
+
+            match part {
+                Some(part) => html! {
+                    <tr>
+                        <td><div>{slot}</div></td>
+                        <td><div>{&part.name}</div></td>
+                        <td><div>{&part.manufactuer}</div></td>
+                        <td><div><Rating rating={part.rating.get() as f32} /></div></td>
+                        <td>
+                            <div class={classes!("comparison-button")} onclick={on_remove}>
+                                <h5>{"Remove"}</h5>
+                            </div>
+                        </td>
+                    </tr>
+                },
+                None => html! {
+                    <tr>
+                        <td><div>{slot}</div></td>
+                        <td colspan="3"><div>{"Part not found"}</div></td>
+                        <td>
+                            <div class={classes!("comparison-button")} onclick={on_remove}>
+                                <h5>{"Remove"}</h5>
+                            </div>
+                        </td>
+                    </tr>
+                },
+            }
+        }).collect();
+
+        let average_rating = if self.parts.is_empty() {
+            0.0
+        } else {
+            self.parts.iter().map(|x| x.rating.get()).sum::<f64>() / self.parts.len() as f64
+        };
+
+        let oninput = ctx.link().callback(|event: InputEvent| {
+            let input: HtmlInputElement = event.target().unwrap().dyn_into().unwrap();
+            BuildPageMessage::UpdateName(input.value())
+        });
+        let on_save = ctx.link().callback(|_| BuildPageMessage::Save);
+
+        html! {
+            <div class={classes!("build")}>
+                <div class={classes!("build-header")}>
+                    <input type="text" {oninput} value={self.name.clone()} />
+                    <div class={classes!("comparison-button")} onclick={on_save}>
+                        <h5>{"Save"}</h5>
+                    </div>
+                    <ExportButtons callback={ctx.link().callback(BuildPageMessage::Export)} />
+                </div>
+                <Compatibility ids={build.slots.ids()} />
+                <table class={classes!("comparison-table")}>
+                    <tr>
+                        <th><div><h2>{"Slot"}</h2></div></th>
+                        <th><div><h2>{"Part"}</h2></div></th>
+                        <th><div><h2>{"Manufacturer"}</h2></div></th>
+                        <th><div><h2>{"Rating"}</h2></div></th>
+                        <th></th>
+                    </tr>
+                    {rows}
+                    <tr>
+                        <th><div><h2>{"Total"}</h2></div></th>
+                        <td><div>{format!("{} parts", self.parts.len())}</div></td>
+                        <td><div>{format!("~{}", estimated_power(&self.parts))}</div></td>
+                        <td><div><Rating rating={average_rating as f32} /></div></td>
+                        <td></td>
+                    </tr>
+                </table>
+            </div>
+        }
+    }
+}
+
+impl BuildPage {
+    fn fetch_build(ctx: &Context<Self>) {
+        let callback = ctx.link().callback(BuildPageMessage::SetBuild);
+        let json = GetBuildProps {
+            id: Some(ctx.props().id.clone()),
+            limit: 1,
+        };
+
+        spawn_local(async move {
+            let build: Option<DBBuild> = post_from_db(&format!("{}/api/build", PCPC_IP), json).await.ok();
+            callback.emit(build.map(Box::new));
+        });
+    }
+}
+
+async fn get_parts(ids: Vec<String>, callback: Callback<Vec<DBPart>>) {
+    let mut parts: Vec<DBPart> = Vec::new();
+    for id in ids {
+        let id = match id.parse() {
+            Ok(id) => id,
+            Err(_) => continue,
+        };
+        let json = GetPartProps {
+            id: Some(id),
+            limit: 1,
+            ..Default::default()
+        };
+
+        let part: Option<DBPart> = post_from_db(&format!("{}/api/part", PCPC_IP), json).await.ok();
+        if let Some(part) = part {
+            parts.push(part);
+        }
+    }
+
+    callback.emit(parts);
+}
@@ -1,10 +1,11 @@
 use std::{rc::Rc, collections::HashMap};
 
-use common::PartsCategory;
+use common::{build::{BuildSlots, DBBuildProps, DBBuild}, export::{ExportFormat, Table, format_price, format_property}};
 use wasm_bindgen_futures::spawn_local;
 use yew::prelude::*;
+use yew_router::prelude::*;
 
-use crate::{app::AppContext, parts::{Part, format_property}, side_panel::{SidePanel, SidePanelConfig}};
+use crate::{app::{AppContext, AppRoute, PCPC_IP}, connection::{post_from_db, download}, compatibility::Compatibility, export::ExportButtons, parts::Part, side_panel::{SidePanel, SidePanelConfig}};
 
 pub struct Comparison {
     comparison_context: Rc<ComparisonContext>,
@@ -22,6 +23,8 @@ pub enum ComparisonMessage {
     ContextChanged(Rc<AppContext>),
     PopulateParts(Vec<Part>),
     ChangeConfig(SidePanelConfig),
+    SaveBuild,
+    Export(ExportFormat),
 }
 
 impl Component for Comparison {
@@ -51,7 +54,7 @@ impl Component for Comparison {
         }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             ComparisonMessage::ContextChanged(context) => self.context = context,
             ComparisonMessage::PopulateParts(parts) => {
@@ -68,6 +71,36 @@ impl Component for Comparison {
 
                 self.config = Some(new_config);
             },
+            ComparisonMessage::SaveBuild => {
+                let mut slots = BuildSlots::default();
+                for part in &self.comparison_context.parts {
+                    slots.insert(part.id.clone(), &part.category_properties);
+                }
+
+                let json = DBBuildProps {
+                    name: "New build".to_string(),
+                    slots,
+                };
+                let navigator = ctx.link().navigator();
+                spawn_local(async move {
+                    let build: Option<DBBuild> = post_from_db(&format!("{}/api/build/create", PCPC_IP), json).await.ok();
+                    if let (Some(build), Some(navigator)) = (build, navigator) {
+                        navigator.push(&AppRoute::Build { id: build.id });
+                    }
+                });
+
+                return false;
+            },
+            ComparisonMessage::Export(format) => {
+                let parts: Vec<Part> = self.comparison_context.parts.iter()
+                    .filter(|x| x.category_properties.to_string() == self.context.selected_category)
+                    .cloned()
+                    .collect();
+                let table = comparison_table(&parts, &self.context.visible_properties());
+                download(&format!("comparison.{}", format.extension()), format.mime(), &table.render(format));
+
+                return false;
+            },
         }
 
         true
@@ -99,8 +132,11 @@ impl Component for Comparison {
                             if side_panel {
                                 <SidePanel config={config} />
                             }
-                            <div class={classes!("comparison-empty")}>
-                                <h2>{"No parts in selected category!"}</h2> 
+                            <div class={classes!("comparison-table-container")}>
+                                <Compatibility ids={self.context.selected_parts.clone()} />
+                                <div class={classes!("comparison-empty")}>
+                                    <h2>{"No parts in selected category!"}</h2> 
+                                </div>
                             </div>
                         </div>
                     </ContextProvider<Rc<ComparisonContext>>>
@@ -117,21 +153,14 @@ impl Component for Comparison {
                 });
             }
 
-            let mut properties: Vec<Html> = Vec::new();
-            let selected_category = PartsCategory::from_string(&self.context.selected_category);
-            let template_part = Part {
-                category_properties: selected_category,
-                ..Default::default()
-            };
-            let template_properties = template_part.get_properties_as_map().unwrap_or(HashMap::new());
-            for (property, visible) in &self.context.properties_order {
-                if *visible && template_properties.contains_key(property) {
-                    properties.push(get_property_from_parts(&comparison_parts, property.to_string()));
-                }
-            }
+            let properties: Html = self.context.visible_properties().into_iter()
+                .map(|property| get_property_from_parts(&comparison_parts, property))
+                .collect();
 
             let tabs_callback = ctx.link().callback(|_| ComparisonMessage::ChangeConfig(SidePanelConfig::Tabs));
             let settings_callback = ctx.link().callback(|_| ComparisonMessage::ChangeConfig(SidePanelConfig::Settings));
+            let save_build_callback = ctx.link().callback(|_| ComparisonMessage::SaveBuild);
+            let export_callback = ctx.link().callback(ComparisonMessage::Export);
 
             return html! {
                 <ContextProvider<Rc<ComparisonContext>> context={comparison_context}>
@@ -140,6 +169,8 @@ impl Component for Comparison {
                             <SidePanel config={config} />
                         }
                         <div class={classes!("comparison-table-container")}>
+                            <Compatibility ids={self.context.selected_parts.clone()} />
+                            <ExportButtons callback={export_callback} />
                             <table class={classes!("comparison-table")}>
                                 <tr>
                                     <th class={classes!("buttons")}>
@@ -153,9 +184,15 @@ impl Component for Comparison {
                                             class={classes!("comparison-button")}>
                                             <h5>{"Settings"}</h5>
                                         </div>
+                                        <div 
+                                            onclick={save_build_callback}
+                                            class={classes!("comparison-button")}>
+                                            <h5>{"Save build"}</h5>
+                                        </div>
                                     </th>
                                     {part_names}
                                 </tr>
+                                {get_price_from_parts(&comparison_parts)}
                                 {properties}
                             </table>
                         </div>
@@ -172,7 +209,7 @@ impl Component for Comparison {
     }
 }
 
-fn get_property_from_parts(parts: &Vec<Part>, property: String) -> Html {
+fn get_property_from_parts(parts: &[Part], property: String) -> Html {
     let mut part_properties: Vec<Html> = Vec::new();
     part_properties.push(html! {
         <th>
@@ -182,58 +219,78 @@ fn get_property_from_parts(parts: &Vec<Part>, property: String) -> Html {
         </th>
     });
 
-    for part in parts {
-        let properties = part.get_properties_as_map();
-        if let Ok(properties) = properties {
-            let first_property = properties.get(&property);
-            if let Some(first_property) = first_property {
-                let first_property = format_property(first_property.to_owned());
-
-                let mut different = false;
-                for second_part in parts {
-                    let properties = second_part.get_properties_as_map();
-                    if let Ok(properties) = properties {
-                        let second_property = properties.get(&property);
-                        if let Some(second_property) = second_property {
-                            let second_property = format_property(second_property.to_owned());
-
-                            if second_property != first_property {
-                                different = true;
-                                break;
-                            }
-                        }
-                    }
-                }
+    let values: Vec<Option<String>> = parts.iter()
+        .map(|part| part.get_properties_as_map().remove(&property).map(format_property))
+        .collect();
+    for value in values.iter().flatten() {
+        let different = values.iter().flatten().any(|x| x != value);
+
+        part_properties.push(html! {
+            <td class={classes!(different.then_some("different"))}>
+                <div>
+                    {value}
+                </div>
+            </td>
+        });
+    }
 
-                let result = match different {
-                    true => {
-                        html! {
-                            <td class={classes!("different")}>
-                                <div>
-                                    {&first_property}
-                                </div>
-                            </td>
-                        }
-                    },
-                    false => {
-                        html! {
-                            <td>
-                                <div>
-                                    {&first_property}
-                                </div>
-                            </td>
-                        }
-                    },
-                };
+    html! {
+        <tr>
+            {part_properties}
+        </tr>
+    }
+}
 
-                part_properties.push(result);
-            }
-        }
+/// Same rows as the comparison table shows, a property per row and a part per column
+fn comparison_table(parts: &[Part], properties: &[String]) -> Table {
+    let mut columns = vec!["Property".to_string()];
+    columns.extend(parts.iter().map(|x| x.name.clone()));
+
+    let mut rows = vec![];
+    let mut price_row = vec!["Price".to_string()];
+    price_row.extend(parts.iter().map(|x| format_price(x.price.as_ref())));
+    rows.push(price_row);
+
+    let part_properties: Vec<HashMap<String, String>> = parts.iter()
+        .map(|x| x.get_properties_as_map())
+        .collect();
+    for property in properties {
+        let mut row = vec![property.clone()];
+        row.extend(part_properties.iter().map(|x| format_property(x.get(property).cloned().unwrap_or_default())));
+        rows.push(row);
     }
 
+    Table {
+        columns,
+        rows,
+    }
+}
+
+fn get_price_from_parts(parts: &[Part]) -> Html {
+    let lowest = parts.iter().filter_map(|x| x.price.as_ref().map(|price| price.amount)).min();
+
+    let prices: Vec<Html> = parts.iter().map(|part| {
+        // Cheapest part stands out, unless all cost the same
+        let cheapest = part.price.as_ref().map(|x| Some(x.amount) == lowest).unwrap_or(false) &&
+            parts.iter().any(|x| x.price.as_ref().map(|price| price.amount) != lowest);
+
+        html! {
+            <td class={classes!(cheapest.then_some("different"))}>
+                <div>
+                    {format_price(part.price.as_ref())}
+                </div>
+            </td>
+        }
+    }).collect();
+
     html! {
         <tr>
-            {part_properties}
+            <th>
+                <div>
+                    <h2>{"Price"}</h2>
+                </div>
+            </th>
+            {prices}
         </tr>
     }
 }
@@ -0,0 +1,121 @@
+use common::{compatibility::{CompatibilityIssue, Severity}, error::ApiError, GetCompatibilityProps};
+use wasm_bindgen_futures::spawn_local;
+use yew::prelude::*;
+
+use crate::{app::PCPC_IP, connection::try_post_from_db};
+
+pub struct Compatibility {
+    /// None until the response for current ids arrives
+    issues: Option<Result<Vec<CompatibilityIssue>, ApiError>>,
+}
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct CompatibilityProps {
+    pub ids: Vec<String>,
+}
+
+pub enum CompatibilityMessage {
+    /// Ids the check was requested for and its result
+    SetIssues(Vec<String>, Result<Vec<CompatibilityIssue>, ApiError>),
+}
+
+impl Component for Compatibility {
+    type Message = CompatibilityMessage;
+    type Properties = CompatibilityProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        Self::fetch_issues(ctx);
+
+        Self {
+            issues: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            CompatibilityMessage::SetIssues(ids, issues) => {
+                // Response to a check of parts that are no longer selected
+                if ids != ctx.props().ids {
+                    return false;
+                }
+
+                self.issues = Some(issues);
+            },
+        }
+
+        true
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
+        if ctx.props() != old_props {
+            self.issues = None;
+            Self::fetch_issues(ctx);
+        }
+
+        true
+    }
+
+    fn view(&self, _ctx: &Context<Self>) -> Html {
+        let issues = match &self.issues {
+            None => {
+                return html! {
+                    <div class={classes!("compatibility")}>
+                        <h3>{"Checking compatibility"}</h3>
+                    </div>
+                };
+            },
+            Some(Err(error)) => {
+                return html! {
+                    <div class={classes!("compatibility")}>
+                        <h3>{"Compatibility couldn't be checked"}</h3>
+                        <p class={classes!("error")}>{error.message().to_string()}</p>
+                    </div>
+                };
+            },
+            Some(Ok(issues)) => issues,
+        };
+
+        if issues.is_empty() {
+            return html! {
+                <div class={classes!("compatibility")}>
+                    <h3>{"All selected parts are compatible"}</h3>
+                </div>
+            };
+        }
+
+        let issues: Html = issues.iter().map(|issue| {
+            let class = match issue.severity() {
+                Severity::Warning => "warning",
+                Severity::Error => "error",
+            };
+
+            html! {
+                <li class={classes!(class)}>{issue.to_string()}</li>
+            }
+        }).collect();
+
+        html! {
+            <div class={classes!("compatibility")}>
+                <h3>{"Compatibility issues"}</h3>
+                <ul>
+                    {issues}
+                </ul>
+            </div>
+        }
+    }
+}
+
+impl Compatibility {
+    fn fetch_issues(ctx: &Context<Self>) {
+        let ids = ctx.props().ids.clone();
+        let callback = ctx.link().callback(move |(ids, issues)| CompatibilityMessage::SetIssues(ids, issues));
+        let json = GetCompatibilityProps {
+            ids: ids.clone(),
+        };
+
+        spawn_local(async move {
+            let issues = try_post_from_db(&format!("{}/api/compatibility", PCPC_IP), json).await;
+            callback.emit((ids, issues));
+        });
+    }
+}
@@ -1,22 +1,192 @@
-use gloo_net::http::Request;
+use std::cell::RefCell;
+
+use common::{error::ApiError, user::LoginResponse};
+use gloo_net::http::{Request, Response};
+use gloo_timers::callback::Timeout;
 use serde::{Serialize, de::DeserializeOwned};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Blob, BlobPropertyBag, HtmlAnchorElement, Url};
+use yew::Callback;
 
-pub async fn post_from_db<T, W>(url: &str, json: T) -> Option<W> 
-where T: Serialize,
-      W: DeserializeOwned,
-{
-    let response = Request::post(url)
+/// Key of the session in local storage, so it survives reloads
+const SESSION_KEY: &str = "pcpc_session";
+/// Key of favorites of anonymous users in local storage
+const FAVORITES_KEY: &str = "pcpc_favorites";
+/// Milliseconds a downloaded file stays reachable, browsers may start the download after the click returns
+const DOWNLOAD_URL_LIFETIME: u32 = 60_000;
+
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+fn stored<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let value = local_storage()?.get_item(key).ok()??;
+    serde_json::from_str(&value).ok()
+}
+
+/// None removes stored value
+fn store<T: Serialize + ?Sized>(key: &str, value: Option<&T>) {
+    let storage = match local_storage() {
+        Some(storage) => storage,
+        None => return,
+    };
+
+    let _ = match value.and_then(|x| serde_json::to_string(x).ok()) {
+        Some(value) => storage.set_item(key, &value),
+        None => storage.remove_item(key),
+    };
+}
+
+pub fn stored_session() -> Option<LoginResponse> {
+    stored(SESSION_KEY)
+}
+
+/// None removes stored session
+pub fn store_session(session: Option<&LoginResponse>) {
+    store(SESSION_KEY, session);
+}
+
+pub fn stored_favorites() -> Vec<String> {
+    stored(FAVORITES_KEY).unwrap_or_default()
+}
+
+pub fn store_favorites(favorites: &[String]) {
+    store(FAVORITES_KEY, Some(favorites));
+}
+
+fn with_session(request: Request) -> Request {
+    match stored_session() {
+        Some(session) => request.header("Authorization", &format!("Bearer {}", session.token)),
+        None => request,
+    }
+}
+
+/// POST request with token of stored session, if there is one
+pub fn authorized_post(url: &str) -> Request {
+    with_session(Request::post(url))
+}
+
+thread_local! {
+    /// Shows errors of requests to the user, set once the app is created
+    static ERROR_CALLBACK: RefCell<Option<Callback<ApiError>>> = const { RefCell::new(None) };
+}
+
+pub fn set_error_callback(callback: Callback<ApiError>) {
+    ERROR_CALLBACK.with(|x| *x.borrow_mut() = Some(callback));
+}
+
+fn report_error(error: &ApiError) {
+    ERROR_CALLBACK.with(|x| {
+        if let Some(callback) = x.borrow().as_ref() {
+            callback.emit(error.clone());
+        }
+    });
+}
+
+/// Sends JSON to the URL, errors of the server are decoded from their JSON body
+async fn send<T: Serialize>(url: &str, json: T) -> Result<Response, ApiError> {
+    let request = authorized_post(url)
         .json(&json)
-        .unwrap()
+        .map_err(|error| ApiError::Internal { message: error.to_string() })?;
+
+    send_request(request).await
+}
+
+async fn send_request(request: Request) -> Result<Response, ApiError> {
+    let response = request
         .send()
-        .await;
+        .await
+        .map_err(|_| ApiError::Internal { message: "Server can't be reached".into() })?;
 
-    if let Ok(response) = response {
-        if response.ok() {
-            let get: W = response.json().await.unwrap();
-            return Some(get)
-        }
+    if response.ok() {
+        return Ok(response);
     }
 
-    None
+    // Session expired or was ended elsewhere
+    if response.status() == 401 {
+        store_session(None);
+    }
+
+    let status = response.status();
+    Err(match response.json::<ApiError>().await {
+        Ok(error) => error,
+        Err(_) => ApiError::from_status(status, response.status_text()),
+    })
+}
+
+async fn read_json<W: DeserializeOwned>(response: Response) -> Result<W, ApiError> {
+    response
+        .json()
+        .await
+        .map_err(|error| ApiError::Internal { message: format!("Unexpected response: {}", error) })
+}
+
+/// Like [`post_from_db`], but errors are left for the caller to show
+pub async fn try_post_from_db<T, W>(url: &str, json: T) -> Result<W, ApiError>
+where T: Serialize,
+      W: DeserializeOwned,
+{
+    read_json(send(url, json).await?).await
+}
+
+async fn request_json<W: DeserializeOwned>(request: Request) -> Result<W, ApiError> {
+    let result = match send_request(with_session(request)).await {
+        Ok(response) => read_json(response).await,
+        Err(error) => Err(error),
+    };
+
+    result.inspect_err(report_error)
+}
+
+/// GET request with token of stored session, errors are shown to the user and returned
+pub async fn get_from_db<W: DeserializeOwned>(url: &str) -> Result<W, ApiError> {
+    request_json(Request::get(url)).await
+}
+
+/// DELETE request with token of stored session, errors are shown to the user and returned
+pub async fn delete_from_db<W: DeserializeOwned>(url: &str) -> Result<W, ApiError> {
+    request_json(Request::delete(url)).await
+}
+
+/// Errors are shown to the user and returned
+pub async fn post_from_db<T, W>(url: &str, json: T) -> Result<W, ApiError>
+where T: Serialize,
+      W: DeserializeOwned,
+{
+    try_post_from_db(url, json).await.inspect_err(report_error)
+}
+
+/// Body of the response as text, for files rendered by the server. Errors are shown to the user.
+pub async fn post_text_from_db<T>(url: &str, json: T) -> Result<String, ApiError>
+where T: Serialize,
+{
+    let result = match send(url, json).await {
+        Ok(response) => response.text().await.map_err(|error| ApiError::Internal { message: error.to_string() }),
+        Err(error) => Err(error),
+    };
+
+    result.inspect_err(report_error)
+}
+
+/// Saves content as a file, through a link to it that is clicked right away
+pub fn download(file_name: &str, mime: &str, content: &str) {
+    let _ = (|| -> Option<()> {
+        let document = web_sys::window()?.document()?;
+
+        let options = BlobPropertyBag::new();
+        options.set_type(mime);
+        let parts = js_sys::Array::of1(&JsValue::from_str(content));
+        let blob = Blob::new_with_str_sequence_and_options(&parts, &options).ok()?;
+        let url = Url::create_object_url_with_blob(&blob).ok()?;
+
+        let link: HtmlAnchorElement = document.create_element("a").ok()?.dyn_into().ok()?;
+        link.set_href(&url);
+        link.set_download(file_name);
+        link.click();
+
+        Timeout::new(DOWNLOAD_URL_LIFETIME, move || {
+            Url::revoke_object_url(&url).ok();
+        }).forget();
+        Some(())
+    })();
 }
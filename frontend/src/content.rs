@@ -3,7 +3,7 @@ use std::rc::Rc;
 use yew::prelude::*;
 use yew_router::prelude::*;
 
-use crate::{parts::Parts, app::{AppContext, AppRoute}, comparison::Comparison, home::Home, create::CreatePart, favorites::Favorites};
+use crate::{parts::Parts, app::{AppContext, AppRoute}, comparison::Comparison, home::Home, create::CreatePart, favorites::Favorites, build::BuildPage, login::Login, part_page::PartPage, suggest::Suggest};
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum ContentPage {
@@ -55,9 +55,13 @@ fn switch(routes: AppRoute) -> Html {
     match routes {
         AppRoute::Home => html! { <Home /> },
         AppRoute::Parts => html! { <Parts /> },
+        AppRoute::Part { id } => html! { <PartPage id={id} /> },
         AppRoute::Comparison => html! { <Comparison /> },
         AppRoute::Create => html! { <CreatePart /> },
         AppRoute::Favorites => html! { <Favorites /> },
+        AppRoute::Login => html! { <Login /> },
+        AppRoute::Build { id } => html! { <BuildPage id={id} /> },
+        AppRoute::Suggest => html! { <Suggest /> },
         AppRoute::NotFound => html! { <h1>{ "404" }</h1> },
     }
 }
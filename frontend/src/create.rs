@@ -1,125 +1,92 @@
-use std::{collections::HashMap, rc::Rc};
+use std::collections::HashMap;
 
-use anyhow::bail;
-use common::{DBPartProps, traits::PartProperties, PartsCategory};
-use gloo_net::http::Request;
-use serde_json::{Value, Map};
+use common::{DBPart, DBPartProps, PartsCategory, error::FieldError, traits::{Field, FieldType, PartProperties}, validation::validate_part};
 use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::spawn_local;
-use yew::{Component, html, classes, Callback, Properties, Html, ContextHandle};
-use web_sys::{Event, InputEvent, HtmlInputElement, HtmlSelectElement, RequestCredentials};
+use yew::{Component, html, classes, Callback, Properties, Html};
+use web_sys::{Event, InputEvent, HtmlInputElement, HtmlSelectElement};
 
-use crate::app::{AppContext, PCPC_IP};
+use crate::{app::PCPC_IP, connection::post_from_db};
 
 pub struct CreatePart {
-    context: Rc<AppContext>,
-    _listener: ContextHandle<Rc<AppContext>>,
-    part: HashMap<String, String>,
+    /// Text entered for each field, by key
+    inputs: HashMap<String, String>,
     selected_category: PartsCategory,
+    /// Fields rejected on last submit
+    errors: Vec<FieldError>,
 }
 
 pub enum CreatePartMessage {
-    ContextChanged(Rc<AppContext>),
     Update(String, String),
     SetSelectedCategory(PartsCategory),
+    Submit,
+    SetErrors(Vec<FieldError>),
 }
 
 impl Component for CreatePart {
     type Message = CreatePartMessage;
     type Properties = ();
 
-    fn create(ctx: &yew::Context<Self>) -> Self {
-        let (context, _listener) = ctx
-            .link()
-            .context::<Rc<AppContext>>(ctx.link().callback(CreatePartMessage::ContextChanged))
-            .unwrap();
-
-        let default_part = DBPartProps::default();
-        let mut map = part_props_get_properties_as_map(default_part).unwrap();
-        map.remove("Category");
-
-        Self {
-            context,
-            _listener,
-            part: map,
+    fn create(_ctx: &yew::Context<Self>) -> Self {
+        let mut create_part = Self {
+            inputs: HashMap::new(),
             selected_category: PartsCategory::default(),
-        }
+            errors: Vec::new(),
+        };
+        create_part.fill_inputs();
+
+        create_part
     }
 
-    fn update(&mut self, _: &yew::Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &yew::Context<Self>, msg: Self::Message) -> bool {
         match msg {
-            CreatePartMessage::Update(key, value) => { self.part.insert(key, value); },
-            CreatePartMessage::ContextChanged(context) => self.context = context,
-            CreatePartMessage::SetSelectedCategory(category) => { 
-                let default_part = DBPartProps {
-                    category: category.clone(),
-                    ..Default::default()
-                };
-                let mut default_map = part_props_get_properties_as_map(default_part).unwrap();
-                default_map.remove("Category");
-
-                let part = self.part.clone();
-                let keys = part.keys().clone();
-                for key in keys {
-                    if !default_map.contains_key(key) {
-                        self.part.remove(key);
-                    }
-                }
-
-                for (key, value) in default_map {
-                    if !self.part.contains_key(&key) {
-                        self.part.insert(key, value);
-                    }
+            CreatePartMessage::Update(key, value) => { self.inputs.insert(key, value); },
+            CreatePartMessage::SetSelectedCategory(category) => {
+                self.selected_category = category;
+                self.fill_inputs();
+            },
+            CreatePartMessage::Submit => {
+                match self.part_props() {
+                    Ok(json) => {
+                        self.errors.clear();
+                        let callback = ctx.link().callback(CreatePartMessage::SetErrors);
+                        spawn_local(async move {
+                            let result = post_from_db::<_, DBPart>(&format!("{}/api/part/create", PCPC_IP), json).await;
+                            if let Err(error) = result {
+                                callback.emit(error.fields().to_vec());
+                            }
+                        });
+                    },
+                    Err(errors) => self.errors = errors,
                 }
-
-                self.selected_category = category 
             },
+            CreatePartMessage::SetErrors(errors) => self.errors = errors,
         }
 
         true
     }
 
     fn view(&self, ctx: &yew::Context<Self>) -> yew::Html {
-        let onclick = {
-            let map = self.part.clone();
-            let selected_category = self.selected_category.clone();
-
-            Callback::from(move |_| {
-                let json = get_json(&map, &selected_category).unwrap();
-                spawn_local(async move {
-                    let json = json.to_owned();
-                    Request::post(&format!("{}/api/part/create", PCPC_IP))
-                        .credentials(RequestCredentials::Include)
-                        .json(&json)
-                        .unwrap()
-                        .send()
-                        .await
-                        .unwrap();
-                });
-            }
-        )};
+        let onclick = ctx.link().callback(|_| CreatePartMessage::Submit);
 
-        let callback = ctx.link().callback(move |(name, value): (String, String)| {
-            CreatePartMessage::Update(name, value)
+        let callback = ctx.link().callback(move |(key, value): (String, String)| {
+            CreatePartMessage::Update(key, value)
         });
 
-        let mut inputs: Vec<Html> = Vec::new();
-        let mut unused = self.part.clone();
-        for key in self.context.properties_order.keys() {
-            let value = self.part.get(key);
-            unused.remove(key);
-            if let Some(value) = value {
-                inputs.push(html! {
-                    <PropertyInput callback={callback.clone()} name={key.clone()} value={value.clone()} />
-                });
+        let inputs: Html = self.template().fields().into_iter().filter(|field| !field.computed).map(|field| {
+            let error = self.errors.iter()
+                .find(|x| x.field == field.key)
+                .map(|x| x.message.clone());
+
+            html! {
+                <PropertyInput
+                    callback={callback.clone()}
+                    field={field}
+                    value={self.inputs.get(field.key).cloned().unwrap_or_default()}
+                    error={error}
+                />
             }
-        }
-
-        for (key, value) in unused {
-            inputs.push(html! {
-                <PropertyInput callback={callback.clone()} name={key.clone()} value={value.clone()} />
-            });
-        }
+        }).collect();
 
         let mut categories_html: Vec<Html> = Vec::new();
         for category in PartsCategory::get_all_variats() {
@@ -130,7 +97,7 @@ impl Component for CreatePart {
 
                 continue;
             }
-        
+
             categories_html.push(html! {
                 <option value={category.clone()}>{category.clone()}</option>
             });
@@ -165,13 +132,60 @@ impl Component for CreatePart {
     }
 }
 
+impl CreatePart {
+    /// New part of the selected category
+    fn template(&self) -> DBPartProps {
+        DBPartProps {
+            category: self.selected_category.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Keeps text of fields the selected category still has, the rest starts with default values
+    fn fill_inputs(&mut self) {
+        let template = self.template();
+        let fields = template.fields();
+
+        self.inputs.retain(|key, _| fields.iter().any(|x| x.key == key));
+        for field in fields {
+            if !self.inputs.contains_key(field.key) {
+                self.inputs.insert(field.key.to_string(), template.display(field.key).unwrap_or_default());
+            }
+        }
+        self.errors.clear();
+    }
+
+    /// Part read from entered text, or errors of all fields that couldn't be read or break rules of parts.
+    /// Computed fields keep their defaults.
+    fn part_props(&self) -> Result<DBPartProps, Vec<FieldError>> {
+        let mut props = self.template();
+        let mut errors: Vec<FieldError> = props.fields().into_iter()
+            .filter(|field| !field.computed)
+            .filter_map(|field| props.set_input(field.key, self.inputs.get(field.key)?).err())
+            .collect();
+
+        // Fields that couldn't be read are reported once
+        if let Err(rule_errors) = validate_part(&props) {
+            let unread: Vec<String> = errors.iter().map(|x| x.field.clone()).collect();
+            errors.extend(rule_errors.into_iter().filter(|x| !unread.contains(&x.field)));
+        }
+
+        match errors.is_empty() {
+            true => Ok(props),
+            false => Err(errors),
+        }
+    }
+}
+
 pub struct PropertyInput;
 
 #[derive(Properties, Clone, PartialEq)]
 pub struct PropertyInputProps {
+    /// Emits key of the field with entered text
     callback: Callback<(String, String)>,
-    name: String,
+    field: &'static Field,
     value: String,
+    error: Option<String>,
 }
 
 impl Component for PropertyInput {
@@ -184,181 +198,56 @@ impl Component for PropertyInput {
 
     fn view(&self, ctx: &yew::Context<Self>) -> yew::Html {
         let props = ctx.props();
-        let callback = props.callback.clone();
-        let oninput = { 
-            let name = props.name.clone();
+        let field = props.field;
+        let oninput = {
+            let callback = props.callback.clone();
             Callback::from(move |event: InputEvent| {
                 let event: Event = event.dyn_into().unwrap();
                 let event_target = event.target().unwrap();
                 let html_element: HtmlInputElement = event_target.dyn_into().unwrap();
-                callback.emit((name.clone(), html_element.value()));
+                callback.emit((field.key.to_string(), html_element.value()));
             }
         )};
+        let onchange = {
+            let callback = props.callback.clone();
+            Callback::from(move |event: Event| {
+                let html_element: HtmlSelectElement = event.target().unwrap().dyn_into().unwrap();
+                callback.emit((field.key.to_string(), html_element.value()));
+            })
+        };
+
+        let options: Vec<(&str, &str)> = match field.field_type {
+            FieldType::Boolean => vec![("true", "Yes"), ("false", "No")],
+            _ => field.options.iter().map(|x| (*x, *x)).collect(),
+        };
+        let input = match options.is_empty() {
+            true => html! {
+                <input type="text" {oninput} value={props.value.clone()} placeholder={field.unit.unwrap_or_default()} />
+            },
+            false => html! {
+                <select {onchange}>
+                    if field.field_type != FieldType::Boolean {
+                        <option value="" selected={props.value.is_empty()}></option>
+                    }
+                    {for options.into_iter().map(|(value, label)| html! {
+                        <option value={value} selected={props.value == value}>{label}</option>
+                    })}
+                </select>
+            },
+        };
+        let label = match field.required {
+            true => format!("{} *", field.label),
+            false => field.label.to_string(),
+        };
 
         html! {
             <div class={classes!("property-input")}>
-                <p>{&props.name}</p>
-                <input type="text" {oninput} value={props.value.clone()}/>
+                <p>{label}</p>
+                {input}
+                if let Some(error) = &props.error {
+                    <p class={classes!("field-error")}>{error}</p>
+                }
             </div>
         }
     }
 }
-
-fn get_json(part: &HashMap<String, String>, selected_category: &PartsCategory) -> anyhow::Result<DBPartProps> {
-    let mut result = "{".to_string();
-    let default_part = DBPartProps::default();
-    let part_value = serde_json::to_value(default_part)?;
-    let selected_category = selected_category.clone();
-    let default_category = selected_category.clone();
-    let category_value = serde_json::to_value(default_category)?;
-
-    let mut stored_map = part.clone();
-    for (old_key, value) in stored_map.clone() {
-        let mut key = old_key.clone();
-        let mut chars: Vec<char> = key.chars().collect();
-        let lowercase_char = chars[0].to_lowercase().nth(0);
-        if let Some(lowercase_char) = lowercase_char {
-            chars[0] = lowercase_char;
-            key = chars.into_iter().collect();
-        }
-        key = key.replace(" ", "_");
-        
-        let value = value.trim();
-
-        stored_map.remove(&old_key);
-        stored_map.insert(key, value.to_string());
-    }
-
-
-    match part_value {
-        serde_json::Value::Object(mut part_map) => {
-            let mut category_map: Map<String, Value>;
-            match category_value {
-                serde_json::Value::String(_) => {
-                    category_map = Map::default();
-                }
-                serde_json::Value::Object(object) => {
-                    category_map = object;
-                },
-                _ => bail!("No way"),
-            }
-
-            let first_key: Vec<&String> = category_map.keys().collect();
-            if !first_key.is_empty() {
-                let first = first_key.first();
-                if let Some(first) = first {
-                    let value = category_map.get(*first);
-                    if let Some(value) = value {
-                        match value {
-                            serde_json::Value::Object(object) => category_map = object.clone(),
-                            _ => bail!("Unexpected error on category"),
-                        }
-                    }
-                }
-            }
-
-            part_map.remove("category");
-
-            let mut index = 0;
-            for map in [part_map, category_map] {
-                let mut map_index = 0;
-                let map_len = map.len();
-
-                for (key, value) in map {
-                    let stored_value = stored_map.get(&key);
-
-                    if let Some(stored_value) = stored_value {
-                        result.push_str(&format!("\"{}\":", key));
-                        
-                        match value {
-                            serde_json::Value::Bool(_) => {
-                                let bool_value: Result<bool, _> = stored_value.parse();
-                                
-                                if let Ok(bool_value) = bool_value {
-                                    result.push_str(&bool_value.to_string());
-                                } else {
-                                    bail!("Property {}, has wrong value! Expected bool", &key);
-                                }
-                            },
-                            serde_json::Value::Number(value) => {
-                                if value.is_u64() {
-                                    let u64_value: Result<u64, _> = stored_value.parse();
-
-                                    if let Ok(u64_value) = u64_value {
-                                        result.push_str(&u64_value.to_string());
-                                    } else {
-                                        bail!("Property {}, has wrong value! Expected u64", &key);
-                                    }
-                                } else if value.is_i64() {
-                                    let i64_value: Result<i64, _> = stored_value.parse();
-                                    
-                                    if let Ok(i64_value) = i64_value {
-                                        result.push_str(&i64_value.to_string());
-                                    } else {
-                                        bail!("Property {}, has wrong value! Expected i64", &key);
-                                    }
-                                } else {
-                                    let f64_value: Result<f64, _> = stored_value.parse();
-                                    
-                                    if let Ok(f64_value) = f64_value {
-                                        result.push_str(&f64_value.to_string());
-                                    } else {
-                                        bail!("Property {}, has wrong value! Expected f64", &key);
-                                    }
-                                }
-                            },
-                            serde_json::Value::String(_) => {
-                                result.push_str(&format!("\"{}\"", stored_value.as_str()));
-                            },
-                            _ => bail!("What"),
-                        }
-
-                        if map_index != map_len - 1 {
-                            result.push_str(",");
-                        }
-                    }
-
-                    map_index += 1;
-                }
-
-                if index == 0 {
-                    let string_category = selected_category.to_string();
-                    if selected_category == PartsCategory::Basic {
-                        result.push_str(",\"category\":\"Basic\"");
-                    } else {
-                        result.push_str(&format!(",\"category\":{{\"{}\":{{", string_category));
-                    }
-                }
-
-                if index == 1 {
-                    if selected_category != PartsCategory::Basic {
-                        result.push_str("}}");
-                    }
-                }
-
-                index += 1;
-            }
-        },
-        _ => bail!("HOW"),
-    }
-
-    result.push_str("}");
-
-    let part_props_struct: DBPartProps = serde_json::from_str(&result)?;
-
-    Ok(part_props_struct)
-}
-
-fn part_props_get_properties_as_map(props: DBPartProps) -> anyhow::Result<HashMap<String, String>> {
-    let props_stripped = DBPartProps {
-        category: PartsCategory::default(),
-        ..props
-    };
-
-    let mut base_map = props_stripped.to_string_vec()?;
-    let category_properties_map = props.category.to_string_vec()?;
-    
-    base_map.extend(category_properties_map);
-
-    Ok(base_map)
-}
@@ -0,0 +1,48 @@
+use common::export::ExportFormat;
+use serde::Serialize;
+use yew::prelude::*;
+
+use crate::connection::{download, post_text_from_db};
+
+/// Button for each export format
+pub struct ExportButtons;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct ExportButtonsProps {
+    pub callback: Callback<ExportFormat>,
+}
+
+impl Component for ExportButtons {
+    type Message = ();
+    type Properties = ExportButtonsProps;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let buttons: Html = ExportFormat::all().into_iter().map(|format| {
+            let callback = ctx.props().callback.clone();
+            let onclick = Callback::from(move |_| callback.emit(format));
+
+            html! {
+                <div class={classes!("comparison-button")} {onclick}>
+                    <h5>{format!("Export {}", format)}</h5>
+                </div>
+            }
+        }).collect();
+
+        html! {
+            <div class={classes!("export-buttons")}>
+                {buttons}
+            </div>
+        }
+    }
+}
+
+/// Downloads file rendered by the server
+pub async fn download_export<T: Serialize>(url: String, json: T, name: String, format: ExportFormat) {
+    if let Ok(content) = post_text_from_db(&url, json).await {
+        download(&format!("{}.{}", name, format.extension()), format.mime(), &content);
+    }
+}
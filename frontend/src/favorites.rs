@@ -38,9 +38,18 @@ impl Component for Favorites {
         }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
-            FavoritesMessage::ContextChanged(context) => self.context = context,
+            FavoritesMessage::ContextChanged(context) => {
+                // Unfavorited parts stay listed until the page is opened again, so they can be favorited back
+                let loaded = context.favorites.iter().any(|x| !self.parts.iter().any(|part| part.id == *x));
+                self.context = context;
+
+                if loaded {
+                    let callback = ctx.link().callback(FavoritesMessage::PopulateParts);
+                    spawn_local(get_parts(self.context.clone(), callback));
+                }
+            },
             FavoritesMessage::PopulateParts(mut parts) => {
                 let selected_parts = &self.context.selected_parts;
                 let favorited_parts = &self.context.favorites;
@@ -81,7 +90,7 @@ impl Component for Favorites {
 
         let parts = self.parts.clone();
         if !parts.is_empty() {
-            let html: Vec<Html> = parts.iter().map(|x| x.to_html(None, callback_selected.clone(), callback_favorite.clone())).collect();
+            let html: Vec<Html> = parts.iter().map(|x| x.to_html(&[], callback_selected.clone(), callback_favorite.clone())).collect();
 
             return html! {
                 <div class={classes!("favorites")}>
@@ -1,19 +1,38 @@
 use std::{rc::Rc, collections::HashMap};
 
-use common::PartsCategory;
+use common::{DBPartProps, PartsCategory, export::property_fields, query::{FilterCondition, GetPropertyValuesProps, PropertyFilter, PropertyKind}, traits::{Field, FieldType, PartProperties}};
+use serde_json::Value;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
 use yew::prelude::*;
 
-use crate::{app::AppContext, parts::Part};
+use crate::{app::{AppContext, PCPC_IP}, connection::post_from_db};
+
+/// Keys not worth filtering on, name and model are covered by search bar
+const UNFILTERED_KEYS: [&str; 3] = ["name", "image_url", "model"];
 
 pub struct Filter {
     context: Rc<AppContext>,
+    /// Known values of text properties, shown as options
+    values: HashMap<String, Vec<Value>>,
     _listener: ContextHandle<Rc<AppContext>>,
 }
 
+#[derive(Clone, Copy)]
+pub enum Bound {
+    Min,
+    Max,
+}
+
 pub enum FilterMessage {
     ContextChanged(Rc<AppContext>),
     FilterVisibilityChanged((String, bool)),
     CategorySelectedChanged(String),
+    SetValues(HashMap<String, Vec<Value>>),
+    SetRange(String, Bound, Option<f64>),
+    ToggleValue(String, Value, bool),
+    SetBoolean(String, Option<bool>),
 }
 
 impl Component for Filter {
@@ -32,15 +51,62 @@ impl Component for Filter {
             context.properties_order_callback.emit(ordering.clone());
         }
 
+        Self::fetch_values(ctx, &context.selected_category);
+
         Self {
             context,
+            values: HashMap::new(),
             _listener,
         }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
-            FilterMessage::ContextChanged(context) => self.context = context,
+            FilterMessage::ContextChanged(context) => {
+                if context.selected_category != self.context.selected_category {
+                    self.values.clear();
+                    Self::fetch_values(ctx, &context.selected_category);
+                }
+
+                self.context = context;
+            },
+            FilterMessage::SetValues(values) => self.values = values,
+            FilterMessage::SetRange(key, bound, value) => {
+                let (mut min, mut max) = match self.condition(&key) {
+                    Some(FilterCondition::Range { min, max }) => (*min, *max),
+                    _ => (None, None),
+                };
+                match bound {
+                    Bound::Min => min = value,
+                    Bound::Max => max = value,
+                }
+
+                let condition = match (min, max) {
+                    (None, None) => None,
+                    _ => Some(FilterCondition::Range { min, max }),
+                };
+                self.set_filter(key, condition);
+            },
+            FilterMessage::ToggleValue(key, value, selected) => {
+                let mut values = match self.condition(&key) {
+                    Some(FilterCondition::OneOf(values)) => values.clone(),
+                    _ => Vec::new(),
+                };
+                values.retain(|x| *x != value);
+                if selected {
+                    values.push(value);
+                }
+
+                let condition = match values.is_empty() {
+                    true => None,
+                    false => Some(FilterCondition::OneOf(values)),
+                };
+                self.set_filter(key, condition);
+            },
+            FilterMessage::SetBoolean(key, value) => {
+                let condition = value.map(|value| FilterCondition::Equals(Value::Bool(value)));
+                self.set_filter(key, condition);
+            },
             FilterMessage::FilterVisibilityChanged((name, selected)) => {
                 let context = &self.context;
                 let mut properties_order = context.properties_order.clone();
@@ -78,29 +144,27 @@ impl Component for Filter {
             });
         }
 
-        let mut ordering_properties: Vec<Html> = Vec::new();
         let selected_category = PartsCategory::from_string(&self.context.selected_category);
-        let template_part = Part {
-            category_properties: selected_category,
-            ..Default::default()
-        };
-        let template_properties = template_part.get_properties_as_map().unwrap_or(HashMap::new());
-        let mut properties_order = self.context.properties_order.clone();
-
-        properties_order.retain(|key, _| template_properties.contains_key(key));
-
-        for (name, selected) in properties_order {
+        let ordering_properties: Html = property_fields(selected_category.clone()).into_iter().map(|field| {
+            let selected = self.context.properties_order.get(field.label).copied().unwrap_or(false);
             let callback = ctx.link().callback(move |(name, selected)| FilterMessage::FilterVisibilityChanged((name, selected)));
-            ordering_properties.push(html! {
-                <Property name={name.to_owned()} selected={selected} callback={callback} />
-            });
-        }
+            html! {
+                <Property name={field.label.to_string()} selected={selected} callback={callback} />
+            }
+        }).collect();
+
+        let filters: Html = filtered_fields(selected_category)
+            .into_iter()
+            .map(|field| self.view_property_filter(ctx, field))
+            .collect();
 
         html! {
             <div class={classes!("side-panel")}>
                 <div class={classes!("filter")}>
                     <h2>{"Category"}</h2>
                     {categories_html}
+                    <h2>{"Filters"}</h2>
+                    {filters}
                     <h2>{"Properties"}</h2>
                     {ordering_properties}
                 </div>
@@ -112,25 +176,175 @@ impl Component for Filter {
     }
 }
 
-fn categories() -> Vec<String> {
-    return PartsCategory::get_all_variats();
+impl Filter {
+    fn fetch_values(ctx: &Context<Self>, category: &str) {
+        let callback = ctx.link().callback(FilterMessage::SetValues);
+        let category = category.to_string();
+        // Values of text properties with options are known without asking
+        let keys: Vec<String> = filtered_fields(PartsCategory::from_string(&category))
+            .into_iter()
+            .filter(|field| field.field_type == FieldType::Text && field.options.is_empty())
+            .map(|field| field.key.to_string())
+            .collect();
+
+        spawn_local(async move {
+            let mut values: HashMap<String, Vec<Value>> = HashMap::new();
+            for key in keys {
+                let json = GetPropertyValuesProps {
+                    category: Some(category.clone()),
+                    key: key.clone(),
+                };
+
+                let key_values: Option<Vec<Value>> = post_from_db(&format!("{}/api/part/values", PCPC_IP), json).await.ok();
+                if let Some(key_values) = key_values {
+                    values.insert(key, key_values);
+                }
+            }
+
+            callback.emit(values);
+        });
+    }
+
+    fn condition(&self, key: &str) -> Option<&FilterCondition> {
+        self.context.property_filters.iter()
+            .find(|x| x.key == key)
+            .map(|x| &x.condition)
+    }
+
+    /// Replaces filter of the key, None removes it
+    fn set_filter(&self, key: String, condition: Option<FilterCondition>) {
+        let mut filters = self.context.property_filters.clone();
+        filters.retain(|x| x.key != key);
+        if let Some(condition) = condition {
+            filters.push(PropertyFilter { key, condition });
+        }
+
+        self.context.property_filters_callback.emit(filters);
+    }
+
+    fn view_property_filter(&self, ctx: &Context<Self>, field: &Field) -> Html {
+        let key = field.key.to_string();
+        let kind = PropertyKind::from(field);
+        let condition = self.condition(&key);
+
+        let widget = match &kind {
+            PropertyKind::Number | PropertyKind::Quantity(_) | PropertyKind::Date => {
+                let unit = match &kind {
+                    PropertyKind::Quantity(unit) => format!(" ({})", unit),
+                    PropertyKind::Date => " (e.g. 2022Q4)".to_string(),
+                    _ => String::new(),
+                };
+
+                let bound_input = |bound: Bound, placeholder: &str| {
+                    let key = key.clone();
+                    let kind = kind.clone();
+                    let onchange = ctx.link().callback(move |event: Event| {
+                        let input: HtmlInputElement = event.target().unwrap().dyn_into().unwrap();
+                        FilterMessage::SetRange(key.clone(), bound, kind.parse_number(&input.value()))
+                    });
+
+                    html! {
+                        <input type="text" placeholder={format!("{}{}", placeholder, unit)} {onchange} />
+                    }
+                };
+
+                html! {
+                    <div class={classes!("property-filter-range")}>
+                        {bound_input(Bound::Min, "min")}
+                        {bound_input(Bound::Max, "max")}
+                    </div>
+                }
+            },
+            PropertyKind::Boolean => {
+                let selected = match condition {
+                    Some(FilterCondition::Equals(Value::Bool(value))) => Some(*value),
+                    _ => None,
+                };
+                let onchange = {
+                    let key = key.clone();
+                    ctx.link().callback(move |event: Event| {
+                        let select: HtmlSelectElement = event.target().unwrap().dyn_into().unwrap();
+                        let value = match select.value().as_str() {
+                            "yes" => Some(true),
+                            "no" => Some(false),
+                            _ => None,
+                        };
+                        FilterMessage::SetBoolean(key.clone(), value)
+                    })
+                };
+
+                html! {
+                    <select {onchange}>
+                        <option value="any" selected={selected.is_none()}>{"Any"}</option>
+                        <option value="yes" selected={selected == Some(true)}>{"Yes"}</option>
+                        <option value="no" selected={selected == Some(false)}>{"No"}</option>
+                    </select>
+                }
+            },
+            PropertyKind::Text => {
+                let options: Vec<Value> = field.options.iter().map(|x| Value::from(*x)).collect();
+                let values = match self.values.get(&key) {
+                    _ if !options.is_empty() => &options,
+                    Some(values) if !values.is_empty() => values,
+                    _ => return html! {},
+                };
+                let selected_values = match condition {
+                    Some(FilterCondition::OneOf(values)) => values.clone(),
+                    _ => Vec::new(),
+                };
+
+                values.iter().map(|value| {
+                    let selected = selected_values.contains(value);
+                    let label = match value {
+                        Value::String(string) => string.clone(),
+                        value => value.to_string(),
+                    };
+                    let onchange = {
+                        let key = key.clone();
+                        let value = value.clone();
+                        ctx.link().callback(move |_| FilterMessage::ToggleValue(key.clone(), value.clone(), !selected))
+                    };
+
+                    html! {
+                        <div class={classes!("ordering-property")}>
+                            <input type="checkbox" {onchange} checked={selected} />
+                            <h3>{label}</h3>
+                        </div>
+                    }
+                }).collect()
+            },
+        };
+
+        html! {
+            <div class={classes!("property-filter")} key={format!("{}-{}", self.context.selected_category, key)}>
+                <h3>{field.label}</h3>
+                {widget}
+            </div>
+        }
+    }
 }
 
-pub fn ordering(category: PartsCategory) -> HashMap<String, bool> {
-    let part_template = Part {
-        category_properties: category,
+/// Fields of parts in the category that can be filtered on
+fn filtered_fields(category: PartsCategory) -> Vec<&'static Field> {
+    let template = DBPartProps {
+        category,
         ..Default::default()
     };
-    let part_field_names = part_template.get_properties_as_map();
-    if let Ok(part_field_names) = part_field_names {
-        let mut map: HashMap<String, bool> = HashMap::new();
-        for key in part_field_names.keys() {
-            map.insert(key.to_string(), true);
-        }
-        return map;
-    }
 
-    HashMap::new()
+    template.fields().into_iter()
+        .filter(|field| !UNFILTERED_KEYS.contains(&field.key))
+        .collect()
+}
+
+fn categories() -> Vec<String> {
+    return PartsCategory::get_all_variats();
+}
+
+/// Labels of properties of the category, all of them shown
+pub fn ordering(category: PartsCategory) -> HashMap<String, bool> {
+    property_fields(category).into_iter()
+        .map(|field| (field.label.to_string(), true))
+        .collect()
 }
 
 pub struct Property;
@@ -1,25 +1,77 @@
+use std::rc::Rc;
+
+use common::user::Permission;
+use wasm_bindgen_futures::spawn_local;
 use yew::prelude::*;
 use yew_router::prelude::*;
 
-use crate::app::AppRoute;
+use crate::{app::{AppContext, AppRoute, PCPC_IP}, connection::authorized_post};
+
+pub struct Header {
+    context: Rc<AppContext>,
+    _listener: ContextHandle<Rc<AppContext>>,
+}
 
-pub struct Header;
+pub enum HeaderMessage {
+    ContextChanged(Rc<AppContext>),
+}
 
 impl Component for Header {
-    type Message = ();
+    type Message = HeaderMessage;
     type Properties = ();
 
-    fn create(_ctx: &yew::Context<Self>) -> Self {
-        Self
+    fn create(ctx: &yew::Context<Self>) -> Self {
+        let (context, _listener) = ctx
+            .link()
+            .context::<Rc<AppContext>>(ctx.link().callback(HeaderMessage::ContextChanged))
+            .unwrap();
+
+        Self { context, _listener }
+    }
+
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            HeaderMessage::ContextChanged(context) => self.context = context,
+        }
+
+        true
     }
 
     fn view(&self, _ctx: &Context<Self>) -> Html {
+        let can_create = self.context.user.as_ref().is_some_and(|x| x.role.can(Permission::CreateParts));
+
+        let session = match &self.context.user {
+            Some(user) => {
+                let session_callback = self.context.session_callback.clone();
+                let onclick = Callback::from(move |event: MouseEvent| {
+                    event.prevent_default();
+                    let session_callback = session_callback.clone();
+                    spawn_local(async move {
+                        // Session is forgotten even if backend can't be reached
+                        let _ = authorized_post(&format!("{}/api/auth/logout", PCPC_IP)).send().await;
+                        session_callback.emit(None);
+                    });
+                });
+
+                html! {
+                    <a class={classes!("link")} href="#" onclick={onclick}>{ format!("Log out ({})", user.username) }</a>
+                }
+            },
+            None => html! {
+                <Link<AppRoute> classes={classes!("link")} to={AppRoute::Login}>{ "Log in" }</Link<AppRoute>>
+            },
+        };
+
         html! {
             <div class={classes!("header")}>
                 <Link<AppRoute> classes={classes!("link")} to={AppRoute::Parts}>{ "Parts" }</Link<AppRoute>>
                 <Link<AppRoute> classes={classes!("link")} to={AppRoute::Comparison}>{ "Compare" }</Link<AppRoute>>
+                <Link<AppRoute> classes={classes!("link")} to={AppRoute::Suggest}>{ "Suggest" }</Link<AppRoute>>
                 <Link<AppRoute> classes={classes!("link")} to={AppRoute::Favorites}>{ "Favorites" }</Link<AppRoute>>
-                <Link<AppRoute> classes={classes!("link")} to={AppRoute::Create}>{ "Create" }</Link<AppRoute>>
+                if can_create {
+                    <Link<AppRoute> classes={classes!("link")} to={AppRoute::Create}>{ "Create" }</Link<AppRoute>>
+                }
+                {session}
             </div>
         }
     }
@@ -11,3 +11,10 @@ mod icons;
 mod rating;
 mod side_panel;
 mod favorites;
+mod compatibility;
+mod build;
+mod login;
+mod part_page;
+mod price_chart;
+mod suggest;
+mod export;
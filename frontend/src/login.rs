@@ -0,0 +1,117 @@
+use std::rc::Rc;
+
+use common::{error::ApiError, user::{LoginProps, LoginResponse}};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{Event, InputEvent, HtmlInputElement, SubmitEvent};
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::{app::{AppContext, AppRoute, PCPC_IP}, connection::try_post_from_db};
+
+pub struct Login {
+    context: Rc<AppContext>,
+    _listener: ContextHandle<Rc<AppContext>>,
+    username: String,
+    password: String,
+    /// Why the last attempt failed, shown under the form
+    error: Option<ApiError>,
+}
+
+pub enum LoginMessage {
+    ContextChanged(Rc<AppContext>),
+    SetUsername(String),
+    SetPassword(String),
+    Submit,
+    LoggedIn(Result<LoginResponse, ApiError>),
+}
+
+fn input_value(event: InputEvent) -> String {
+    let event: Event = event.dyn_into().unwrap();
+    let html_element: HtmlInputElement = event.target().unwrap().dyn_into().unwrap();
+    html_element.value()
+}
+
+impl Component for Login {
+    type Message = LoginMessage;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let (context, _listener) = ctx
+            .link()
+            .context::<Rc<AppContext>>(ctx.link().callback(LoginMessage::ContextChanged))
+            .unwrap();
+
+        Self {
+            context,
+            _listener,
+            username: String::new(),
+            password: String::new(),
+            error: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            LoginMessage::ContextChanged(context) => self.context = context,
+            LoginMessage::SetUsername(username) => self.username = username,
+            LoginMessage::SetPassword(password) => self.password = password,
+            LoginMessage::Submit => {
+                let json = LoginProps {
+                    username: self.username.clone(),
+                    password: self.password.clone(),
+                };
+                let callback = ctx.link().callback(LoginMessage::LoggedIn);
+                spawn_local(async move {
+                    let session: Result<LoginResponse, ApiError> = try_post_from_db(&format!("{}/api/auth/login", PCPC_IP), json).await;
+                    callback.emit(session);
+                });
+
+                return false;
+            },
+            LoginMessage::LoggedIn(session) => {
+                self.password.clear();
+                match session {
+                    Ok(session) => {
+                        self.error = None;
+                        self.context.session_callback.emit(Some(session));
+                        if let Some(navigator) = ctx.link().navigator() {
+                            navigator.push(&AppRoute::Parts);
+                        }
+                    },
+                    Err(error) => self.error = Some(error),
+                }
+            },
+        }
+
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let onsubmit = ctx.link().callback(|event: SubmitEvent| {
+            event.prevent_default();
+            LoginMessage::Submit
+        });
+        let username_oninput = ctx.link().callback(|event| LoginMessage::SetUsername(input_value(event)));
+        let password_oninput = ctx.link().callback(|event| LoginMessage::SetPassword(input_value(event)));
+
+        html! {
+            <form class={classes!("login")} onsubmit={onsubmit}>
+                <div class={classes!("property-input")}>
+                    <p>{"Username"}</p>
+                    <input type="text" value={self.username.clone()} oninput={username_oninput} />
+                </div>
+                <div class={classes!("property-input")}>
+                    <p>{"Password"}</p>
+                    <input type="password" value={self.password.clone()} oninput={password_oninput} />
+                </div>
+                if let Some(error) = &self.error {
+                    <p class={classes!("login-error")}>{error.message().to_string()}</p>
+                }
+                <button type="submit" class={classes!("create-part-button")}>
+                    <h2>{"Log in"}</h2>
+                </button>
+            </form>
+        }
+    }
+}
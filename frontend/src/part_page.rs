@@ -0,0 +1,279 @@
+use std::rc::Rc;
+
+use common::{error::FieldError, price::{Currency, GetPriceHistoryProps, Price}, export::{format_price, format_property, property_fields}, review::{MAX_STARS, MIN_STARS, PartReviews, Review, SubmitReviewProps}, traits::PartProperties, user::Permission, validation::validate_review};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+use crate::{app::{AppContext, PCPC_IP}, connection::{delete_from_db, get_from_db, post_from_db}, parts::Part, price_chart::PriceChart, rating::Rating};
+
+/// Page of a single part, its URL can be shared
+pub struct PartPage {
+    part: Option<Part>,
+    /// False until the part was fetched, so "not found" isn't shown while loading
+    loaded: bool,
+    prices: Vec<Price>,
+    /// Newest first
+    reviews: Vec<Review>,
+    /// Review the logged in user is writing
+    review: SubmitReviewProps,
+    /// Whether the logged in user already reviewed the part
+    reviewed: bool,
+    review_errors: Vec<FieldError>,
+    context: Rc<AppContext>,
+    _listener: ContextHandle<Rc<AppContext>>,
+}
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct PartPageProps {
+    pub id: String,
+}
+
+pub enum PartPageMessage {
+    ContextChanged(Rc<AppContext>),
+    SetPart(Option<Box<Part>>),
+    SetPrices(Vec<Price>),
+    SetReviews(PartReviews),
+    SetStars(u8),
+    SetReviewText(String),
+    SubmitReview,
+    DeleteReview,
+    SetReviewErrors(Vec<FieldError>),
+}
+
+impl Component for PartPage {
+    type Message = PartPageMessage;
+    type Properties = PartPageProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let (context, _listener) = ctx
+            .link()
+            .context::<Rc<AppContext>>(ctx.link().callback(PartPageMessage::ContextChanged))
+            .unwrap();
+
+        Self::fetch_part(ctx, context.clone());
+
+        Self {
+            part: None,
+            loaded: false,
+            prices: Vec::new(),
+            reviews: Vec::new(),
+            review: SubmitReviewProps { stars: MAX_STARS, text: String::new() },
+            reviewed: false,
+            review_errors: Vec::new(),
+            context,
+            _listener,
+        }
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
+        if ctx.props() != old_props {
+            self.loaded = false;
+            Self::fetch_part(ctx, self.context.clone());
+        }
+
+        true
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            PartPageMessage::ContextChanged(context) => self.context = context,
+            PartPageMessage::SetPart(part) => {
+                self.part = part.map(|part| *part);
+                self.loaded = true;
+            },
+            PartPageMessage::SetPrices(prices) => self.prices = prices,
+            PartPageMessage::SetReviews(reviews) => {
+                if let Some(part) = &mut self.part {
+                    part.rating = reviews.rating.into();
+                    part.review_count = reviews.review_count;
+                }
+
+                // Review the user already wrote is edited
+                let username = self.context.user.as_ref().map(|x| x.username.clone());
+                let own = reviews.reviews.iter().find(|x| Some(&x.username) == username.as_ref());
+                self.review = match own {
+                    Some(own) => SubmitReviewProps { stars: own.stars, text: own.text.clone() },
+                    None => SubmitReviewProps { stars: MAX_STARS, text: String::new() },
+                };
+                self.reviewed = own.is_some();
+                self.reviews = reviews.reviews;
+                self.review_errors.clear();
+            },
+            PartPageMessage::SetStars(stars) => self.review.stars = stars,
+            PartPageMessage::SetReviewText(text) => self.review.text = text,
+            PartPageMessage::SubmitReview => {
+                if let Err(errors) = validate_review(&self.review) {
+                    self.review_errors = errors;
+                    return true;
+                }
+
+                let url = format!("{}/api/part/{}/reviews", PCPC_IP, ctx.props().id);
+                let json = self.review.clone();
+                let reviews_callback = ctx.link().callback(PartPageMessage::SetReviews);
+                let errors_callback = ctx.link().callback(PartPageMessage::SetReviewErrors);
+                spawn_local(async move {
+                    match post_from_db::<_, PartReviews>(&url, json).await {
+                        Ok(reviews) => reviews_callback.emit(reviews),
+                        Err(error) => errors_callback.emit(error.fields().to_vec()),
+                    }
+                });
+            },
+            PartPageMessage::DeleteReview => {
+                let url = format!("{}/api/part/{}/reviews", PCPC_IP, ctx.props().id);
+                let reviews_callback = ctx.link().callback(PartPageMessage::SetReviews);
+                spawn_local(async move {
+                    if let Ok(reviews) = delete_from_db::<PartReviews>(&url).await {
+                        reviews_callback.emit(reviews);
+                    }
+                });
+            },
+            PartPageMessage::SetReviewErrors(errors) => self.review_errors = errors,
+        }
+
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let part = match &self.part {
+            Some(part) => part,
+            None => {
+                return html! {
+                    <div class={classes!("comparison-empty")}>
+                        <h2>{ if self.loaded { "Part not found" } else { "Loading" } }</h2>
+                    </div>
+                };
+            },
+        };
+
+        let props = part.props();
+        let rows: Html = property_fields(props.category.clone()).into_iter().map(|field| html! {
+            <tr>
+                <th><div><h2>{field.label}</h2></div></th>
+                <td><div>{format_property(props.display(field.key).unwrap_or_default())}</div></td>
+            </tr>
+        }).collect();
+
+        html! {
+            <div class={classes!("part-page")}>
+                <div class={classes!("part-page-header")}>
+                    <div class={classes!("part_img")}>
+                        <img src={part.image_url.clone()} alt="PC Part Image" />
+                    </div>
+                    <div>
+                        <h2>{&part.name}</h2>
+                        <Rating rating={part.rating} />
+                        <h3>{match part.review_count {
+                            1 => "1 review".to_string(),
+                            count => format!("{} reviews", count),
+                        }}</h3>
+                        <h3>{format_price(part.price.as_ref())}</h3>
+                    </div>
+                </div>
+                <table class={classes!("comparison-table")}>
+                    <tr>
+                        <th><div><h2>{"Category"}</h2></div></th>
+                        <td><div>{part.category_properties.to_string()}</div></td>
+                    </tr>
+                    {rows}
+                </table>
+                <h2>{format!("Price history ({})", Currency::default())}</h2>
+                <PriceChart prices={self.prices.clone()} />
+                <h2>{"Reviews"}</h2>
+                {self.view_review_form(ctx)}
+                <div class={classes!("reviews")}>
+                    {for self.reviews.iter().map(|review| html! {
+                        <div class={classes!("review")}>
+                            <div class={classes!("review-header")}>
+                                <Rating rating={review.stars as f32} />
+                                <h4>{&review.username}</h4>
+                                <span>{review.updated_at.split('T').next().unwrap_or_default()}</span>
+                            </div>
+                            <p>{&review.text}</p>
+                        </div>
+                    })}
+                </div>
+            </div>
+        }
+    }
+}
+
+impl PartPage {
+    fn view_review_form(&self, ctx: &Context<Self>) -> Html {
+        let can_review = self.context.user.as_ref().is_some_and(|x| x.role.can(Permission::WriteReviews));
+        if !can_review {
+            return html! {
+                <p class={classes!("review-login")}>{"Log in to review this part"}</p>
+            };
+        }
+
+        let onchange = ctx.link().callback(|event: Event| {
+            let select: HtmlSelectElement = event.target().unwrap().dyn_into().unwrap();
+            PartPageMessage::SetStars(select.value().parse().unwrap_or(MAX_STARS))
+        });
+        let oninput = ctx.link().callback(|event: InputEvent| {
+            let input: HtmlInputElement = event.target().unwrap().dyn_into().unwrap();
+            PartPageMessage::SetReviewText(input.value())
+        });
+        let onclick = ctx.link().callback(|_| PartPageMessage::SubmitReview);
+        let on_delete = ctx.link().callback(|_| PartPageMessage::DeleteReview);
+        let errors: Html = self.review_errors.iter().map(|error| html! {
+            <p class={classes!("field-error")}>{format!("{} {}", error.field, error.message)}</p>
+        }).collect();
+
+        html! {
+            <div class={classes!("review-form")}>
+                <select {onchange}>
+                    {for (MIN_STARS..=MAX_STARS).rev().map(|stars| {
+                        let label = "★".repeat(stars as usize);
+                        html! {
+                            <option value={stars.to_string()} selected={self.review.stars == stars}>{label}</option>
+                        }
+                    })}
+                </select>
+                <input type="text" placeholder="What do you think of it?" value={self.review.text.clone()} {oninput} />
+                <div class={classes!("create-part-button")} {onclick}>
+                    <h2>{"Submit review"}</h2>
+                </div>
+                if self.reviewed {
+                    <div class={classes!("create-part-button")} onclick={on_delete}>
+                        <h2>{"Delete review"}</h2>
+                    </div>
+                }
+                {errors}
+            </div>
+        }
+    }
+
+    fn fetch_part(ctx: &Context<Self>, context: Rc<AppContext>) {
+        let id = ctx.props().id.clone();
+        let part_callback = ctx.link().callback(PartPageMessage::SetPart);
+        let prices_callback = ctx.link().callback(PartPageMessage::SetPrices);
+        let reviews_callback = ctx.link().callback(PartPageMessage::SetReviews);
+
+        spawn_local(async move {
+            let part = context.get_part(id.clone()).await;
+            let found = part.is_some();
+            part_callback.emit(part.map(Box::new));
+            if !found {
+                return;
+            }
+
+            if let Ok(reviews) = get_from_db(&format!("{}/api/part/{}/reviews", PCPC_IP, id)).await {
+                reviews_callback.emit(reviews);
+            }
+
+            let part = match id.parse() {
+                Ok(part) => part,
+                Err(_) => return,
+            };
+            let json = GetPriceHistoryProps {
+                part,
+                currency: Some(Currency::default()),
+            };
+            let prices: Option<Vec<Price>> = post_from_db(&format!("{}/api/price/history", PCPC_IP), json).await.ok();
+            prices_callback.emit(prices.unwrap_or_default());
+        });
+    }
+}
@@ -1,23 +1,30 @@
 use std::{rc::Rc, collections::HashMap};
 
-use common::{DBPart, PartsCategory, traits::PartProperties};
-use serde::Serialize;
+use common::{DBPart, DBPartProps, PartsCategory, price::Price, types::ReleaseDate, export::{ExportFormat, ExportPartsProps, format_price, format_property, labeled_properties}};
 use wasm_bindgen_futures::spawn_local;
 use yew::prelude::*;
+use yew_router::prelude::*;
 
-use crate::{app::{AppContext, get_parts_with_callback}, filter::Filter, icons::SearchBar, rating::Rating};
+use crate::{app::{AppContext, AppRoute, PCPC_IP, get_parts_with_callback}, compatibility::Compatibility, export::{ExportButtons, download_export}, filter::Filter, icons::SearchBar, rating::Rating};
+
+const PAGE_SIZE: u32 = 20;
 
 pub struct Parts {
     parts: Vec<Part>,
+    /// Number of parts matching current category, search term and filters
+    total: u32,
     context: Rc<AppContext>,
     _listener: ContextHandle<Rc<AppContext>>,
 }
 
 pub enum PartsMessage {
     ContextChanged(Rc<AppContext>),
-    AddParts(Vec<Part>),
+    /// Offset of the page, parts and total
+    AddParts((u32, Vec<Part>, u32)),
+    LoadMore,
     SetSelected(String, bool),
     SetFavorite(String, bool),
+    Export(ExportFormat),
 }
 
 impl Component for Parts {
@@ -31,19 +38,25 @@ impl Component for Parts {
             .context::<Rc<AppContext>>(ctx.link().callback(PartsMessage::ContextChanged))
             .unwrap();
 
-        let callback = ctx.link().callback(move |parts| { PartsMessage::AddParts(parts) });
-        spawn_local(get_parts_with_callback(context.clone(), 20, callback));
+        let callback = ctx.link().callback(move |page| { PartsMessage::AddParts(page) });
+        spawn_local(get_parts_with_callback(context.clone(), 0, PAGE_SIZE, callback));
 
         Self { 
             parts: Vec::new(),
+            total: 0,
             context,
             _listener,
         }
     }
 
-    fn update(&mut self, _: &Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
-            PartsMessage::AddParts(mut parts) => {
+            PartsMessage::AddParts((offset, mut parts, total)) => {
+                // Page requested before category or search term changed
+                if offset as usize != self.parts.len() {
+                    return false;
+                }
+
                 let selected_parts = &self.context.selected_parts;
                 let favorited_parts = &self.context.favorites;
                 for part in parts.iter_mut() {
@@ -57,8 +70,30 @@ impl Component for Parts {
                 }
 
                 self.parts.append(&mut parts);
+                self.total = total;
             }
-            PartsMessage::ContextChanged(context) => self.context = context,
+            PartsMessage::LoadMore => {
+                let callback = ctx.link().callback(move |page| { PartsMessage::AddParts(page) });
+                spawn_local(get_parts_with_callback(self.context.clone(), self.parts.len() as u32, PAGE_SIZE, callback));
+            },
+            PartsMessage::ContextChanged(context) => {
+                let query_changed = context.selected_category != self.context.selected_category ||
+                    context.search_term != self.context.search_term ||
+                    context.property_filters != self.context.property_filters;
+                self.context = context;
+
+                // Favorites are loaded from the server after parts can already be shown
+                for part in self.parts.iter_mut() {
+                    part.favorited = self.context.favorites.contains(&part.id);
+                }
+
+                if query_changed {
+                    self.parts.clear();
+                    self.total = 0;
+                    let callback = ctx.link().callback(move |page| { PartsMessage::AddParts(page) });
+                    spawn_local(get_parts_with_callback(self.context.clone(), 0, PAGE_SIZE, callback));
+                }
+            },
             PartsMessage::SetSelected(part_id, selected) => {
                 let part = self.parts.iter_mut().find(|x| x.id == part_id);
                 if let Some(part) = part {
@@ -73,6 +108,16 @@ impl Component for Parts {
                     self.context.favorites_callback.emit((part.id.clone(), favorited));
                 }
             },
+            PartsMessage::Export(format) => {
+                let json = ExportPartsProps {
+                    query: self.context.part_query(0, 0),
+                    columns: self.context.visible_properties(),
+                    format,
+                };
+                spawn_local(download_export(format!("{}/api/part/export", PCPC_IP), json, "parts".to_string(), format));
+
+                return false;
+            },
         }
 
         true
@@ -82,17 +127,11 @@ impl Component for Parts {
         let callback_selected = ctx.link().callback(move |(part_id, selected): (String, bool)| PartsMessage::SetSelected(part_id.clone(), !selected));
         let callback_favorite = ctx.link().callback(move |(part_id, selected): (String, bool)| PartsMessage::SetFavorite(part_id.clone(), !selected));
 
-        let mut ordering_properties = self.context.properties_order.clone();
+        let properties = self.context.visible_properties();
         let parts: Html = self.parts.iter().map(|part| {
-            if self.context.selected_category == part.category_properties.to_string() &&
-                part.name.to_lowercase().contains(&self.context.search_term.to_lowercase()) {
-                ordering_properties.retain(|_, selected| *selected);
-                let ordering_properties: Vec<&String> = ordering_properties.keys().collect();
-                return part.to_html(Some(&ordering_properties), callback_selected.clone(), callback_favorite.clone());
-            }
-            
-            return html! {}
+            part.to_html(&properties, callback_selected.clone(), callback_favorite.clone())
         }).collect();
+        let on_load_more = ctx.link().callback(|_| PartsMessage::LoadMore);
 
         html! {
             <div class={classes!("parts-page")}>
@@ -101,38 +140,41 @@ impl Component for Parts {
                 }
                 <div class={classes!("parts-container")}>
                     <SearchBar />
+                    <ExportButtons callback={ctx.link().callback(PartsMessage::Export)} />
+                    if !self.context.selected_parts.is_empty() {
+                        <Compatibility ids={self.context.selected_parts.clone()} />
+                    }
                     <div class={classes!("parts")}>
                         {parts}
                     </div>
+                    if (self.parts.len() as u32) < self.total {
+                        <div class={classes!("comparison-button", "load-more")} onclick={on_load_more}>
+                            <h5>{format!("Load more ({} of {})", self.parts.len(), self.total)}</h5>
+                        </div>
+                    }
                 </div>
             </div>
         }
     }
 }
 
-#[derive(Serialize, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct Part {
-    #[serde(skip_serializing)]
     pub id: String,
-    #[serde(skip_serializing)]
     pub selected: bool,
-    #[serde(skip_serializing)]
     pub favorited: bool,
-    #[serde(skip_serializing)]
     pub name: String,
-    #[serde(skip_serializing)]
     pub image_url: String,
     pub model: String,
     pub manufactuer: String,
-    pub release_date: String,
-    #[serde(skip_serializing)]
+    pub release_date: Option<ReleaseDate>,
     pub rating: f32,
-    #[serde(skip_serializing)]
+    pub review_count: u32,
     pub category_properties: PartsCategory,
+    /// Current lowest price, None if no retailer has it
+    pub price: Option<Price>,
 }
 
-impl PartProperties for Part {}
-
 impl PartialEq for Part {
     fn eq(&self, other: &Self) -> bool {
         self.id == other.id
@@ -146,7 +188,7 @@ impl Part {
         image_url: T,
         model: T,
         manufactuer: T,
-        release_date: String,
+        release_date: Option<ReleaseDate>,
         rating: f32,
         category: PartsCategory,
     ) -> Self 
@@ -162,20 +204,33 @@ impl Part {
             manufactuer: manufactuer.into(),
             release_date,
             rating,
+            review_count: 0,
             category_properties: category,
+            price: None,
         }
     }
 
-    pub fn get_properties_as_map(&self) -> anyhow::Result<HashMap<String, String>> {
-        let mut base_map = self.to_string_vec()?;
-        let category_properties_map = self.category_properties.to_string_vec()?;
-        
-        base_map.extend(category_properties_map);
+    /// Content of the part, as it's stored
+    pub fn props(&self) -> DBPartProps {
+        DBPartProps {
+            name: self.name.clone(),
+            image_url: self.image_url.clone(),
+            model: self.model.clone(),
+            manufactuer: self.manufactuer.clone(),
+            release_date: self.release_date,
+            rating: self.rating.into(),
+            review_count: self.review_count,
+            category: self.category_properties.clone(),
+        }
+    }
 
-        Ok(base_map)
+    /// Properties keyed by their labels
+    pub fn get_properties_as_map(&self) -> HashMap<String, String> {
+        labeled_properties(&self.props())
     }
 
-    pub fn to_html(&self, order: Option<&Vec<&String>>, callback_selected: Callback<(String, bool)>, callback_favorite: Callback<(String, bool)>) -> Html {
+    /// Card of the part listing given properties, by their labels
+    pub fn to_html(&self, properties: &[String], callback_selected: Callback<(String, bool)>, callback_favorite: Callback<(String, bool)>) -> Html {
         let on_click_selected = {
             let callback = callback_selected.clone();
             let part_id = self.id.clone();
@@ -195,21 +250,15 @@ impl Part {
         };
 
         let map = self.get_properties_as_map();
-        let mut properties: Vec<Html> = Vec::new();
-        if let (Ok(map), Some(order)) = (map, order) {
-            for key in order {
-                let value = map.get(*key);
-                if let Some(value) = value {
-                    let value = format_property(value.to_owned());
-                    properties.push(html! {
-                        <div class={classes!("part_specification")}>
-                            <h4>{format!("{}:", key.to_owned())}</h4>
-                            <h5>{value}</h5>
-                        </div>
-                    });
-                }
-            }
-        }
+        let properties: Vec<Html> = properties.iter().filter_map(|key| {
+            let value = format_property(map.get(key)?.to_owned());
+            Some(html! {
+                <div class={classes!("part_specification")}>
+                    <h4>{format!("{}:", key)}</h4>
+                    <h5>{value}</h5>
+                </div>
+            })
+        }).collect();
 
         html! {
             <div class={classes!("part")}>
@@ -218,10 +267,16 @@ impl Part {
                 </div>
                 <div class={classes!("part_content")}>
                     <div class={classes!("part_header")}>
-                        <h3 class={classes!("part_name")}>{&self.name}</h3>
+                        <Link<AppRoute> classes={classes!("part_name")} to={AppRoute::Part { id: self.id.clone() }}>
+                            <h3>{&self.name}</h3>
+                        </Link<AppRoute>>
                     </div>
                     <div class={classes!("part_info")}>
                         <Rating rating={self.rating} />
+                        <div class={classes!("part_specification")}>
+                            <h4>{"Price:"}</h4>
+                            <h5>{format_price(self.price.as_ref())}</h5>
+                        </div>
                         {properties}
                     </div>
                     <div class={classes!("part_footer")}>
@@ -254,32 +309,31 @@ impl Default for Part {
             image_url: "".into(), 
             model: "".into(), 
             manufactuer: "".into(), 
-            release_date: "".to_string(), 
+            release_date: None,
             rating: 0.0,
+            review_count: 0,
             category_properties: PartsCategory::Basic,
+            price: None,
         }
     }
 }
 
 impl From<DBPart> for Part {
     fn from(value: DBPart) -> Self {
-        Self::new(
-            value.id, 
-            value.name, 
-            value.image_url, 
-            value.model, 
-            value.manufactuer, 
-            value.release_date, 
-            value.rating.into(),
-            value.category,
-        )
+        let review_count = value.review_count;
+        Self {
+            review_count,
+            ..Self::new(
+                value.id, 
+                value.name, 
+                value.image_url, 
+                value.model, 
+                value.manufactuer, 
+                value.release_date, 
+                value.rating.into(),
+                value.category,
+            )
+        }
     }
 }
 
-pub fn format_property(property: String) -> String {
-    match property.as_str() {
-        "true" => "Yes".to_string(),
-        "false" => "No".to_string(),
-        _ => property,
-    }
-}
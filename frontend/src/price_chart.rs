@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+
+use common::price::Price;
+use yew::prelude::*;
+
+const WIDTH: f64 = 640.0;
+const HEIGHT: f64 = 320.0;
+/// Space around the plot for labels
+const PADDING: f64 = 80.0;
+/// Colors of retailers, repeated when there are more of them
+const COLORS: [&str; 5] = ["#FF3352", "#3D7FFF", "#2DB87D", "#F2A516", "#9B59D0"];
+
+pub struct PriceChart;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct PriceChartProps {
+    /// Prices in a single currency, oldest first
+    pub prices: Vec<Price>,
+}
+
+/// Linear map of `value` from `from` range to `to` range. Empty `from` range maps to its middle.
+fn scale(value: f64, from: (f64, f64), to: (f64, f64)) -> f64 {
+    if from.1 <= from.0 {
+        return (to.0 + to.1) / 2.0;
+    }
+
+    to.0 + (value - from.0) / (from.1 - from.0) * (to.1 - to.0)
+}
+
+fn date(price: &Price) -> &str {
+    price.observed_at.get(..10).unwrap_or(&price.observed_at)
+}
+
+impl Component for PriceChart {
+    type Message = ();
+    type Properties = PriceChartProps;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let prices = &ctx.props().prices;
+        let (first, last) = match (prices.first(), prices.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return html! { <p class={classes!("price-chart-empty")}>{"No price history"}</p> },
+        };
+
+        let times: Vec<f64> = prices.iter().map(|x| js_sys::Date::parse(&x.observed_at)).collect();
+        let time_range = (times[0], times[times.len() - 1]);
+        let amount_range = (
+            prices.iter().map(|x| x.amount).min().unwrap_or(0) as f64,
+            prices.iter().map(|x| x.amount).max().unwrap_or(0) as f64,
+        );
+
+        // Higher prices are higher in the chart, SVG y axis points down
+        let point = |index: usize| -> (f64, f64) {
+            (
+                scale(times[index], time_range, (PADDING, WIDTH - PADDING)),
+                scale(prices[index].amount as f64, amount_range, (HEIGHT - PADDING, PADDING)),
+            )
+        };
+
+        let mut retailers: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        for (index, price) in prices.iter().enumerate() {
+            retailers.entry(&price.retailer).or_default().push(index);
+        }
+
+        let mut lines: Vec<Html> = Vec::new();
+        let mut legend: Vec<Html> = Vec::new();
+        for (retailer_index, (retailer, indexes)) in retailers.into_iter().enumerate() {
+            let color = COLORS[retailer_index % COLORS.len()];
+            let points: Vec<String> = indexes.iter()
+                .map(|x| point(*x))
+                .map(|(x, y)| format!("{:.1},{:.1}", x, y))
+                .collect();
+
+            lines.push(html! {
+                <polyline points={points.join(" ")} fill="none" stroke={color} stroke-width="2" />
+            });
+            for index in indexes {
+                let (x, y) = point(index);
+                let price = &prices[index];
+                lines.push(html! {
+                    <circle cx={format!("{:.1}", x)} cy={format!("{:.1}", y)} r="4" fill={color}>
+                        <title>{format!("{}: {} ({})", retailer, price, date(price))}</title>
+                    </circle>
+                });
+            }
+
+            legend.push(html! {
+                <span class={classes!("price-chart-retailer")} style={format!("color: {}", color)}>{retailer}</span>
+            });
+        }
+
+        let lowest = prices.iter().min_by_key(|x| x.amount).unwrap_or(first);
+        let highest = prices.iter().max_by_key(|x| x.amount).unwrap_or(first);
+
+        html! {
+            <div class={classes!("price-chart")}>
+                <svg viewBox={format!("0 0 {} {}", WIDTH, HEIGHT)} width={WIDTH.to_string()} height={HEIGHT.to_string()}>
+                    <line x1={PADDING.to_string()} y1={(HEIGHT - PADDING).to_string()} x2={(WIDTH - PADDING).to_string()} y2={(HEIGHT - PADDING).to_string()} stroke="#bbbbbe" />
+                    <line x1={PADDING.to_string()} y1={PADDING.to_string()} x2={PADDING.to_string()} y2={(HEIGHT - PADDING).to_string()} stroke="#bbbbbe" />
+                    <text x="4" y={PADDING.to_string()} font-size="11">{highest.to_string()}</text>
+                    <text x="4" y={(HEIGHT - PADDING).to_string()} font-size="11">{lowest.to_string()}</text>
+                    <text x={PADDING.to_string()} y={(HEIGHT - PADDING / 2.0).to_string()} font-size="11">{date(first)}</text>
+                    <text x={(WIDTH - PADDING).to_string()} y={(HEIGHT - PADDING / 2.0).to_string()} font-size="11" text-anchor="end">{date(last)}</text>
+                    {lines}
+                </svg>
+                <div class={classes!("price-chart-legend")}>
+                    {legend}
+                </div>
+            </div>
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use common::{build::{DBBuild, DBBuildProps}, optimizer::{BuildSuggestion, SuggestBuildProps, UseCase}, price::{Currency, format_amount}, query::{PropertyFilter, FilterCondition}};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::{app::{AppRoute, PCPC_IP}, connection::post_from_db};
+
+/// Finds builds under a budget with the build optimizer
+pub struct Suggest {
+    /// Whole units of the currency, as entered
+    budget: String,
+    use_case: UseCase,
+    socket: String,
+    min_cores: String,
+    /// None until the first search finishes
+    suggestions: Option<Vec<BuildSuggestion>>,
+}
+
+pub enum SuggestMessage {
+    SetBudget(String),
+    SetUseCase(UseCase),
+    SetSocket(String),
+    SetMinCores(String),
+    Search,
+    SetSuggestions(Vec<BuildSuggestion>),
+    Save(usize),
+}
+
+fn input_value(event: InputEvent) -> String {
+    let input: HtmlInputElement = event.target().unwrap().dyn_into().unwrap();
+    input.value()
+}
+
+impl Suggest {
+    fn props(&self) -> Option<SuggestBuildProps> {
+        let budget: f64 = self.budget.trim().parse().ok()?;
+
+        let mut constraints = Vec::new();
+        if !self.socket.trim().is_empty() {
+            constraints.push(PropertyFilter {
+                key: "socket".into(),
+                condition: FilterCondition::Equals(self.socket.trim().into()),
+            });
+        }
+        if let Ok(min_cores) = self.min_cores.trim().parse::<f64>() {
+            constraints.push(PropertyFilter {
+                key: "cores".into(),
+                condition: FilterCondition::Range { min: Some(min_cores), max: None },
+            });
+        }
+
+        Some(SuggestBuildProps {
+            budget: (budget * 100.0).round() as u64,
+            currency: Currency::default(),
+            use_case: self.use_case,
+            constraints,
+            limit: 3,
+        })
+    }
+}
+
+impl Component for Suggest {
+    type Message = SuggestMessage;
+    type Properties = ();
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            budget: "1500".to_string(),
+            use_case: UseCase::default(),
+            socket: String::new(),
+            min_cores: String::new(),
+            suggestions: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            SuggestMessage::SetBudget(budget) => self.budget = budget,
+            SuggestMessage::SetUseCase(use_case) => self.use_case = use_case,
+            SuggestMessage::SetSocket(socket) => self.socket = socket,
+            SuggestMessage::SetMinCores(min_cores) => self.min_cores = min_cores,
+            SuggestMessage::Search => {
+                if let Some(json) = self.props() {
+                    let callback = ctx.link().callback(SuggestMessage::SetSuggestions);
+                    spawn_local(async move {
+                        let suggestions: Option<Vec<BuildSuggestion>> = post_from_db(&format!("{}/api/build/suggest", PCPC_IP), json).await.ok();
+                        callback.emit(suggestions.unwrap_or_default());
+                    });
+                }
+
+                return false;
+            },
+            SuggestMessage::SetSuggestions(suggestions) => self.suggestions = Some(suggestions),
+            SuggestMessage::Save(index) => {
+                let suggestion = self.suggestions.as_ref().and_then(|x| x.get(index));
+                if let Some(suggestion) = suggestion {
+                    let json = DBBuildProps {
+                        name: format!("{} build", self.use_case),
+                        slots: suggestion.slots.clone(),
+                    };
+                    let navigator = ctx.link().navigator();
+                    spawn_local(async move {
+                        let build: Option<DBBuild> = post_from_db(&format!("{}/api/build/create", PCPC_IP), json).await.ok();
+                        if let (Some(build), Some(navigator)) = (build, navigator) {
+                            navigator.push(&AppRoute::Build { id: build.id });
+                        }
+                    });
+                }
+
+                return false;
+            },
+        }
+
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let use_cases: Html = UseCase::all().into_iter().map(|use_case| html! {
+            <option selected={use_case == self.use_case} value={use_case.to_string()}>{use_case.to_string()}</option>
+        }).collect();
+        let use_case_onchange = ctx.link().callback(|event: Event| {
+            let select: HtmlSelectElement = event.target().unwrap().dyn_into().unwrap();
+            SuggestMessage::SetUseCase(select.value().parse().unwrap_or_default())
+        });
+
+        let suggestions: Html = match &self.suggestions {
+            None => html! {},
+            Some(suggestions) if suggestions.is_empty() => html! {
+                <h2>{"No compatible build fits the budget"}</h2>
+            },
+            Some(suggestions) => suggestions.iter().enumerate().map(|(index, suggestion)| {
+                let rows: Html = suggestion.slots.slots().into_iter().map(|(slot, id)| {
+                    let name = suggestion.parts.iter().find(|x| x.id == id).map(|x| x.name.clone()).unwrap_or(id);
+                    html! {
+                        <tr>
+                            <td><div>{slot}</div></td>
+                            <td><div>{name}</div></td>
+                        </tr>
+                    }
+                }).collect();
+                let notes: Html = suggestion.issues.iter().map(|x| x.to_string())
+                    .chain(suggestion.missing.iter().map(|x| format!("No {} with a price", x)))
+                    .map(|note| html! { <p class={classes!("warning")}>{note}</p> })
+                    .collect();
+                let on_save = ctx.link().callback(move |_| SuggestMessage::Save(index));
+
+                html! {
+                    <div class={classes!("suggestion")}>
+                        <div class={classes!("build-header")}>
+                            <h2>{format_amount(suggestion.total, suggestion.currency)}</h2>
+                            <div class={classes!("comparison-button")} onclick={on_save}>
+                                <h5>{"Save build"}</h5>
+                            </div>
+                        </div>
+                        <table class={classes!("comparison-table")}>
+                            {rows}
+                        </table>
+                        <div class={classes!("compatibility")}>
+                            {notes}
+                        </div>
+                    </div>
+                }
+            }).collect(),
+        };
+
+        let onsubmit = ctx.link().callback(|event: SubmitEvent| {
+            event.prevent_default();
+            SuggestMessage::Search
+        });
+
+        html! {
+            <div class={classes!("build")}>
+                <form class={classes!("suggest-form")} onsubmit={onsubmit}>
+                    <div class={classes!("property-input")}>
+                        <p>{format!("Budget ({})", Currency::default())}</p>
+                        <input type="number" min="0" value={self.budget.clone()} oninput={ctx.link().callback(|x| SuggestMessage::SetBudget(input_value(x)))} />
+                    </div>
+                    <div class={classes!("property-input")}>
+                        <p>{"Use case"}</p>
+                        <select onchange={use_case_onchange}>
+                            {use_cases}
+                        </select>
+                    </div>
+                    <div class={classes!("property-input")}>
+                        <p>{"Socket"}</p>
+                        <input type="text" placeholder="Any" value={self.socket.clone()} oninput={ctx.link().callback(|x| SuggestMessage::SetSocket(input_value(x)))} />
+                    </div>
+                    <div class={classes!("property-input")}>
+                        <p>{"Min cores"}</p>
+                        <input type="number" min="1" placeholder="Any" value={self.min_cores.clone()} oninput={ctx.link().callback(|x| SuggestMessage::SetMinCores(input_value(x)))} />
+                    </div>
+                    <button type="submit" class={classes!("comparison-button")}>
+                        <h5>{"Suggest"}</h5>
+                    </button>
+                </form>
+                {suggestions}
+            </div>
+        }
+    }
+}